@@ -1,10 +1,11 @@
 mod errors;
 pub(crate) mod script_args;
 
-use std::env;
 use std::fmt::Display;
 use std::marker::PhantomData;
 use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::{env, iter};
 
 use anyhow::Result;
 
@@ -12,51 +13,150 @@ use self::errors::ArgError;
 #[cfg(windows)]
 use crate::args::script_args::SWITCH_TO_PROFILE_PREFIX;
 use crate::args::script_args::{
-    NEXT_BOOT_OPERATING_SYSTEM_PREFIX, NEXT_WINDOWS_BOOT_PROFILE_PREFIX,
+    Choices, NEXT_BOOT_OPERATING_SYSTEM_PREFIX, NEXT_WINDOWS_BOOT_PROFILE_PREFIX, ONCE_SUFFIX,
 };
+use crate::configuration::ConfigureOptions;
 use crate::dialog::Mode;
+use crate::host_os::HOST_OS;
 use crate::options_types::{
-    LabeledProfile, OperatingSystem, ProfileId, SerializeToString, Values as _,
+    DeserializeFromString as _, LabeledOperatingSystem, LabeledProfile, OperatingSystem, ProfileId,
+    SerializeToString,
 };
-use crate::script::{Script, SetOrUnset};
+use crate::persist::configs::{Configs, PredefinedScript, ScriptEdit};
+use crate::script::{ExecutionOptions, Script, ScriptHooks, SetOrUnset};
+use crate::text::{Localized, localized};
 
 pub enum ParsedArgs {
     Dialog(Mode),
-    ShowState,
-    Script(Script),
+    ShowState(OutputFormat),
+    Script(Script, ExecutionOptions),
     PredefinedScript(PredefinedScriptParsedArgs),
-    Configure,
+    Restore(RestoreParsedArgs),
+    Configure(ConfigureOptions),
+    Config(ConfigParsedArgs),
+    Grub(GrubParsedArgs),
+    Doctor,
+    Cancel,
     Usage,
     Version,
 }
 
 pub(crate) enum PredefinedScriptParsedArgs {
+    Number(NonZeroUsize, ExecutionOptions),
+    List(OutputFormat),
+    Edit(OperatingSystem, ScriptEdit),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OutputFormat {
+    Text,
+    Json,
+    Toml,
+}
+
+pub(crate) enum RestoreParsedArgs {
     Number(NonZeroUsize),
     List,
 }
 
+pub(crate) enum ConfigParsedArgs {
+    /// Upgrades the configs file to the current layout, or only checks whether it's needed.
+    Migrate { check: bool },
+    /// Reports the problems of the configs file, checking the Grub entries against `grub_cfg`.
+    Validate { grub_cfg: Option<PathBuf> },
+}
+
+pub(crate) enum GrubParsedArgs {
+    /// Edits the GRUB scripts to use the shared `grubenv`, on Linux.
+    InstallHooks(GrubHooksAction),
+}
+
+pub(crate) enum GrubHooksAction {
+    Install {
+        grub_id: String,
+    },
+    /// Only checks whether the edits are applied.
+    Check,
+    /// Restores the scripts from their backups.
+    Undo,
+}
+
+/// Goes before all other arguments, so that the state directory is known before the arguments
+/// that depend on the configs are parsed.
+const STATE_DIR_OPTION: &str = "--state-dir";
+
+/// The value of the leading `--state-dir` option.
+pub fn parse_state_dir() -> Result<Option<PathBuf>, ArgError> {
+    let mut args = env::args().skip(1);
+    match args.next() {
+        Some(arg) if arg == STATE_DIR_OPTION => match args.next() {
+            Some(path) => Ok(Some(path.into())),
+            None => errors::missing_argument_error(PATH.get()),
+        },
+        _ => Ok(None),
+    }
+}
+
 pub fn parse() -> Result<ParsedArgs, ArgError> {
     let mut args = env::args();
     args.next();
 
-    let parsed_args = match args.next() {
+    let mut arg = args.next();
+    if arg.as_deref() == Some(STATE_DIR_OPTION) {
+        // Its value was already taken by parse_state_dir().
+        args.next();
+        arg = args.next();
+    }
+
+    let parsed_args = match arg {
         Some(arg) => match &arg[..] {
             "dialog" => {
                 let mode = parse_dialog_args(&mut args)?;
                 ParsedArgs::Dialog(mode)
             }
-            "show" => ParsedArgs::ShowState,
+            "show" => {
+                let format = parse_output_format(&mut args)?;
+                ParsedArgs::ShowState(format)
+            }
             "script" => {
                 let script_arg = parse_script_args(&mut args)?;
                 ParsedArgs::PredefinedScript(script_arg)
             }
-            "configure" => ParsedArgs::Configure,
+            "restore" => {
+                let restore_arg = parse_restore_args(&mut args)?;
+                ParsedArgs::Restore(restore_arg)
+            }
+            "configure" => {
+                let options = parse_configure_args(&mut args)?;
+                ParsedArgs::Configure(options)
+            }
+            "config" => {
+                let config_arg = parse_config_args(&mut args)?;
+                ParsedArgs::Config(config_arg)
+            }
+            "grub" => {
+                let grub_arg = parse_grub_args(&mut args)?;
+                ParsedArgs::Grub(grub_arg)
+            }
+            "doctor" => ParsedArgs::Doctor,
+            "cancel" => ParsedArgs::Cancel,
             "-h" | "--help" => ParsedArgs::Usage,
             "-v" | "--version" => ParsedArgs::Version,
-            _ => match script_args::parse(&arg, &mut args)? {
-                Some(script) => ParsedArgs::Script(script),
-                None => return errors::unknown_argument_error(&arg),
-            },
+            _ => {
+                let mut options = ExecutionOptions::default();
+                let mut script_args = iter::once(arg)
+                    .chain(args.by_ref())
+                    .filter(|arg| !parse_execution_option(arg, &mut options))
+                    .collect::<Vec<_>>()
+                    .into_iter();
+                let Some(arg) = script_args.next() else {
+                    return errors::missing_argument_error(ACTION.get());
+                };
+                match script_args::parse(&arg, &mut script_args, &script_choices())? {
+                    Some(script) => ParsedArgs::Script(script, options),
+                    None => return errors::unknown_argument_error(&arg),
+                }
+            }
         },
         None => ParsedArgs::Dialog(Mode::Basic),
     };
@@ -65,6 +165,37 @@ pub fn parse() -> Result<ParsedArgs, ArgError> {
     Ok(parsed_args)
 }
 
+/// The values that can be referred to in script arguments. Falls back to the builtin ones if the
+/// configs can't be loaded.
+fn script_choices() -> Choices {
+    match Configs::load() {
+        Ok(configs) => Choices::from_configs(&configs),
+        Err(_) => Choices::builtin(),
+    }
+}
+
+// Placeholders in the usage and in the errors.
+const NUMBER: Localized = Localized::new("NÚMERO", "NUMBER");
+const LABEL: Localized = Localized::new("RÓTULO", "LABEL");
+const FORMAT: Localized = Localized::new("FORMATO", "FORMAT");
+const FORMAT_CHOICES: Localized = Localized::new(
+    "FORMATO pode ser 'text' (padrão), 'json' ou 'toml'.",
+    "FORMAT can be 'text' (default), 'json' or 'toml'.",
+);
+const OPTIONS: Localized = Localized::new("OPÇÕES", "OPTIONS");
+const GRUB_ID: Localized = Localized::new("ID-DO-GRUB", "GRUB-ID");
+const PATH: Localized = Localized::new("CAMINHO", "PATH");
+const OS: Localized = Localized::new("SO", "OS");
+const PROFILE: Localized = Localized::new("PERFIL", "PROFILE");
+#[cfg(windows)]
+const PROFILE_SWITCH: Localized = Localized::new("TROCA-DE-PERFIL", "PROFILE-SWITCH");
+const ACTION: Localized = Localized::new("AÇÃO", "ACTION");
+const SCHEDULE: Localized = Localized::new("AGENDAMENTO", "SCHEDULE");
+const DRY_RUN_USAGE: Localized = Localized::new(
+    "--dry-run - Exibe o que seria alterado e executado, sem alterar nada.",
+    "--dry-run - Shows what would be changed and run, without changing anything.",
+);
+
 fn parse_dialog_args(args: &mut env::Args) -> Result<Mode, ArgError> {
     match args.next() {
         None => Ok(Mode::Basic),
@@ -75,148 +206,638 @@ fn parse_dialog_args(args: &mut env::Args) -> Result<Mode, ArgError> {
 
 fn parse_script_args(args: &mut env::Args) -> Result<PredefinedScriptParsedArgs, ArgError> {
     match args.next() {
-        Some(arg) if arg == "list" => Ok(PredefinedScriptParsedArgs::List),
-        Some(arg) => arg
-            .parse()
-            .map(PredefinedScriptParsedArgs::Number)
-            .map_err(|e| ArgError::new(&format!("Número inválido de script {arg:?}: {e}"), &arg)),
-        None => errors::missing_argument_error("'list' ou NÚMERO"),
+        Some(arg) if arg == "list" => {
+            let format = parse_output_format(args)?;
+            Ok(PredefinedScriptParsedArgs::List(format))
+        }
+        Some(arg) if ["add", "remove", "move", "edit"].contains(&&arg[..]) => {
+            let (os, edit) = parse_script_edit_args(&arg, args)?;
+            Ok(PredefinedScriptParsedArgs::Edit(os, edit))
+        }
+        Some(arg) => {
+            let number = parse_script_number(&arg)?;
+            let mut options = ExecutionOptions::default();
+            for arg in args {
+                if !parse_execution_option(&arg, &mut options) {
+                    return errors::unknown_argument_error(&arg);
+                }
+            }
+            Ok(PredefinedScriptParsedArgs::Number(number, options))
+        }
+        None => errors::missing_argument_error(&localized!(
+            pt_br: "'list' ou {NUMBER}",
+            en: "'list' or {NUMBER}",
+        )),
+    }
+}
+
+fn parse_script_number(arg: &str) -> Result<NonZeroUsize, ArgError> {
+    arg.parse().map_err(|e| {
+        ArgError::new(
+            &localized!(
+                pt_br: "Número inválido de script {arg:?}: {e}",
+                en: "Invalid script number {arg:?}: {e}",
+            ),
+            arg,
+        )
+    })
+}
+
+/// Parses the arguments of `script add|remove|move|edit`, whose script tokens are the same as
+/// the ones of the ad-hoc scripts.
+fn parse_script_edit_args(
+    command: &str,
+    args: &mut env::Args,
+) -> Result<(OperatingSystem, ScriptEdit), ArgError> {
+    let operating_systems = script_choices().operating_systems;
+    let configured_os = |arg: &str| {
+        arg.strip_prefix("--")
+            .and_then(OperatingSystem::deserialize_from_string)
+            .filter(|os| operating_systems.contains(os))
+    };
+
+    let mut os = HOST_OS;
+    let mut label_template = None;
+    let mut positional = Vec::new();
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--label" => match args.next() {
+                Some(label) => label_template = Some(label),
+                None => return errors::missing_argument_error(LABEL.get()),
+            },
+            // Like `--linux`, for any configured operating system.
+            _ if arg.starts_with("--") => match configured_os(&arg) {
+                Some(given_os) => os = given_os,
+                None => return errors::unknown_argument_error(&arg),
+            },
+            _ => positional.push(arg),
+        }
+    }
+    let mut positional = positional.into_iter();
+    let next_number = |positional: &mut std::vec::IntoIter<String>| match positional.next() {
+        Some(arg) => parse_script_number(&arg),
+        None => errors::missing_argument_error(NUMBER.get()),
+    };
+
+    let edit = match command {
+        "add" => {
+            let Some(label_template) = label_template else {
+                return errors::missing_argument_error(&format!("--label {LABEL}"));
+            };
+            let script = parse_script_tokens(&mut positional)?;
+            ScriptEdit::Add(PredefinedScript {
+                script,
+                label_template: Some(label_template),
+                confirm: false,
+                hooks: ScriptHooks::default(),
+            })
+        }
+        "remove" => ScriptEdit::Remove(next_number(&mut positional)?),
+        "move" => ScriptEdit::Move {
+            from: next_number(&mut positional)?,
+            to: next_number(&mut positional)?,
+        },
+        _ => {
+            let number = next_number(&mut positional)?;
+            let script = parse_script_tokens(&mut positional)?;
+            ScriptEdit::Edit {
+                number,
+                label_template,
+                script,
+            }
+        }
+    };
+    if let Some(arg) = positional.next() {
+        return errors::unknown_argument_error(&arg);
+    }
+
+    Ok((os, edit))
+}
+
+/// The script of the remaining tokens, which may be none.
+fn parse_script_tokens(tokens: &mut impl Iterator<Item = String>) -> Result<Script, ArgError> {
+    let Some(arg) = tokens.next() else {
+        return Ok(Script::new());
+    };
+    match script_args::parse(&arg, tokens, &script_choices())? {
+        Some(script) => Ok(script),
+        None => errors::unknown_argument_error(&arg),
+    }
+}
+
+/// Parses the options that apply to both predefined and ad-hoc scripts. Returns whether the
+/// argument is one of them.
+fn parse_execution_option(arg: &str, options: &mut ExecutionOptions) -> bool {
+    match arg {
+        "--dry-run" => options.dry_run = true,
+        "--confirm" => options.confirm = true,
+        "-y" | "--yes" => options.yes = true,
+        _ => return false,
+    }
+    true
+}
+
+fn parse_configure_args(args: &mut env::Args) -> Result<ConfigureOptions, ArgError> {
+    let mut options = ConfigureOptions::default();
+
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "-y" | "--yes" => options.yes = true,
+            "--linux" => options.operating_system = Some(OperatingSystem::LINUX),
+            "--windows" => options.operating_system = Some(OperatingSystem::WINDOWS),
+            "--grub-cfg" => match args.next() {
+                Some(path) => options.grub_cfg = Some(path.into()),
+                None => return errors::missing_argument_error(PATH.get()),
+            },
+            "--fstab" => options.fstab = true,
+            "--profiles" => match args.next() {
+                Some(path) => options.profiles = Some(path.into()),
+                None => return errors::missing_argument_error(PATH.get()),
+            },
+            _ => return errors::unknown_argument_error(&arg),
+        }
+    }
+
+    Ok(options)
+}
+
+fn parse_config_args(args: &mut env::Args) -> Result<ConfigParsedArgs, ArgError> {
+    match args.next().as_deref() {
+        Some("migrate") => match args.next() {
+            None => Ok(ConfigParsedArgs::Migrate { check: false }),
+            Some(arg) if arg == "--check" => Ok(ConfigParsedArgs::Migrate { check: true }),
+            Some(arg) => errors::unknown_argument_error(&arg),
+        },
+        Some("validate") => {
+            let mut grub_cfg = None;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--grub-cfg" => match args.next() {
+                        Some(path) => grub_cfg = Some(path.into()),
+                        None => return errors::missing_argument_error(PATH.get()),
+                    },
+                    _ => return errors::unknown_argument_error(&arg),
+                }
+            }
+            Ok(ConfigParsedArgs::Validate { grub_cfg })
+        }
+        Some(arg) => errors::unknown_argument_error(arg),
+        None => errors::missing_argument_error("'migrate' | 'validate'"),
+    }
+}
+
+fn parse_grub_args(args: &mut env::Args) -> Result<GrubParsedArgs, ArgError> {
+    match args.next().as_deref() {
+        Some("install-hooks") => {
+            let mut action = None;
+            while let Some(arg) = args.next() {
+                let arg_action = match arg.as_str() {
+                    "--grub-id" => match args.next() {
+                        Some(grub_id) => GrubHooksAction::Install { grub_id },
+                        None => return errors::missing_argument_error(GRUB_ID.get()),
+                    },
+                    "--check" => GrubHooksAction::Check,
+                    "--undo" => GrubHooksAction::Undo,
+                    _ => return errors::unknown_argument_error(&arg),
+                };
+                if action.replace(arg_action).is_some() {
+                    return errors::exceeding_argument_error(&arg);
+                }
+            }
+            let Some(action) = action else {
+                return errors::missing_argument_error("'--grub-id' | '--check' | '--undo'");
+            };
+            Ok(GrubParsedArgs::InstallHooks(action))
+        }
+        Some(arg) => errors::unknown_argument_error(arg),
+        None => errors::missing_argument_error("'install-hooks'"),
+    }
+}
+
+fn parse_output_format(args: &mut env::Args) -> Result<OutputFormat, ArgError> {
+    match args.next() {
+        None => Ok(OutputFormat::Text),
+        Some(arg) if arg == "--format" => match args.next().as_deref() {
+            Some("text") => Ok(OutputFormat::Text),
+            Some("json") => Ok(OutputFormat::Json),
+            Some("toml") => Ok(OutputFormat::Toml),
+            Some(format) => Err(ArgError::new(
+                &localized!(pt_br: "Formato inválido", en: "Invalid format"),
+                format,
+            )),
+            None => errors::missing_argument_error(FORMAT.get()),
+        },
+        Some(arg) => errors::unknown_argument_error(&arg),
+    }
+}
+
+fn parse_restore_args(args: &mut env::Args) -> Result<RestoreParsedArgs, ArgError> {
+    match args.next() {
+        Some(arg) if arg == "list" => Ok(RestoreParsedArgs::List),
+        Some(arg) => arg.parse().map(RestoreParsedArgs::Number).map_err(|e| {
+            ArgError::new(
+                &localized!(
+                    pt_br: "Número inválido de backup {arg:?}: {e}",
+                    en: "Invalid backup number {arg:?}: {e}",
+                ),
+                &arg,
+            )
+        }),
+        None => Ok(RestoreParsedArgs::Number(NonZeroUsize::MIN)),
     }
 }
 
 pub(crate) struct Usage {
-    profile_labels: Result<[String; 2]>,
+    configs: Result<Configs>,
 }
 impl Usage {
-    pub(crate) fn new(profile_labels: Result<[String; 2]>) -> Self {
-        Self { profile_labels }
+    pub(crate) fn new(configs: Result<Configs>) -> Self {
+        Self { configs }
     }
 }
 impl Display for Usage {
     #[expect(clippy::too_many_lines)]
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let profiles = ProfileId::values().map(|id| {
-            let display = std::fmt::from_fn(move |f| {
-                if let Ok(labels) = &self.profile_labels {
-                    write!(f, "{}", LabeledProfile::new(id, &labels[id as usize]))
-                } else {
-                    write!(f, "{id} (*)")
-                }
-            });
-            (id, display)
-        });
+        let profiles: Vec<_> = match &self.configs {
+            Ok(configs) => configs
+                .profile
+                .keys()
+                .map(|id| {
+                    let label = LabeledProfile::get(id, configs).unwrap().to_string();
+                    (id.clone(), label)
+                })
+                .collect(),
+            Err(_) => [ProfileId::A, ProfileId::B]
+                .map(|id| {
+                    let label = format!("{id} (*)");
+                    (id, label)
+                })
+                .into(),
+        };
+
+        let operating_systems: Vec<_> = match &self.configs {
+            Ok(configs) => configs
+                .operating_system
+                .keys()
+                .map(|os| {
+                    let label = LabeledOperatingSystem::get(os, configs)
+                        .unwrap()
+                        .to_string();
+                    (os.clone(), label)
+                })
+                .collect(),
+            Err(_) => [OperatingSystem::WINDOWS, OperatingSystem::LINUX]
+                .map(|os| {
+                    let label = os.to_string();
+                    (os, label)
+                })
+                .into(),
+        };
 
         let mut f = UsageWriter::new(f);
 
-        f.write_block("Usos:", |f| {
+        f.write_block(Localized::new("Usos:", "Usage:"), |f| {
             f.write_block("my-reboot [dialog]", |f| {
-                f.write("Exibe diálogo básico.")?;
+                f.write(Localized::new("Exibe diálogo básico.", "Shows the basic dialog."))?;
                 f.write("")
             })?;
 
             f.write_block("my-reboot dialog -x", |f| {
-                f.write("Exibe diálogo avançado.")?;
+                f.write(Localized::new("Exibe diálogo avançado.", "Shows the advanced dialog."))?;
                 f.write("")
             })?;
 
             f.write_block(std::fmt::from_fn(|f| {
-                write!(f, "my-reboot (SO | PERFIL | ")?;
+                write!(f, "my-reboot ({OS} | {PROFILE} | ")?;
                 #[cfg(windows)]
-                write!(f, "TROCA-DE-PERFIL | ")?;
-                write!(f, "AÇÃO)+")
+                write!(f, "{PROFILE_SWITCH} | ")?;
+                write!(f, "{ACTION})+ [--dry-run] [--confirm]")
             }), |f| {
-                f.write_block("SO pode ser:", |f| {
+                f.write_block(localized!(pt_br: "{OS} pode ser:", en: "{OS} can be:"), |f| {
                     let pouf = PrefixedOptionUsageFormatter::<SetOrUnset<OperatingSystem>>::new(NEXT_BOOT_OPERATING_SYSTEM_PREFIX);
-                    for os in OperatingSystem::values() {
+                    for (os, label) in &operating_systems {
                         f.write(pouf.format(
                             PrefixedOptionUsageFormat::OptionalPrefix,
-                            os,
-                            format_args!("Inicia {os} na próxima inicialização do computador."),
+                            os.clone(),
+                            localized!(
+                                pt_br: "Inicia {label} na próxima inicialização do computador.",
+                                en: "Starts {label} on the next boot of the computer.",
+                            ),
                         ))?;
                     }
                     f.write(pouf.format(
                         PrefixedOptionUsageFormat::Full,
                         SetOrUnset::Unset,
-                        "Deixa o Grub decidir o S.O. na próxima inicialização do computador.",
+                        Localized::new(
+                            "Deixa o Grub decidir o S.O. na próxima inicialização do computador.",
+                            "Lets Grub decide the OS on the next boot of the computer.",
+                        ),
+                    ))?;
+                    let once = format!("{OS}:{ONCE_SUFFIX}");
+                    f.write(localized!(
+                        pt_br: "{once} - Inicia o S.O. somente na próxima inicialização do computador, voltando depois ao S.O. anterior.",
+                        en: "{once} - Starts the OS only on the next boot of the computer, going back to the previous OS afterwards.",
                     ))?;
                     f.write("")
                 })?;
 
-                f.write_block("PERFIL pode ser:", |f| {
+                f.write_block(localized!(pt_br: "{PROFILE} pode ser:", en: "{PROFILE} can be:"), |f| {
                     let pouf = PrefixedOptionUsageFormatter::<SetOrUnset<ProfileId>>::new(NEXT_WINDOWS_BOOT_PROFILE_PREFIX);
+                    let windows = OperatingSystem::WINDOWS;
                     for (id, profile) in &profiles {
                         f.write(pouf.format(
                             PrefixedOptionUsageFormat::Full,
-                            *id,
-                            format_args!("Usa o perfil {profile} na próxima inicialização do {}.", OperatingSystem::Windows),
+                            id.clone(),
+                            localized!(
+                                pt_br: "Usa o perfil {profile} na próxima inicialização do {windows}.",
+                                en: "Uses the profile {profile} on the next boot of {windows}.",
+                            ),
                         ))?;
                     }
                     f.write(pouf.format(
                         PrefixedOptionUsageFormat::Full,
                         SetOrUnset::Unset,
-                        format_args!("Deixa o {} decidir o perfil na próxima inicialização.", OperatingSystem::Windows),
+                        localized!(
+                            pt_br: "Deixa o {windows} decidir o perfil na próxima inicialização.",
+                            en: "Lets {windows} decide the profile on the next boot.",
+                        ),
                     ))?;
                     f.write("")
                 })?;
 
                 #[cfg(windows)]
-                f.write_block("TROCA-DE-PERFIL pode ser:", |f| {
+                f.write_block(localized!(pt_br: "{PROFILE_SWITCH} pode ser:", en: "{PROFILE_SWITCH} can be:"), |f| {
                     use crate::script::SwitchToProfile;
                     let pouf = PrefixedOptionUsageFormatter::<SwitchToProfile>::new(SWITCH_TO_PROFILE_PREFIX);
-                    f.write(pouf.format(PrefixedOptionUsageFormat::OptionalValue, SwitchToProfile::Other, "Troca para o outro perfil."))?;
+                    let windows = OperatingSystem::WINDOWS;
+                    f.write(pouf.format(
+                        PrefixedOptionUsageFormat::OptionalValue,
+                        SwitchToProfile::Other,
+                        Localized::new("Troca para o próximo perfil.", "Switches to the next profile."),
+                    ))?;
                     for (id, profile) in &profiles {
                         f.write(pouf.format(
                             PrefixedOptionUsageFormat::Full,
-                            *id,
-                            format_args!("Troca para o perfil {profile}."),
+                            id.clone(),
+                            localized!(
+                                pt_br: "Troca para o perfil {profile}.",
+                                en: "Switches to the profile {profile}.",
+                            ),
                         ))?;
                     }
                     f.write(pouf.format(
                         PrefixedOptionUsageFormat::Full,
                         SwitchToProfile::Saved,
-                        format_args!("Troca para o perfil definido para ser usado na próxima inicialização do {}.", OperatingSystem::Windows),
+                        localized!(
+                            pt_br: "Troca para o perfil definido para ser usado na próxima inicialização do {windows}.",
+                            en: "Switches to the profile set to be used on the next boot of {windows}.",
+                        ),
                     ))?;
                     f.write("")
                 })?;
 
-                f.write_block("AÇÃO pode ser:", |f| {
-                    f.write("reboot - Reinicia o computador.")?;
-                    f.write("shutdown - Desliga o computador.")?;
+                f.write_block(localized!(pt_br: "{ACTION} pode ser:", en: "{ACTION} can be:"), |f| {
+                    f.write(localized!(
+                        pt_br: "reboot - Reinicia o computador.",
+                        en: "reboot - Reboots the computer.",
+                    ))?;
+                    f.write(localized!(
+                        pt_br: "shutdown - Desliga o computador.",
+                        en: "shutdown - Shuts down the computer.",
+                    ))?;
+                    f.write(localized!(
+                        pt_br: "firmware-setup - Reinicia o computador na configuração do firmware (UEFI).",
+                        en: "firmware-setup - Reboots the computer into the firmware (UEFI) setup.",
+                    ))?;
+                    f.write(localized!(
+                        pt_br: "suspend - Suspende o computador.",
+                        en: "suspend - Suspends the computer.",
+                    ))?;
+                    f.write(localized!(
+                        pt_br: "hibernate - Hiberna o computador.",
+                        en: "hibernate - Hibernates the computer.",
+                    ))?;
+                    f.write(localized!(
+                        pt_br: "logout - Encerra a sessão do usuário.",
+                        en: "logout - Logs the user out.",
+                    ))?;
+                    #[cfg(not(windows))]
+                    f.write(localized!(
+                        pt_br: "kexec - Reinicia diretamente no kernel do Linux, sem passar pelo firmware e pelo Grub.",
+                        en: "kexec - Reboots directly into the Linux kernel, skipping the firmware and Grub.",
+                    ))?;
+                    f.write(localized!(
+                        pt_br: "{ACTION}@{SCHEDULE} - Agenda a ação (somente reboot e shutdown) para depois de alguns minutos ou horas (ex.: reboot@+10m, shutdown@+2h) ou para um horário (ex.: shutdown@23:30).",
+                        en: "{ACTION}@{SCHEDULE} - Schedules the action (only reboot and shutdown) to after some minutes or hours (e.g. reboot@+10m, shutdown@+2h) or to a time (e.g. shutdown@23:30).",
+                    ))?;
                     f.write("")
-                })
+                })?;
+
+                f.write(DRY_RUN_USAGE)?;
+                f.write(localized!(
+                    pt_br: "--confirm - Pede confirmação antes da {ACTION}.",
+                    en: "--confirm - Asks for confirmation before the {ACTION}.",
+                ))?;
+                f.write("")
+            })?;
+
+            f.write_block(format_args!("my-reboot show [--format {FORMAT}]"), |f| {
+                f.write(Localized::new(
+                    "Exibe as opções atuais para inicialização.",
+                    "Shows the current boot options.",
+                ))?;
+                f.write(FORMAT_CHOICES)?;
+                f.write("")
+            })?;
+
+            f.write_block(format_args!("my-reboot script {NUMBER} [--dry-run] [--confirm|--yes]"), |f| {
+                f.write(Localized::new(
+                    "Executa o script pré-definido para o S.O. atual.",
+                    "Executes the predefined script for the current OS.",
+                ))?;
+                f.write(DRY_RUN_USAGE)?;
+                f.write(Localized::new(
+                    "--confirm - Pede confirmação, mesmo se o script não for configurado com 'confirm = true'.",
+                    "--confirm - Asks for confirmation, even if the script isn't configured with 'confirm = true'.",
+                ))?;
+                f.write(Localized::new(
+                    "--yes - Não pede confirmação, mesmo se o script for configurado com 'confirm = true'.",
+                    "--yes - Doesn't ask for confirmation, even if the script is configured with 'confirm = true'.",
+                ))?;
+                f.write("")
+            })?;
+
+            f.write_block(format_args!("my-reboot script list [--format {FORMAT}]"), |f| {
+                f.write(Localized::new(
+                    "Lista os scripts pré-definidos para o S.O. atual.",
+                    "Lists the predefined scripts for the current OS.",
+                ))?;
+                f.write(FORMAT_CHOICES)?;
+                f.write("")
+            })?;
+
+            f.write_block(
+                format_args!("my-reboot script add|remove|move|edit [--{OS}] ..."),
+                |f| {
+                    f.write(localized!(
+                        pt_br: "Altera os scripts pré-definidos do S.O. informado (ex.: --linux ou --windows), por padrão o atual:",
+                        en: "Changes the predefined scripts of the given OS (e.g. --linux or --windows), by default the current one:",
+                    ))?;
+                    f.write(localized!(
+                        pt_br: "add --label {LABEL} ... - Adiciona um script, com os mesmos argumentos dos scripts avulsos.",
+                        en: "add --label {LABEL} ... - Adds a script, with the same arguments as the ad-hoc scripts.",
+                    ))?;
+                    f.write(localized!(
+                        pt_br: "remove {NUMBER} - Remove o script.",
+                        en: "remove {NUMBER} - Removes the script.",
+                    ))?;
+                    f.write(localized!(
+                        pt_br: "move {NUMBER} {NUMBER} - Move o script para a posição informada.",
+                        en: "move {NUMBER} {NUMBER} - Moves the script to the given position.",
+                    ))?;
+                    f.write(localized!(
+                        pt_br: "edit {NUMBER} [--label {LABEL}] ... - Altera o rótulo e as opções informadas do script.",
+                        en: "edit {NUMBER} [--label {LABEL}] ... - Changes the label and the given options of the script.",
+                    ))?;
+                    f.write("")
+                },
+            )?;
+
+            f.write_block(format_args!("my-reboot restore [{NUMBER}]"), |f| {
+                f.write(Localized::new(
+                    "Restaura um backup dos arquivos de estado, por padrão o mais recente.",
+                    "Restores a backup of the state files, by default the most recent one.",
+                ))?;
+                f.write(Localized::new(
+                    "Os backups são feitos antes de cada alteração, e o estado atual também é salvo antes de restaurar.",
+                    "The backups are made before each change, and the current state is also saved before restoring.",
+                ))?;
+                f.write("")
             })?;
 
-            f.write_block("my-reboot show", |f| {
-                f.write("Exibe as opções atuais para inicialização.")?;
+            f.write_block("my-reboot restore list", |f| {
+                f.write(Localized::new(
+                    "Lista os backups disponíveis, do mais recente ao mais antigo.",
+                    "Lists the available backups, from the most recent to the oldest.",
+                ))?;
                 f.write("")
             })?;
 
-            f.write_block("my-reboot script NÚMERO", |f| {
-                f.write("Executa o script pré-definido para o S.O. atual.")?;
+            f.write_block("my-reboot cancel", |f| {
+                f.write(Localized::new(
+                    "Cancela a ação agendada.",
+                    "Cancels the scheduled action.",
+                ))?;
                 f.write("")
             })?;
 
-            f.write_block("my-reboot script list", |f| {
-                f.write("Lista os scripts pré-definidos para o S.O. atual.")?;
+            f.write_block(format_args!("my-reboot configure [{OPTIONS}]"), |f| {
+                let linux = OperatingSystem::LINUX;
+                let windows = OperatingSystem::WINDOWS;
+                f.write(localized!(
+                    pt_br: "Configura. Deve ser executado no {linux} e no {windows} ao menos uma vez.",
+                    en: "Configures. Must be run on {linux} and on {windows} at least once.",
+                ))?;
+                f.write_block(localized!(pt_br: "{OPTIONS} podem ser:", en: "{OPTIONS} can be:"), |f| {
+                    f.write(localized!(
+                        pt_br: "-y|--yes - Executa sem perguntas, falhando quando algo precisaria ser perguntado.",
+                        en: "-y|--yes - Runs without questions, failing when something would need to be asked.",
+                    ))?;
+                    f.write(localized!(
+                        pt_br: "--linux|--windows - Garante que a configuração seja a do S.O. informado.",
+                        en: "--linux|--windows - Ensures that the configuration is the one of the given OS.",
+                    ))?;
+                    f.write(localized!(
+                        pt_br: "--grub-cfg {PATH} - Detecta as entradas do Grub a partir do arquivo informado ({linux}).",
+                        en: "--grub-cfg {PATH} - Detects the Grub entries from the given file ({linux}).",
+                    ))?;
+                    f.write(localized!(
+                        pt_br: "--fstab - Mostra a linha do /etc/fstab que monta a partição com o grubenv ({linux}).",
+                        en: "--fstab - Shows the /etc/fstab line that mounts the partition with the grubenv ({linux}).",
+                    ))?;
+                    f.write(localized!(
+                        pt_br: "--profiles {PATH} - Importa os perfis das tabelas [profile.*] do arquivo informado ({windows}).",
+                        en: "--profiles {PATH} - Imports the profiles from the [profile.*] tables of the given file ({windows}).",
+                    ))
+                })?;
+                f.write("")
+            })?;
+
+            f.write_block("my-reboot config migrate [--check]", |f| {
+                f.write(Localized::new(
+                    "Atualiza o arquivo de configurações para a versão atual do formato.",
+                    "Upgrades the configs file to the current layout version.",
+                ))?;
+                f.write(Localized::new(
+                    "--check - Apenas verifica, falhando se uma atualização for necessária.",
+                    "--check - Only checks, failing if an upgrade is needed.",
+                ))?;
+                f.write("")
+            })?;
+
+            f.write_block(format_args!("my-reboot config validate [--grub-cfg {PATH}]"), |f| {
+                f.write(Localized::new(
+                    "Verifica o arquivo de configurações, informando a linha de cada problema.",
+                    "Checks the configs file, reporting the line of each problem.",
+                ))?;
+                let linux = OperatingSystem::LINUX;
+                f.write(localized!(
+                    pt_br: "--grub-cfg {PATH} - Verifica as entradas do Grub no arquivo informado ({linux}).",
+                    en: "--grub-cfg {PATH} - Checks the Grub entries against the given file ({linux}).",
+                ))?;
+                f.write("")
+            })?;
+
+            f.write_block(
+                format_args!("my-reboot grub install-hooks --grub-id {GRUB_ID}|--check|--undo"),
+                |f| {
+                    let linux = OperatingSystem::LINUX;
+                    f.write(localized!(
+                        pt_br: "Altera os scripts do Grub em /etc/grub.d para usarem o grubenv compartilhado, guardando cópias .bkp ({linux}, como root).",
+                        en: "Edits the Grub scripts in /etc/grub.d to use the shared grubenv, keeping .bkp copies ({linux}, as root).",
+                    ))?;
+                    f.write(Localized::new(
+                        "--check - Apenas verifica se as alterações estão aplicadas.",
+                        "--check - Only checks whether the edits are applied.",
+                    ))?;
+                    f.write(Localized::new(
+                        "--undo - Desfaz as alterações.",
+                        "--undo - Undoes the edits.",
+                    ))?;
+                    f.write("")
+                },
+            )?;
+
+            f.write_block("my-reboot doctor", |f| {
+                f.write(Localized::new(
+                    "Verifica toda a configuração, informando o resultado de cada item.",
+                    "Checks the whole setup, reporting the result of each item.",
+                ))?;
                 f.write("")
             })?;
 
-            f.write_block("my-reboot configure", |f| {
-                f.write(format_args!("Configura. Deve ser executado no {} e no {} ao menos uma vez.", OperatingSystem::Linux, OperatingSystem::Windows))?;
+            f.write_block(format_args!("my-reboot --state-dir {PATH} ..."), |f| {
+                f.write(Localized::new(
+                    "Usa o diretório de estado informado, em vez do da variável de ambiente MY_REBOOT_STATE_DIR, do arquivo de configuração do usuário ou da compilação.",
+                    "Uses the given state directory, instead of the one of the MY_REBOOT_STATE_DIR environment variable, of the user config file or of the build.",
+                ))?;
                 f.write("")
             })?;
 
             f.write_block("my-reboot -h|--help", |f| {
-                f.write("Exibe este conteúdo.")?;
+                f.write(Localized::new("Exibe este conteúdo.", "Shows this content."))?;
                 f.write("")
             })?;
 
             f.write_block("my-reboot -v|--version", |f| {
-                f.write("Exibe versão e informações de build.")
+                f.write(Localized::new(
+                    "Exibe versão e informações de build.",
+                    "Shows version and build information.",
+                ))
             })
         })?;
 
-        if let Err(e) = &self.profile_labels {
+        if let Err(e) = &self.configs {
             f.write("")?;
             f.write("")?;
             f.write(format_args!("(*): {e}"))?;
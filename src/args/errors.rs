@@ -2,6 +2,8 @@ use std::env::Args;
 use std::error::Error;
 use std::fmt::Display;
 
+use crate::text::localized;
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct ArgError {
     message: String,
@@ -29,14 +31,23 @@ pub fn check_no_more_arguments(args: &mut Args) -> Result<(), ArgError> {
     }
 }
 
-fn exceeding_argument_error<T>(arg: &str) -> Result<T, ArgError> {
-    Err(ArgError::new("Argumento em excesso", arg))
+pub fn exceeding_argument_error<T>(arg: &str) -> Result<T, ArgError> {
+    Err(ArgError::new(
+        &localized!(pt_br: "Argumento em excesso", en: "Exceeding argument"),
+        arg,
+    ))
 }
 
 pub fn unknown_argument_error<T>(arg: &str) -> Result<T, ArgError> {
-    Err(ArgError::new("Argumento inesperado", arg))
+    Err(ArgError::new(
+        &localized!(pt_br: "Argumento inesperado", en: "Unexpected argument"),
+        arg,
+    ))
 }
 
 pub fn missing_argument_error<T>(name: &str) -> Result<T, ArgError> {
-    Err(ArgError::new("Argumento faltando", name))
+    Err(ArgError::new(
+        &localized!(pt_br: "Argumento faltando", en: "Missing argument"),
+        name,
+    ))
 }
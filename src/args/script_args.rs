@@ -1,29 +1,59 @@
 use serde::Deserialize;
 
 use super::errors::{self, ArgError};
-use crate::options_types::{DeserializeFromString as _, OperatingSystem, RebootAction};
+use crate::options_types::{
+    DeserializeFromString as _, OperatingSystem, ProfileId, RebootAction, Schedule,
+};
+use crate::persist::configs::Configs;
 #[cfg(any(windows, test))]
 use crate::script::SwitchToProfile;
 use crate::script::{Script, SetOrUnset};
-use crate::text;
+use crate::text::{self, Localized, localized};
+
+/// The values that can be referred to in script arguments.
+pub(crate) struct Choices {
+    pub(crate) operating_systems: Vec<OperatingSystem>,
+    pub(crate) profiles: Vec<ProfileId>,
+}
+impl Choices {
+    pub(crate) fn from_configs(configs: &Configs) -> Self {
+        Self {
+            operating_systems: configs.operating_system.keys().cloned().collect(),
+            profiles: configs.profile.keys().cloned().collect(),
+        }
+    }
+
+    /// The values that exist when nothing has been configured yet.
+    pub(crate) fn builtin() -> Self {
+        Self {
+            operating_systems: vec![OperatingSystem::WINDOWS, OperatingSystem::LINUX],
+            profiles: vec![ProfileId::A, ProfileId::B],
+        }
+    }
+}
 
 pub(super) const NEXT_BOOT_OPERATING_SYSTEM_PREFIX: &str = "os";
+/// Appended to the next boot operating system for it to be booted only once.
+pub(super) const ONCE_SUFFIX: &str = "once";
 pub(super) const NEXT_WINDOWS_BOOT_PROFILE_PREFIX: &str = "profile";
+/// Separates the reboot action from its schedule, as in `reboot@+10m`.
+const SCHEDULE_SEPARATOR: char = '@';
 #[cfg(any(windows, test))]
 pub(super) const SWITCH_TO_PROFILE_PREFIX: &str = "switch";
 
 pub fn parse(
     arg: &str,
     args: &mut impl Iterator<Item = String>,
+    choices: &Choices,
 ) -> Result<Option<Script>, ArgError> {
     let mut script = Script::new();
 
-    if !parse_single(arg, &mut script)? {
+    if !parse_single(arg, &mut script, choices)? {
         return Ok(None);
     }
 
     for arg in args {
-        if !parse_single(&arg, &mut script)? {
+        if !parse_single(&arg, &mut script, choices)? {
             return errors::unknown_argument_error(&arg);
         }
     }
@@ -31,17 +61,17 @@ pub fn parse(
     Ok(Some(script))
 }
 
-fn parse_single(arg: &str, script: &mut Script) -> Result<bool, ArgError> {
-    if parse_next_boot_operating_system(arg, script)? {
+fn parse_single(arg: &str, script: &mut Script, choices: &Choices) -> Result<bool, ArgError> {
+    if parse_next_boot_operating_system(arg, script, &choices.operating_systems)? {
         return Ok(true);
     }
 
-    if parse_next_windows_boot_profile(arg, script)? {
+    if parse_next_windows_boot_profile(arg, script, &choices.profiles)? {
         return Ok(true);
     }
 
     #[cfg(any(windows, test))]
-    if parse_switch_to_profile(arg, script)? {
+    if parse_switch_to_profile(arg, script, &choices.profiles)? {
         return Ok(true);
     }
 
@@ -52,21 +82,51 @@ fn parse_single(arg: &str, script: &mut Script) -> Result<bool, ArgError> {
     Ok(false)
 }
 
-fn parse_next_boot_operating_system(arg: &str, script: &mut Script) -> Result<bool, ArgError> {
-    let with_prefix = || SetOrUnset::from_str_with_prefix(arg, NEXT_BOOT_OPERATING_SYSTEM_PREFIX);
-    let without_prefix = || OperatingSystem::deserialize_from_string(arg).map(SetOrUnset::Set);
+fn parse_next_boot_operating_system(
+    arg: &str,
+    script: &mut Script,
+    operating_systems: &[OperatingSystem],
+) -> Result<bool, ArgError> {
+    let (os_arg, once) = match strip_suffix(arg, ONCE_SUFFIX) {
+        Some(os_arg) => (os_arg, true),
+        None => (arg, false),
+    };
 
-    set_option(
-        with_prefix().or_else(without_prefix),
+    let is_known = |os: &OperatingSystem| operating_systems.contains(os);
+    let with_prefix = || {
+        SetOrUnset::from_str_with_prefix(os_arg, NEXT_BOOT_OPERATING_SYSTEM_PREFIX)
+            .filter(|option| option.is_unset_or(is_known))
+    };
+    let without_prefix = || {
+        OperatingSystem::deserialize_from_string(os_arg)
+            .filter(is_known)
+            .map(SetOrUnset::Set)
+    };
+    // Only an operating system can be booted once.
+    let option = with_prefix()
+        .or_else(without_prefix)
+        .filter(|option| !once || matches!(option, SetOrUnset::Set(_)));
+
+    let is_set = set_option(
+        option,
         &mut script.next_boot_operating_system,
         text::operating_system::ON_NEXT_BOOT_DESCRIPTION,
         arg,
-    )
+    )?;
+    if is_set {
+        script.boot_once = once;
+    }
+    Ok(is_set)
 }
 
-fn parse_next_windows_boot_profile(arg: &str, script: &mut Script) -> Result<bool, ArgError> {
+fn parse_next_windows_boot_profile(
+    arg: &str,
+    script: &mut Script,
+    profiles: &[ProfileId],
+) -> Result<bool, ArgError> {
     set_option(
-        SetOrUnset::from_str_with_prefix(arg, NEXT_WINDOWS_BOOT_PROFILE_PREFIX),
+        SetOrUnset::from_str_with_prefix(arg, NEXT_WINDOWS_BOOT_PROFILE_PREFIX)
+            .filter(|option| option.is_unset_or(|id| profiles.contains(id))),
         &mut script.next_windows_boot_profile,
         text::profile::ON_NEXT_WINDOWS_BOOT_DESCRIPTION,
         arg,
@@ -74,10 +134,18 @@ fn parse_next_windows_boot_profile(arg: &str, script: &mut Script) -> Result<boo
 }
 
 #[cfg(any(windows, test))]
-fn parse_switch_to_profile(arg: &str, script: &mut Script) -> Result<bool, ArgError> {
+fn parse_switch_to_profile(
+    arg: &str,
+    script: &mut Script,
+    profiles: &[ProfileId],
+) -> Result<bool, ArgError> {
     let full = || {
         strip_prefix(arg, SWITCH_TO_PROFILE_PREFIX)
             .and_then(SwitchToProfile::deserialize_from_string)
+            .filter(|switch_to| match switch_to {
+                SwitchToProfile::Profile(id) => profiles.contains(id),
+                SwitchToProfile::Other | SwitchToProfile::Saved => true,
+            })
     };
     let prefix_only = || (arg == SWITCH_TO_PROFILE_PREFIX).then_some(SwitchToProfile::Other);
 
@@ -90,18 +158,39 @@ fn parse_switch_to_profile(arg: &str, script: &mut Script) -> Result<bool, ArgEr
 }
 
 fn parse_reboot_action(arg: &str, script: &mut Script) -> Result<bool, ArgError> {
-    set_option(
-        RebootAction::deserialize_from_string(arg),
+    let (action_arg, schedule_arg) = match arg.split_once(SCHEDULE_SEPARATOR) {
+        Some((action_arg, schedule_arg)) => (action_arg, Some(schedule_arg)),
+        None => (arg, None),
+    };
+
+    let Some(action) = RebootAction::deserialize_from_string(action_arg) else {
+        return Ok(false);
+    };
+    let schedule = schedule_arg
+        .map(|schedule_arg| Schedule::try_from(schedule_arg.to_string()))
+        .transpose()
+        .map_err(|message| ArgError::new(&message, arg))?;
+    if schedule.is_some() && !action.can_be_scheduled() {
+        return Err(ArgError::new(
+            &text::reboot_action::cannot_be_scheduled(action),
+            arg,
+        ));
+    }
+
+    let is_set = set_option(
+        Some(action),
         &mut script.reboot_action,
         text::reboot_action::ACTION_DESCRIPTION,
         arg,
-    )
+    )?;
+    script.schedule = schedule;
+    Ok(is_set)
 }
 
 fn set_option<T>(
     option: Option<T>,
     value: &mut Option<T>,
-    descr: &str,
+    descr: Localized,
     arg: &str,
 ) -> Result<bool, ArgError> {
     if let Some(option) = option {
@@ -110,7 +199,10 @@ fn set_option<T>(
             Ok(true)
         } else {
             Err(ArgError::new(
-                &format!("A opção de {descr} não pode ser usada mais de uma vez"),
+                &localized!(
+                    pt_br: "A opção de {descr} não pode ser usada mais de uma vez",
+                    en: "The {descr} option can't be used more than once",
+                ),
                 arg,
             ))
         }
@@ -123,6 +215,13 @@ impl<'de, T: Deserialize<'de>> SetOrUnset<T> {
     pub(crate) fn from_str_with_prefix(s: &str, prefix: &str) -> Option<Self> {
         strip_prefix(s, prefix).and_then(SetOrUnset::deserialize_from_string)
     }
+
+    fn is_unset_or(&self, f: impl FnOnce(&T) -> bool) -> bool {
+        match self {
+            SetOrUnset::Set(value) => f(value),
+            SetOrUnset::Unset => true,
+        }
+    }
 }
 
 fn strip_prefix<'a>(arg: &'a str, prefix: &str) -> Option<&'a str> {
@@ -130,6 +229,10 @@ fn strip_prefix<'a>(arg: &'a str, prefix: &str) -> Option<&'a str> {
         .then(|| &arg[prefix.len() + 1..])
 }
 
+fn strip_suffix<'a>(arg: &'a str, suffix: &str) -> Option<&'a str> {
+    arg.strip_suffix(suffix)?.strip_suffix(':')
+}
+
 #[cfg(test)]
 mod tests {
     use std::iter;
@@ -144,13 +247,13 @@ mod tests {
         let arg = "os:windows";
         let mut args = iter::empty();
 
-        let result = parse(arg, &mut args);
+        let result = parse(arg, &mut args, &Choices::builtin());
 
         let option = result.expect("result should be Ok(_)");
         let script = option.expect("option should be Some(_)");
         assert_eq!(
             script.next_boot_operating_system,
-            Some(Set(OperatingSystem::Windows))
+            Some(Set(OperatingSystem::WINDOWS))
         );
     }
 
@@ -159,7 +262,7 @@ mod tests {
         let arg = "blah";
         let mut args = iter::empty();
 
-        let result = parse(arg, &mut args);
+        let result = parse(arg, &mut args, &Choices::builtin());
 
         let option = result.expect("result should be Ok(_)");
         assert!(option.is_none());
@@ -170,13 +273,13 @@ mod tests {
         let arg = "profile:a";
         let mut args = ["os:windows".to_string()].into_iter();
 
-        let result = parse(arg, &mut args);
+        let result = parse(arg, &mut args, &Choices::builtin());
 
         let option = result.expect("result should be Ok(_)");
         let script = option.expect("option should be Some(_)");
         assert_eq!(
             script.next_boot_operating_system,
-            Some(Set(OperatingSystem::Windows))
+            Some(Set(OperatingSystem::WINDOWS))
         );
         assert_eq!(script.next_windows_boot_profile, Some(Set(ProfileId::A)));
     }
@@ -186,7 +289,7 @@ mod tests {
         let arg = "os:windows";
         let mut args = ["blah".to_string()].into_iter();
 
-        let result = parse(arg, &mut args);
+        let result = parse(arg, &mut args, &Choices::builtin());
 
         assert!(result.is_err());
     }
@@ -195,13 +298,13 @@ mod tests {
     fn test_parse_single_os() {
         let mut script = Script::new();
 
-        let result = parse_single("os:windows", &mut script);
+        let result = parse_single("os:windows", &mut script, &Choices::builtin());
 
         let success = result.expect("result should be Ok(_)");
         assert!(success);
         assert_eq!(
             script.next_boot_operating_system,
-            Some(Set(OperatingSystem::Windows))
+            Some(Set(OperatingSystem::WINDOWS))
         );
     }
 
@@ -209,7 +312,7 @@ mod tests {
     fn test_parse_single_profile() {
         let mut script = Script::new();
 
-        let result = parse_single("profile:a", &mut script);
+        let result = parse_single("profile:a", &mut script, &Choices::builtin());
 
         let success = result.expect("result should be Ok(_)");
         assert!(success);
@@ -220,7 +323,7 @@ mod tests {
     fn test_parse_single_switch_to_profile() {
         let mut script = Script::new();
 
-        let result = parse_single("switch:a", &mut script);
+        let result = parse_single("switch:a", &mut script, &Choices::builtin());
 
         let success = result.expect("result should be Ok(_)");
         assert!(success);
@@ -234,7 +337,7 @@ mod tests {
     fn test_parse_single_reboot_action() {
         let mut script = Script::new();
 
-        let result = parse_single("reboot", &mut script);
+        let result = parse_single("reboot", &mut script, &Choices::builtin());
 
         let success = result.expect("result should be Ok(_)");
         assert!(success);
@@ -245,7 +348,7 @@ mod tests {
     fn test_parse_single_no_script_arg() {
         let mut script = Script::new();
 
-        let result = parse_single("blah", &mut script);
+        let result = parse_single("blah", &mut script, &Choices::builtin());
 
         let success = result.expect("result should be Ok(_)");
         assert!(!success);
@@ -255,7 +358,7 @@ mod tests {
     fn test_parse_single_invalid() {
         let mut script = Script::new();
 
-        let result = parse_single("profile:blah", &mut script);
+        let result = parse_single("profile:blah", &mut script, &Choices::builtin());
 
         assert_eq!(result, Ok(false));
         assert_eq!(script, Script::new());
@@ -264,28 +367,91 @@ mod tests {
     #[test]
     fn test_parse_next_boot_operating_system() {
         let cases = [
-            ("os:windows", Set(OperatingSystem::Windows)),
-            ("windows", Set(OperatingSystem::Windows)),
-            ("os:linux", Set(OperatingSystem::Linux)),
-            ("linux", Set(OperatingSystem::Linux)),
+            ("os:windows", Set(OperatingSystem::WINDOWS)),
+            ("windows", Set(OperatingSystem::WINDOWS)),
+            ("os:linux", Set(OperatingSystem::LINUX)),
+            ("linux", Set(OperatingSystem::LINUX)),
             ("os:unset", Unset),
         ];
 
         for (arg, expected) in cases {
             let mut script = Script::new();
 
-            let result = parse_next_boot_operating_system(arg, &mut script);
+            let result = parse_next_boot_operating_system(
+                arg,
+                &mut script,
+                &Choices::builtin().operating_systems,
+            );
 
             assert_eq!(result, Ok(true), "Result for argument \"{arg}\"");
             assert_eq!(script.next_boot_operating_system, Some(expected));
         }
     }
 
+    #[test]
+    fn test_parse_next_boot_operating_system_once() {
+        for arg in ["os:windows:once", "windows:once"] {
+            let mut script = Script::new();
+
+            let result = parse_next_boot_operating_system(
+                arg,
+                &mut script,
+                &Choices::builtin().operating_systems,
+            );
+
+            assert_eq!(result, Ok(true), "Result for argument \"{arg}\"");
+            assert_eq!(
+                script.next_boot_operating_system,
+                Some(Set(OperatingSystem::WINDOWS))
+            );
+            assert!(script.boot_once);
+        }
+
+        for arg in ["os:unset:once", "os:windows:twice", "windows:"] {
+            let mut script = Script::new();
+
+            let result = parse_next_boot_operating_system(
+                arg,
+                &mut script,
+                &Choices::builtin().operating_systems,
+            );
+
+            assert_eq!(result, Ok(false), "Result for argument \"{arg}\"");
+            assert_eq!(script, Script::new());
+        }
+    }
+
     #[test]
     fn test_parse_next_boot_operating_system_invalid() {
         let mut script = Script::new();
 
-        let result = parse_next_boot_operating_system("os:blah", &mut script);
+        let result = parse_next_boot_operating_system(
+            "os:blah",
+            &mut script,
+            &Choices::builtin().operating_systems,
+        );
+
+        assert_eq!(result, Ok(false));
+        assert_eq!(script.next_boot_operating_system, None);
+    }
+
+    #[test]
+    fn test_parse_next_boot_operating_system_configured() {
+        let fedora = OperatingSystem::try_from("fedora".to_string()).unwrap();
+        let operating_systems = [OperatingSystem::WINDOWS, fedora.clone()];
+
+        for arg in ["os:fedora", "fedora"] {
+            let mut script = Script::new();
+
+            let result = parse_next_boot_operating_system(arg, &mut script, &operating_systems);
+
+            assert_eq!(result, Ok(true), "Result for argument \"{arg}\"");
+            assert_eq!(script.next_boot_operating_system, Some(Set(fedora.clone())));
+        }
+
+        let mut script = Script::new();
+
+        let result = parse_next_boot_operating_system("os:linux", &mut script, &operating_systems);
 
         assert_eq!(result, Ok(false));
         assert_eq!(script.next_boot_operating_system, None);
@@ -296,7 +462,11 @@ mod tests {
         let mut script = Script::new();
         let arg = "blah".to_string();
 
-        let result = parse_next_boot_operating_system(&arg, &mut script);
+        let result = parse_next_boot_operating_system(
+            &arg,
+            &mut script,
+            &Choices::builtin().operating_systems,
+        );
 
         assert_eq!(result, Ok(false), "Result for argument \"{arg}\"");
     }
@@ -306,7 +476,11 @@ mod tests {
         let mut script = Script::new();
         script.next_boot_operating_system = Some(Unset);
 
-        let result = parse_next_boot_operating_system("os:windows", &mut script);
+        let result = parse_next_boot_operating_system(
+            "os:windows",
+            &mut script,
+            &Choices::builtin().operating_systems,
+        );
 
         assert!(result.is_err());
     }
@@ -322,18 +496,50 @@ mod tests {
         for (arg, expected) in cases {
             let mut script = Script::new();
 
-            let result = parse_next_windows_boot_profile(arg, &mut script);
+            let result =
+                parse_next_windows_boot_profile(arg, &mut script, &Choices::builtin().profiles);
 
             assert_eq!(result, Ok(true), "Result for argument \"{arg}\"");
             assert_eq!(script.next_windows_boot_profile, Some(expected));
         }
     }
 
+    #[test]
+    fn test_parse_next_windows_boot_profile_configured() {
+        let tv = ProfileId::try_from("tv".to_string()).unwrap();
+        let profiles = [ProfileId::A, tv.clone()];
+
+        let mut script = Script::new();
+
+        let result = parse_next_windows_boot_profile("profile:tv", &mut script, &profiles);
+
+        assert_eq!(result, Ok(true));
+        assert_eq!(script.next_windows_boot_profile, Some(Set(tv.clone())));
+
+        let mut script = Script::new();
+
+        let result = parse_next_windows_boot_profile("profile:b", &mut script, &profiles);
+
+        assert_eq!(result, Ok(false));
+        assert_eq!(script.next_windows_boot_profile, None);
+
+        let mut script = Script::new();
+
+        let result = parse_switch_to_profile("switch:tv", &mut script, &profiles);
+
+        assert_eq!(result, Ok(true));
+        assert_eq!(script.switch_to_profile, Some(SwitchToProfile::Profile(tv)));
+    }
+
     #[test]
     fn test_parse_next_windows_boot_profile_invalid() {
         let mut script = Script::new();
 
-        let result = parse_next_windows_boot_profile("profile:blah", &mut script);
+        let result = parse_next_windows_boot_profile(
+            "profile:blah",
+            &mut script,
+            &Choices::builtin().profiles,
+        );
 
         assert_eq!(result, Ok(false));
         assert_eq!(script.next_windows_boot_profile, None);
@@ -344,7 +550,8 @@ mod tests {
         let mut script = Script::new();
         let arg = "blah".to_string();
 
-        let result = parse_next_windows_boot_profile(&arg, &mut script);
+        let result =
+            parse_next_windows_boot_profile(&arg, &mut script, &Choices::builtin().profiles);
 
         assert_eq!(result, Ok(false), "Result for argument \"{arg}\"");
     }
@@ -354,7 +561,8 @@ mod tests {
         let mut script = Script::new();
         script.next_windows_boot_profile = Some(Unset);
 
-        let result = parse_next_windows_boot_profile("profile:a", &mut script);
+        let result =
+            parse_next_windows_boot_profile("profile:a", &mut script, &Choices::builtin().profiles);
 
         assert!(result.is_err());
     }
@@ -372,7 +580,7 @@ mod tests {
         for (arg, expected) in cases {
             let mut script = Script::new();
 
-            let result = parse_switch_to_profile(arg, &mut script);
+            let result = parse_switch_to_profile(arg, &mut script, &Choices::builtin().profiles);
 
             assert_eq!(result, Ok(true), "Result for argument \"{arg}\"");
             assert_eq!(script.switch_to_profile, Some(expected));
@@ -383,7 +591,8 @@ mod tests {
     fn test_parse_switch_to_profile_invalid() {
         let mut script = Script::new();
 
-        let result = parse_switch_to_profile("switch:blah", &mut script);
+        let result =
+            parse_switch_to_profile("switch:blah", &mut script, &Choices::builtin().profiles);
 
         assert_eq!(result, Ok(false));
         assert_eq!(script.switch_to_profile, None);
@@ -394,7 +603,7 @@ mod tests {
         let mut script = Script::new();
         let arg = "blah";
 
-        let result = parse_switch_to_profile(arg, &mut script);
+        let result = parse_switch_to_profile(arg, &mut script, &Choices::builtin().profiles);
 
         assert_eq!(result, Ok(false), "Result for argument \"{arg}\"");
     }
@@ -404,7 +613,8 @@ mod tests {
         let mut script = Script::new();
         script.switch_to_profile = Some(SwitchToProfile::Other);
 
-        let result = parse_switch_to_profile("switch:saved", &mut script);
+        let result =
+            parse_switch_to_profile("switch:saved", &mut script, &Choices::builtin().profiles);
 
         assert!(result.is_err());
     }
@@ -414,6 +624,11 @@ mod tests {
         let cases = [
             ("reboot", RebootAction::Reboot),
             ("shutdown", RebootAction::Shutdown),
+            ("firmware-setup", RebootAction::FirmwareSetup),
+            ("suspend", RebootAction::Suspend),
+            ("hibernate", RebootAction::Hibernate),
+            ("logout", RebootAction::Logout),
+            ("kexec", RebootAction::Kexec),
         ];
 
         for (arg, expected) in cases {
@@ -426,6 +641,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_reboot_action_scheduled() {
+        let mut script = Script::new();
+
+        let result = parse_reboot_action("shutdown@+2h", &mut script);
+
+        assert_eq!(result, Ok(true));
+        assert_eq!(script.reboot_action, Some(RebootAction::Shutdown));
+        assert_eq!(script.schedule, Some(Schedule::In(120)));
+
+        let mut script = Script::new();
+
+        let result = parse_reboot_action("reboot@tomorrow", &mut script);
+
+        assert!(result.is_err());
+        assert_eq!(script, Script::new());
+
+        let result = parse_reboot_action("suspend@+10m", &mut script);
+
+        assert!(result.is_err());
+        assert_eq!(script, Script::new());
+    }
+
     #[test]
     fn test_parse_reboot_action_no_switch_arg() {
         let mut script = Script::new();
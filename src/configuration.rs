@@ -1,25 +1,85 @@
-use anyhow::Result;
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
 use rustyline::DefaultEditor;
 
 use crate::host_os::{self, HOST_OS};
-use crate::options_types::{OperatingSystem, ProfileId, Values as _};
+use crate::options_types::OperatingSystem;
 use crate::persist::configs::ConfigsWriter;
+use crate::text::localized;
+
+#[derive(Debug, Default)]
+pub(crate) struct ConfigureOptions {
+    /// Runs unattended: nothing is asked, and it fails whenever it would need to ask something.
+    pub(crate) yes: bool,
+    /// The operating system expected to be configured, which must be the current one.
+    pub(crate) operating_system: Option<OperatingSystem>,
+    /// The GRUB configuration file to detect the GRUB entries from, on Linux.
+    pub(crate) grub_cfg: Option<PathBuf>,
+    /// Shows the `/etc/fstab` line that mounts the shared partition at the state directory, on
+    /// Linux.
+    pub(crate) fstab: bool,
+    /// A file with `[profile.*]` tables to be imported instead of capturing the profiles, on
+    /// Windows.
+    pub(crate) profiles: Option<PathBuf>,
+}
 
-pub(crate) fn configure() -> Result<()> {
-    let mut configurer = Configurer::new()?;
+pub(crate) fn configure(options: ConfigureOptions) -> Result<()> {
+    check_options(&options)?;
+    let mut configurer = Configurer::new(options)?;
     configurer.configure()
 }
 
+fn check_options(options: &ConfigureOptions) -> Result<()> {
+    if let Some(os) = &options.operating_system
+        && *os != HOST_OS
+    {
+        bail!(localized!(
+            pt_br: "A configuração no {os} deve ser executada no {os}",
+            en: "The configuration on {os} must be run on {os}",
+        ));
+    }
+
+    if options.grub_cfg.is_some() {
+        only_on("--grub-cfg", &OperatingSystem::LINUX)?;
+    }
+    if options.fstab {
+        only_on("--fstab", &OperatingSystem::LINUX)?;
+    }
+    if options.profiles.is_some() {
+        only_on("--profiles", &OperatingSystem::WINDOWS)?;
+    }
+
+    Ok(())
+}
+
+/// Fails if the option isn't being used on the operating system it's meant for.
+pub(crate) fn only_on(option: &str, os: &OperatingSystem) -> Result<()> {
+    if *os == HOST_OS {
+        Ok(())
+    } else {
+        bail!(localized!(
+            pt_br: "A opção {option} só pode ser usada no {os}",
+            en: "The option {option} can only be used on {os}",
+        ))
+    }
+}
+
 pub(crate) struct Configurer {
     pub(crate) configs: ConfigsWriter,
     pub(crate) readline: DefaultEditor,
+    pub(crate) options: ConfigureOptions,
 }
 
 impl Configurer {
-    fn new() -> Result<Self> {
+    fn new(options: ConfigureOptions) -> Result<Self> {
         let configs = ConfigsWriter::load()?;
         let readline = DefaultEditor::new()?;
-        Ok(Configurer { configs, readline })
+        Ok(Configurer {
+            configs,
+            readline,
+            options,
+        })
     }
 
     fn configure(&mut self) -> Result<()> {
@@ -29,16 +89,27 @@ impl Configurer {
     }
 
     fn do_configuration(&mut self) -> Result<()> {
-        if self.is_configured(HOST_OS) {
-            println!("A configuração no {HOST_OS} já está feita.");
-            self.readline
-                .readline("Tecle ENTER para refazê-la, ou Ctrl+C para cancelar.")?;
+        if self.is_configured(&HOST_OS) && !self.options.yes {
+            println!(
+                "{}",
+                localized!(
+                    pt_br: "A configuração no {HOST_OS} já está feita.",
+                    en: "The configuration on {HOST_OS} is already done.",
+                )
+            );
+            self.readline.readline(&localized!(
+                pt_br: "Tecle ENTER para refazê-la, ou Ctrl+C para cancelar.",
+                en: "Press ENTER to redo it, or Ctrl+C to cancel.",
+            ))?;
             println!();
         }
 
         host_os::configuration::configure(self)?;
 
-        println!("Salvando configurações...");
+        println!(
+            "{}",
+            localized!(pt_br: "Salvando configurações...", en: "Saving configs...")
+        );
         self.configs.save()?;
         Ok(())
     }
@@ -46,27 +117,39 @@ impl Configurer {
     fn show_configuration_status(&self) {
         println!();
 
-        for os in OperatingSystem::values() {
-            let status = if self.is_configured(os) {
-                "✅ Feita"
+        for os in [OperatingSystem::WINDOWS, OperatingSystem::LINUX] {
+            let status = if self.is_configured(&os) {
+                localized!(pt_br: "✅ Feita", en: "✅ Done")
             } else {
-                "❌ Pendente"
+                localized!(pt_br: "❌ Pendente", en: "❌ Pending")
             };
 
-            println!("Configuração no {os}: {status}");
+            println!(
+                "{}",
+                localized!(
+                    pt_br: "Configuração no {os}: {status}",
+                    en: "Configuration on {os}: {status}",
+                )
+            );
         }
 
         println!();
     }
 
-    fn is_configured(&self, os: OperatingSystem) -> bool {
-        match os {
-            OperatingSystem::Windows => ProfileId::values()
-                .into_iter()
-                .all(|id| self.configs.has_profile_configs(id)),
-            OperatingSystem::Linux => OperatingSystem::values()
-                .into_iter()
-                .all(|os| self.configs.has_grub_entry(os)),
+    /// The configuration on Windows captures the profiles, while the configuration on Linux
+    /// captures the GRUB entries of all operating systems.
+    fn is_configured(&self, os: &OperatingSystem) -> bool {
+        if *os == OperatingSystem::WINDOWS {
+            let profile_ids = self.configs.profile_ids();
+            !profile_ids.is_empty()
+                && profile_ids
+                    .iter()
+                    .all(|id| self.configs.has_profile_configs(id))
+        } else {
+            self.configs
+                .operating_systems()
+                .iter()
+                .all(|os| self.configs.has_grub_entry(os))
         }
     }
 }
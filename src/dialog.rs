@@ -1,15 +1,24 @@
 use std::cell::Cell;
 use std::rc::Rc;
+use std::time::Duration;
 
 use anyhow::Result;
-use iced::{Event, Task, Theme, Vector, event, keyboard, window};
+use chrono::Local;
+use iced::widget::{button, column, container, row, space, text};
+use iced::{Event, Fill, Size, Subscription, Task, Theme, Vector, event, keyboard, time, window};
 
 pub use self::advanced::ScriptOptions;
+use crate::host_os::PendingAction;
+use crate::options_types::{OperatingSystem, ProfileId};
+use crate::text::{Capitalized, Localized};
+
+/// The height of the countdown of the pending action, shown above the dialog contents.
+const PENDING_ACTION_HEIGHT: f32 = 40.0;
 
 macro_rules! mode_toggler {
     ($is_checked:expr) => {
         iced::widget::toggler($is_checked)
-            .label("Modo avançado")
+            .label($crate::text::Localized::new("Modo avançado", "Advanced mode").get())
             .on_toggle(|_| $crate::dialog::Message::SwitchMode)
             .text_size(12)
             .text_alignment(iced::alignment::Horizontal::Right)
@@ -27,25 +36,33 @@ pub enum Mode {
     Advanced,
 }
 
+/// A predefined script, as offered by the basic dialog.
+#[derive(Clone, Debug)]
+pub struct PredefinedScriptChoice {
+    pub label: String,
+    /// Whether choosing it starts a countdown, during which it can be canceled.
+    pub confirm: bool,
+}
+
 #[derive(Debug)]
 pub enum Outcome {
     PredefinedScriptIndex(usize),
     ScriptOptions(ScriptOptions),
+    CancelPendingAction,
 }
 
 pub fn show(
     initial_mode: Mode,
-    predefined_script_labels: Vec<String>,
+    predefined_scripts: Vec<PredefinedScriptChoice>,
     initial_script_options: ScriptOptions,
-    profile_labels: [String; 2],
+    operating_systems: Vec<(OperatingSystem, String)>,
+    profiles: Vec<(ProfileId, String)>,
+    pending_action: Option<PendingAction>,
 ) -> Result<Option<Outcome>> {
-    let label_count = predefined_script_labels.len();
+    let label_count = predefined_scripts.len();
 
     let window_settings = window::Settings {
-        size: match initial_mode {
-            Mode::Basic => basic::window_size(label_count),
-            Mode::Advanced => advanced::window_size(),
-        },
+        size: window_size(initial_mode, label_count, pending_action.is_some()),
         position: window::Position::Centered,
         resizable: false,
         icon: Some(window::icon::from_file_data(
@@ -64,9 +81,12 @@ pub fn show(
                 (
                     Dialog {
                         mode: initial_mode,
-                        predefined_script_labels: predefined_script_labels.clone(),
-                        script_options: initial_script_options,
-                        profile_labels: profile_labels.clone(),
+                        predefined_scripts: predefined_scripts.clone(),
+                        script_options: initial_script_options.clone(),
+                        operating_systems: operating_systems.clone(),
+                        profiles: profiles.clone(),
+                        pending_action,
+                        confirmation: None,
                         outcome: outcome.clone(),
                     },
                     Task::none(),
@@ -78,7 +98,7 @@ pub fn show(
     )
     .title("My Reboot")
     .window(window_settings)
-    .subscription(|_| Dialog::subscription())
+    .subscription(Dialog::subscription)
     .run()?;
 
     Ok(outcome.take())
@@ -89,18 +109,42 @@ enum Message {
     BasicDialog(basic::Message),
     AdvancedDialog(advanced::Message),
     SwitchMode,
+    CancelPendingAction,
+    /// Refreshes the countdown of the pending action.
+    Tick,
     Dismiss,
     Debug,
 }
 
 struct Dialog {
     mode: Mode,
-    predefined_script_labels: Vec<String>,
+    predefined_scripts: Vec<PredefinedScriptChoice>,
     script_options: ScriptOptions,
-    profile_labels: [String; 2],
+    /// The operating systems and their labels.
+    operating_systems: Vec<(OperatingSystem, String)>,
+    /// The profiles and their labels.
+    profiles: Vec<(ProfileId, String)>,
+    pending_action: Option<PendingAction>,
+    /// The chosen predefined script whose countdown is running.
+    confirmation: Option<basic::Confirmation>,
     outcome: Rc<Cell<Option<Outcome>>>,
 }
 
+fn window_size(mode: Mode, label_count: usize, has_pending_action: bool) -> Size {
+    let size = match mode {
+        Mode::Basic => basic::window_size(label_count),
+        Mode::Advanced => advanced::window_size(),
+    };
+    if has_pending_action {
+        Size {
+            height: size.height + PENDING_ACTION_HEIGHT,
+            ..size
+        }
+    } else {
+        size
+    }
+}
+
 impl Dialog {
     fn set_outcome_and_close_window<M: Send + 'static>(
         &mut self,
@@ -117,8 +161,10 @@ impl Dialog {
                 advanced::update(self, message).map(Message::AdvancedDialog)
             }
             Message::SwitchMode => {
-                let basic_size = basic::window_size(self.predefined_script_labels.len());
-                let advanced_size = advanced::window_size();
+                let label_count = self.predefined_scripts.len();
+                let has_pending_action = self.pending_action.is_some();
+                let basic_size = window_size(Mode::Basic, label_count, has_pending_action);
+                let advanced_size = window_size(Mode::Advanced, label_count, has_pending_action);
 
                 let from_mode = self.mode;
                 let (from_size, to_size, to_mode) = match from_mode {
@@ -139,16 +185,28 @@ impl Dialog {
                     Task::batch([resize, move_to])
                 })
             }
+            Message::CancelPendingAction => {
+                self.set_outcome_and_close_window(Some(Outcome::CancelPendingAction))
+            }
+            Message::Tick => match self.confirmation {
+                Some(confirmation) if confirmation.is_over() => self.set_outcome_and_close_window(
+                    Some(Outcome::PredefinedScriptIndex(confirmation.index)),
+                ),
+                _ => Task::none(),
+            },
+            // Escape cancels the countdown, if any, before closing the dialog.
+            Message::Dismiss if self.confirmation.is_some() => {
+                self.confirmation = None;
+                Task::none()
+            }
             Message::Dismiss => self.set_outcome_and_close_window(None),
             Message::Debug => {
                 let mode = self.mode;
-                let requested_size = match mode {
-                    Mode::Basic => {
-                        let label_count = self.predefined_script_labels.len();
-                        basic::window_size(label_count)
-                    }
-                    Mode::Advanced => advanced::window_size(),
-                };
+                let requested_size = window_size(
+                    mode,
+                    self.predefined_scripts.len(),
+                    self.pending_action.is_some(),
+                );
 
                 window::latest().and_then(move |id| {
                     window::position(id).then(move |pos| {
@@ -163,14 +221,39 @@ impl Dialog {
     }
 
     fn view(&self) -> iced::Element<'_, Message, Theme, iced::Renderer> {
-        match self.mode {
+        let content = match self.mode {
             Mode::Basic => basic::view(self),
             Mode::Advanced => advanced::view(self),
+        };
+
+        match &self.pending_action {
+            Some(pending_action) => {
+                let countdown =
+                    crate::text::reboot_action::pending_text(pending_action, Local::now());
+                let pending_action_row = row![
+                    text(format!(
+                        "{}: {countdown}",
+                        Capitalized(crate::text::reboot_action::SCHEDULED_DESCRIPTION)
+                    )),
+                    space().width(Fill),
+                    button(Localized::new("Cancelar", "Cancel").get())
+                        .on_press(Message::CancelPendingAction),
+                ]
+                .align_y(iced::alignment::Vertical::Center);
+                column![
+                    container(pending_action_row)
+                        .height(PENDING_ACTION_HEIGHT)
+                        .padding([4, 12]),
+                    content,
+                ]
+                .into()
+            }
+            None => content,
         }
     }
 
-    fn subscription() -> iced::Subscription<Message> {
-        event::listen_with(|event, _status, _window| match event {
+    fn subscription(&self) -> Subscription<Message> {
+        let keys = event::listen_with(|event, _status, _window| match event {
             Event::Keyboard(keyboard::Event::KeyPressed {
                 key: keyboard::Key::Named(keyboard::key::Named::Escape),
                 ..
@@ -180,6 +263,15 @@ impl Dialog {
                 ..
             }) => Some(Message::Debug),
             _ => None,
-        })
+        });
+
+        if self.pending_action.is_some() || self.confirmation.is_some() {
+            Subscription::batch([
+                keys,
+                time::every(Duration::from_secs(1)).map(|_| Message::Tick),
+            ])
+        } else {
+            keys
+        }
     }
 }
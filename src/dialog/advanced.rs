@@ -1,26 +1,22 @@
 use iced::Length::Fill;
-#[cfg(windows)]
-use iced::widget::checkbox;
-use iced::widget::{button, column, container, radio, row, space, text};
+use iced::widget::{button, checkbox, column, container, radio, row, space, text};
 use iced::{Padding, Size, Task, Theme, font};
 
 use super::{Dialog, Outcome};
 use crate::options_types::{OperatingSystem, ProfileId, RebootAction, Values as _};
-use crate::text::Capitalized;
+use crate::text::{Capitalized, Localized};
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct ScriptOptions {
     pub next_boot_operating_system: Option<OperatingSystem>,
+    pub(crate) boot_once: bool,
     pub(crate) next_windows_boot_profile: Option<ProfileId>,
     #[cfg(windows)]
     pub(crate) switch_profile: bool,
     pub reboot_action: Option<RebootAction>,
 }
 
-#[cfg(windows)]
-const WINDOW_HEIGHT: f32 = 428.0;
-#[cfg(not(windows))]
-const WINDOW_HEIGHT: f32 = 406.0;
+const WINDOW_HEIGHT: f32 = 538.0;
 
 pub(crate) fn window_size() -> Size {
     Size {
@@ -31,8 +27,11 @@ pub(crate) fn window_size() -> Size {
 
 #[derive(Clone, Copy, Debug)]
 pub(crate) enum Message {
-    NextBootOperatingSystem(Option<OperatingSystem>),
-    NextWindowsBootProfile(Option<ProfileId>),
+    /// Holds the index of the operating system in [`Dialog::operating_systems`].
+    NextBootOperatingSystem(Option<usize>),
+    BootOnce(bool),
+    /// Holds the index of the profile in [`Dialog::profiles`].
+    NextWindowsBootProfile(Option<usize>),
     #[cfg(windows)]
     SwitchProfile(bool),
     Action(Option<RebootAction>),
@@ -41,12 +40,18 @@ pub(crate) enum Message {
 
 pub(crate) fn update(dialog: &mut Dialog, message: Message) -> Task<Message> {
     match message {
-        Message::NextBootOperatingSystem(os) => {
-            dialog.script_options.next_boot_operating_system = os;
+        Message::NextBootOperatingSystem(index) => {
+            dialog.script_options.next_boot_operating_system =
+                index.map(|index| dialog.operating_systems[index].0.clone());
+            Task::none()
+        }
+        Message::BootOnce(once) => {
+            dialog.script_options.boot_once = once;
             Task::none()
         }
-        Message::NextWindowsBootProfile(profile_id) => {
-            dialog.script_options.next_windows_boot_profile = profile_id;
+        Message::NextWindowsBootProfile(index) => {
+            dialog.script_options.next_windows_boot_profile =
+                index.map(|index| dialog.profiles[index].0.clone());
             Task::none()
         }
         #[cfg(windows)]
@@ -59,7 +64,8 @@ pub(crate) fn update(dialog: &mut Dialog, message: Message) -> Task<Message> {
             Task::none()
         }
         Message::Confirm => {
-            dialog.set_outcome_and_close_window(Some(Outcome::ScriptOptions(dialog.script_options)))
+            let script_options = dialog.script_options.clone();
+            dialog.set_outcome_and_close_window(Some(Outcome::ScriptOptions(script_options)))
         }
     }
 }
@@ -83,8 +89,8 @@ macro_rules! add_to_option_group {
 }
 
 macro_rules! option_radios {
-    ($option_type:ident; $current_value:expr, $label:expr, $none_label:expr, $message:expr $(,)?) => {
-        $option_type::values()
+    ($values:expr; $current_value:expr, $label:expr, $none_label:expr, $message:expr $(,)?) => {
+        $values
             .into_iter()
             .map(Some)
             .chain(std::iter::once(None))
@@ -114,15 +120,29 @@ pub(crate) fn view(dialog: &Dialog) -> iced::Element<'_, super::Message, Theme,
         let widgets = create_option_group!(
             Capitalized(crate::text::operating_system::ON_NEXT_BOOT_DESCRIPTION).to_string()
         );
-        add_to_option_group!(
+        let widgets = add_to_option_group!(
             widgets,
             option_radios!(
-                OperatingSystem;
-                dialog.script_options.next_boot_operating_system,
-                |op: OperatingSystem| op.to_string(),
+                0..dialog.operating_systems.len();
+                dialog.script_options.next_boot_operating_system.as_ref().and_then(|current| {
+                    dialog.operating_systems.iter().position(|(os, _)| os == current)
+                }),
+                |index: usize| dialog.operating_systems[index].1.clone(),
                 crate::text::operating_system::UNDEFINED,
                 |os| super::Message::AdvancedDialog(Message::NextBootOperatingSystem(os)),
             )
+        );
+        add_to_option_group!(
+            widgets,
+            [checkbox(dialog.script_options.boot_once)
+                .label(crate::text::operating_system::ONCE.get())
+                .on_toggle_maybe(
+                    dialog
+                        .script_options
+                        .next_boot_operating_system
+                        .is_some()
+                        .then_some(|once| super::Message::AdvancedDialog(Message::BootOnce(once)))
+                )]
         )
     };
 
@@ -133,14 +153,11 @@ pub(crate) fn view(dialog: &Dialog) -> iced::Element<'_, super::Message, Theme,
         add_to_option_group!(
             widgets,
             option_radios!(
-                ProfileId;
-                dialog.script_options.next_windows_boot_profile,
-                |op: ProfileId| {
-                    match op {
-                        ProfileId::A => dialog.profile_labels[0].clone(),
-                        ProfileId::B => dialog.profile_labels[1].clone(),
-                    }
-                },
+                0..dialog.profiles.len();
+                dialog.script_options.next_windows_boot_profile.as_ref().and_then(|current| {
+                    dialog.profiles.iter().position(|(id, _)| id == current)
+                }),
+                |index: usize| dialog.profiles[index].1.clone(),
                 crate::text::profile::UNDEFINED,
                 |profile_id| super::Message::AdvancedDialog(Message::NextWindowsBootProfile(profile_id)),
             )
@@ -148,12 +165,14 @@ pub(crate) fn view(dialog: &Dialog) -> iced::Element<'_, super::Message, Theme,
     };
 
     let reboot_action_widgets = {
-        let widgets = create_option_group!("Ação");
+        let widgets = create_option_group!(
+            Capitalized(crate::text::reboot_action::ACTION_DESCRIPTION).to_string()
+        );
         #[cfg(windows)]
         let widgets = add_to_option_group!(
             widgets,
             [checkbox(dialog.script_options.switch_profile)
-                .label("trocar de perfil antes")
+                .label(Localized::new("trocar de perfil antes", "switch profile before").get())
                 .on_toggle(
                     |switch| super::Message::AdvancedDialog(Message::SwitchProfile(switch))
                 )]
@@ -161,10 +180,10 @@ pub(crate) fn view(dialog: &Dialog) -> iced::Element<'_, super::Message, Theme,
         add_to_option_group!(
             widgets,
             option_radios!(
-                RebootAction;
+                RebootAction::values().iter().copied();
                 dialog.script_options.reboot_action,
                 |op: RebootAction| op.to_string(),
-                "continuar usando",
+                Localized::new("continuar usando", "keep using"),
                 |action| super::Message::AdvancedDialog(Message::Action(action)),
             )
         )
@@ -1,7 +1,11 @@
-use iced::widget::{button, column, row, space};
+use std::time::{Duration, Instant};
+
+use chrono::TimeDelta;
+use iced::widget::{button, column, row, space, text};
 use iced::{Fill, Size, Task, Theme};
 
 use super::{Dialog, Outcome};
+use crate::text::{Localized, localized};
 
 const WINDOW_WIDTH: f32 = 500.0;
 const PADDING: f32 = 12.0;
@@ -10,6 +14,8 @@ const BUTTON_HEIGHT: f32 = 32.0;
 const ADDITIONAL_WINDOW_HEIGHT: f32 = 60.0;
 #[cfg(not(windows))]
 const ADDITIONAL_WINDOW_HEIGHT: f32 = 94.0;
+/// How long a predefined script to be confirmed waits before being executed.
+const CONFIRMATION_COUNTDOWN: Duration = Duration::from_secs(10);
 
 pub(crate) fn window_size(label_count: usize) -> Size {
     Size {
@@ -19,34 +25,96 @@ pub(crate) fn window_size(label_count: usize) -> Size {
     }
 }
 
+/// A chosen predefined script, executed when the countdown is over unless it's canceled.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Confirmation {
+    pub(crate) index: usize,
+    deadline: Instant,
+}
+impl Confirmation {
+    pub(crate) fn is_over(&self) -> bool {
+        Instant::now() >= self.deadline
+    }
+
+    /// The remaining time, rounded up to whole seconds.
+    fn remaining(&self) -> TimeDelta {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        let seconds = remaining.as_millis().div_ceil(1000);
+        TimeDelta::seconds(i64::try_from(seconds).unwrap_or_default())
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub(crate) enum Message {
     Confirm(usize),
+    ExecuteNow,
+    CancelConfirmation,
 }
 
 pub(crate) fn update(dialog: &mut Dialog, message: Message) -> Task<Message> {
     match message {
+        Message::Confirm(index) if dialog.predefined_scripts[index].confirm => {
+            dialog.confirmation = Some(Confirmation {
+                index,
+                deadline: Instant::now() + CONFIRMATION_COUNTDOWN,
+            });
+            Task::none()
+        }
         Message::Confirm(index) => {
             dialog.set_outcome_and_close_window(Some(Outcome::PredefinedScriptIndex(index)))
         }
+        Message::ExecuteNow => match dialog.confirmation.take() {
+            Some(confirmation) => dialog.set_outcome_and_close_window(Some(
+                Outcome::PredefinedScriptIndex(confirmation.index),
+            )),
+            None => Task::none(),
+        },
+        Message::CancelConfirmation => {
+            dialog.confirmation = None;
+            Task::none()
+        }
     }
 }
 
 pub(crate) fn view(dialog: &Dialog) -> iced::Element<'_, super::Message, Theme, iced::Renderer> {
-    let buttons = dialog
-        .predefined_script_labels
-        .iter()
-        .enumerate()
-        .fold(column![], |column, (index, label)| {
-            let button = button(label.as_str())
-                .on_press(super::Message::BasicDialog(Message::Confirm(index)))
-                .height(BUTTON_HEIGHT)
-                .width(WINDOW_WIDTH - 2.0 * PADDING);
-            column.push(button)
-        })
-        .spacing(1);
-
-    column![buttons, row![space().width(Fill), mode_toggler!(false),],]
+    let content: iced::Element<'_, super::Message, Theme, iced::Renderer> = match &dialog
+        .confirmation
+    {
+        Some(confirmation) => {
+            let label = &dialog.predefined_scripts[confirmation.index].label;
+            let remaining = crate::text::reboot_action::remaining_text(confirmation.remaining());
+            row![
+                text(localized!(
+                    pt_br: "{label} em {remaining}",
+                    en: "{label} in {remaining}",
+                ))
+                .width(Fill),
+                button(Localized::new("Agora", "Now").get())
+                    .on_press(super::Message::BasicDialog(Message::ExecuteNow)),
+                button(Localized::new("Cancelar", "Cancel").get())
+                    .on_press(super::Message::BasicDialog(Message::CancelConfirmation)),
+            ]
+            .spacing(8)
+            .height(BUTTON_HEIGHT)
+            .align_y(iced::alignment::Vertical::Center)
+            .into()
+        }
+        None => dialog
+            .predefined_scripts
+            .iter()
+            .enumerate()
+            .fold(column![], |column, (index, predefined_script)| {
+                let button = button(predefined_script.label.as_str())
+                    .on_press(super::Message::BasicDialog(Message::Confirm(index)))
+                    .height(BUTTON_HEIGHT)
+                    .width(WINDOW_WIDTH - 2.0 * PADDING);
+                column.push(button)
+            })
+            .spacing(1)
+            .into(),
+    };
+
+    column![content, row![space().width(Fill), mode_toggler!(false),],]
         .spacing(16)
         .padding(PADDING)
         .into()
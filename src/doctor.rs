@@ -0,0 +1,72 @@
+use std::fmt::Display;
+
+use anyhow::{Result, bail};
+
+use crate::host_os;
+use crate::persist::configs::Configs;
+use crate::text::localized;
+
+/// An item of the setup checked by `my-reboot doctor`.
+pub(crate) struct Check {
+    description: String,
+    /// Why the check failed, if it did.
+    failure: Option<String>,
+}
+impl Check {
+    pub(crate) fn new(description: String, outcome: Result<()>) -> Self {
+        Check {
+            description,
+            failure: outcome.err().map(|e| format!("{e:#}")),
+        }
+    }
+
+    pub(crate) fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+impl Display for Check {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.failure {
+            None => write!(f, "✅ {}", self.description),
+            Some(failure) => write!(f, "❌ {}: {failure}", self.description),
+        }
+    }
+}
+
+/// Checks the whole setup, showing whether each item passed. Fails if any of them didn't.
+pub(crate) fn doctor() -> Result<()> {
+    let (configs, outcome) = match Configs::load() {
+        Ok(configs) => (Some(configs), Ok(())),
+        Err(e) => (None, Err(e)),
+    };
+    let mut checks = vec![Check::new(
+        localized!(
+            pt_br: "O arquivo de configurações é carregado",
+            en: "The configs file loads",
+        ),
+        outcome,
+    )];
+    checks.extend(host_os::doctor::checks(configs.as_ref()));
+
+    for check in &checks {
+        println!("{check}");
+    }
+
+    let failed = checks.iter().filter(|check| !check.passed()).count();
+    let total = checks.len();
+    if failed > 0 {
+        bail!(localized!(
+            pt_br: "{failed} de {total} verificação(ões) falhou(aram)",
+            en: "{failed} of {total} check(s) failed",
+        ));
+    }
+    println!();
+    println!(
+        "{}",
+        localized!(
+            pt_br: "Todas as verificações passaram",
+            en: "All checks passed",
+        )
+    );
+    Ok(())
+}
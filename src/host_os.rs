@@ -1,22 +1,47 @@
 use std::path::PathBuf;
-use std::process::ExitStatus;
 
 use anyhow::{Result, bail};
+use chrono::{DateTime, Local};
+#[cfg(test)]
+pub(crate) use command::CapturedOutput;
+pub(crate) use command::{
+    CommandRunner, HostCommand, NoOpCommandRunner, RecordingCommandRunner, SystemCommandRunner,
+};
 #[cfg(not(windows))]
 pub use linux::*;
+#[cfg(test)]
+pub(crate) use state_dir::StateDirSource;
+#[cfg(not(test))]
+pub(crate) use state_dir::resolved_state_dir;
+pub(crate) use state_dir::{StateDir, init_state_dir, state_dir};
 #[cfg(windows)]
 pub use windows::*;
 
+use crate::options_types::RebootAction;
 use crate::script::SetOrUnset;
+use crate::text::Localized;
 
+mod command;
 #[cfg(not(windows))]
 mod linux;
+mod state_dir;
 #[cfg(windows)]
 mod windows;
 
 pub(crate) fn state_path(filename: &str) -> PathBuf {
-    let state_dir_path = option_env!("STATE_DIR_PATH").unwrap_or(DEFAULT_STATE_DIR_PATH);
-    PathBuf::from(state_dir_path).join(filename)
+    state_dir_path().join(filename)
+}
+
+pub(crate) fn state_dir_path() -> PathBuf {
+    state_dir().path.clone()
+}
+
+/// A reboot action scheduled to be performed in the future.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(windows, derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct PendingAction {
+    pub(crate) action: RebootAction,
+    pub(crate) at: DateTime<Local>,
 }
 
 pub(crate) struct TemplateResolver {
@@ -29,22 +54,12 @@ impl TemplateResolver {
         }
     }
 
-    pub(crate) fn resolve_set_or_unset_option<T: ToString + Copy>(
-        &mut self,
-        pattern: &str,
-        option: Option<SetOrUnset<T>>,
-        undefined_text: &str,
-    ) {
-        let option = Self::set_or_unset_option_to_option(option);
-        self.resolve_option(pattern, option, undefined_text);
-    }
-
-    pub(crate) fn resolve_set_or_unset_option_with<T: Copy>(
+    pub(crate) fn resolve_set_or_unset_option_with<T>(
         &mut self,
         pattern: &str,
         option: Option<SetOrUnset<T>>,
         f: impl FnOnce(T) -> String,
-        undefined_text: &str,
+        undefined_text: Localized,
     ) {
         let option = Self::set_or_unset_option_to_option(option);
         self.resolve_option_with(pattern, option, f, undefined_text);
@@ -54,7 +69,7 @@ impl TemplateResolver {
         &mut self,
         pattern: &str,
         option: Option<T>,
-        undefined_text: &str,
+        undefined_text: Localized,
     ) {
         self.resolve_option_with(pattern, option, |op| op.to_string(), undefined_text);
     }
@@ -64,13 +79,13 @@ impl TemplateResolver {
         pattern: &str,
         option: Option<T>,
         f: impl FnOnce(T) -> String,
-        undefined_text: &str,
+        undefined_text: Localized,
     ) {
         let replacement = option.map_or_else(|| format!("[{undefined_text}]"), f);
         self.label = self.label.replace(&format!("{{{pattern}}}"), &replacement);
     }
 
-    fn set_or_unset_option_to_option<T: Copy>(option: Option<SetOrUnset<T>>) -> Option<T> {
+    fn set_or_unset_option_to_option<T>(option: Option<SetOrUnset<T>>) -> Option<T> {
         option.and_then(SetOrUnset::into_option)
     }
 
@@ -80,15 +95,12 @@ impl TemplateResolver {
 }
 
 pub trait SuccessOr {
-    fn success_or(self, message: &'static str) -> Result<()>;
+    fn success_or(self, message: Localized) -> Result<()>;
 }
 
-impl SuccessOr for ExitStatus {
-    fn success_or(self, message: &'static str) -> Result<()> {
-        if self.success() {
-            Ok(())
-        } else {
-            bail!(message)
-        }
+/// For whether a command succeeded.
+impl SuccessOr for bool {
+    fn success_or(self, message: Localized) -> Result<()> {
+        if self { Ok(()) } else { bail!(message) }
     }
 }
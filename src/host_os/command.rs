@@ -0,0 +1,331 @@
+use std::fmt::Display;
+use std::io::{self, Read};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use ansi_term::Color;
+use anyhow::{Result, bail};
+
+use crate::text::{Localized, localized};
+
+/// An external command, like `systemctl reboot`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct HostCommand {
+    pub(crate) program: String,
+    pub(crate) args: Vec<String>,
+    /// Whether the arguments are passed as they are, without being quoted, on Windows. `cmd`
+    /// doesn't unquote its command line like the other programs do.
+    pub(crate) raw_args: bool,
+}
+impl HostCommand {
+    pub(crate) fn new<S: ToString>(program: &str, args: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            program: program.to_string(),
+            args: args.into_iter().map(|arg| arg.to_string()).collect(),
+            raw_args: false,
+        }
+    }
+}
+impl HostCommand {
+    /// A command line to be run by the shell, like the hooks.
+    pub(crate) fn shell(command_line: &str) -> Self {
+        cfg_select! {
+            windows => Self {
+                raw_args: true,
+                ..Self::new("cmd", ["/C", command_line])
+            },
+            _ => Self::new("sh", ["-c", command_line]),
+        }
+    }
+
+    fn to_std_command(&self) -> Command {
+        let mut command = Command::new(&self.program);
+        #[cfg(windows)]
+        if self.raw_args {
+            for arg in &self.args {
+                std::os::windows::process::CommandExt::raw_arg(&mut command, arg);
+            }
+            return command;
+        }
+        command.args(&self.args);
+        command
+    }
+}
+impl Display for HostCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.program)?;
+        for arg in &self.args {
+            write!(f, " {arg}")?;
+        }
+        Ok(())
+    }
+}
+
+/// What a command run by [`CommandRunner::run_captured`] printed, and whether it succeeded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CapturedOutput {
+    pub(crate) success: bool,
+    /// The standard output followed by the standard error.
+    pub(crate) output: String,
+}
+
+/// Runs the external commands, so that they can be replaced in tests and dry runs.
+pub(crate) trait CommandRunner {
+    /// Runs the command and returns whether it succeeded.
+    fn run(&self, command: &HostCommand) -> Result<bool>;
+
+    /// Runs the command capturing its output, and kills it if it doesn't finish in time.
+    fn run_captured(&self, command: &HostCommand, timeout: Duration) -> Result<CapturedOutput>;
+}
+
+/// Actually runs the commands.
+pub(crate) struct SystemCommandRunner;
+impl CommandRunner for SystemCommandRunner {
+    fn run(&self, command: &HostCommand) -> Result<bool> {
+        let status = command.to_std_command().status()?;
+        Ok(status.success())
+    }
+
+    /// The processes started by the command are killed with it, and aren't waited for past the
+    /// timeout.
+    fn run_captured(&self, command: &HostCommand, timeout: Duration) -> Result<CapturedOutput> {
+        const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+        let mut process = command.to_std_command();
+        process
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut process, 0);
+        let mut child = process.spawn()?;
+        let stdout = PipeReader::spawn(child.stdout.take());
+        let stderr = PipeReader::spawn(child.stderr.take());
+
+        let deadline = Instant::now() + timeout;
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                kill_tree(&mut child)?;
+                child.wait()?;
+                let seconds = timeout.as_secs();
+                bail!(localized!(
+                    pt_br: "O comando não terminou em {seconds}s: {command}",
+                    en: "The command didn't finish within {seconds}s: {command}",
+                ));
+            }
+            thread::sleep(POLL_INTERVAL);
+        };
+
+        // A process left running in background may keep the pipes open.
+        while !(stdout.is_finished() && stderr.is_finished()) && Instant::now() < deadline {
+            thread::sleep(POLL_INTERVAL);
+        }
+        let mut output = stdout.into_string();
+        output.push_str(&stderr.into_string());
+        Ok(CapturedOutput {
+            success: status.success(),
+            output,
+        })
+    }
+}
+
+/// Kills the child with the processes it started, which are in its process group.
+#[cfg(unix)]
+fn kill_tree(child: &mut Child) -> Result<()> {
+    let process_group = format!("-{}", child.id());
+    let killed = Command::new("kill")
+        .args(["-KILL", "--", &process_group])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    if !killed {
+        child.kill()?;
+    }
+    Ok(())
+}
+
+/// Kills the child with the processes it started.
+#[cfg(windows)]
+fn kill_tree(child: &mut Child) -> Result<()> {
+    let pid = child.id().to_string();
+    let killed = Command::new("taskkill")
+        .args(["/T", "/F", "/PID", &pid])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    if !killed {
+        child.kill()?;
+    }
+    Ok(())
+}
+
+/// Reads a pipe in background, so that the child doesn't block on a full pipe.
+struct PipeReader {
+    content: Arc<Mutex<Vec<u8>>>,
+    thread: JoinHandle<()>,
+}
+impl PipeReader {
+    fn spawn(pipe: Option<impl Read + Send + 'static>) -> Self {
+        let content = Arc::new(Mutex::new(Vec::new()));
+        let thread = thread::spawn({
+            let content = Arc::clone(&content);
+            move || {
+                let Some(mut pipe) = pipe else {
+                    return;
+                };
+                let mut buffer = [0; 4096];
+                loop {
+                    match pipe.read(&mut buffer) {
+                        Ok(0) => break,
+                        Ok(count) => content
+                            .lock()
+                            .unwrap_or_else(PoisonError::into_inner)
+                            .extend_from_slice(&buffer[..count]),
+                        Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                        Err(_) => break,
+                    }
+                }
+            }
+        });
+        PipeReader { content, thread }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// What was read so far. The thread is left reading if the pipe is still open.
+    fn into_string(self) -> String {
+        let content = self.content.lock().unwrap_or_else(PoisonError::into_inner);
+        String::from_utf8_lossy(&content).into_owned()
+    }
+}
+
+/// Only tells the commands that would be run. Used when `NO_REBOOT_ACTION` is defined.
+pub(crate) struct NoOpCommandRunner;
+impl CommandRunner for NoOpCommandRunner {
+    fn run(&self, command: &HostCommand) -> Result<bool> {
+        let not_really = Localized::new("...mas não de verdade!", "...but not really!");
+        println!("{} 😬 ({command})", Color::Yellow.paint(not_really.get()));
+        Ok(true)
+    }
+
+    fn run_captured(&self, command: &HostCommand, _timeout: Duration) -> Result<CapturedOutput> {
+        Ok(CapturedOutput {
+            success: self.run(command)?,
+            output: String::new(),
+        })
+    }
+}
+
+/// Records the commands instead of running them, for dry runs and tests.
+#[derive(Default)]
+pub(crate) struct RecordingCommandRunner {
+    commands: std::cell::RefCell<Vec<HostCommand>>,
+}
+impl RecordingCommandRunner {
+    /// The recorded commands, as in the command line.
+    pub(crate) fn command_lines(&self) -> Vec<String> {
+        self.commands
+            .borrow()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+}
+impl CommandRunner for RecordingCommandRunner {
+    fn run(&self, command: &HostCommand) -> Result<bool> {
+        self.commands.borrow_mut().push(command.clone());
+        Ok(true)
+    }
+
+    fn run_captured(&self, command: &HostCommand, _timeout: Duration) -> Result<CapturedOutput> {
+        Ok(CapturedOutput {
+            success: self.run(command)?,
+            output: String::new(),
+        })
+    }
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_captured_output() -> Result<()> {
+        let command = HostCommand::shell("echo out; echo err >&2; exit 3");
+
+        let captured = SystemCommandRunner.run_captured(&command, Duration::from_secs(10))?;
+
+        assert_eq!(
+            captured,
+            CapturedOutput {
+                success: false,
+                output: "out\nerr\n".to_string(),
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn run_captured_timeout() {
+        let command = HostCommand::shell("sleep 10");
+
+        let begin = Instant::now();
+        let result = SystemCommandRunner.run_captured(&command, Duration::from_millis(100));
+
+        assert!(result.is_err());
+        assert!(begin.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn run_captured_timeout_kills_the_started_processes() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let pid_path = dir.path().join("pid");
+        let command = HostCommand::shell(&format!(
+            "sleep 10 & echo $! > '{}'; wait",
+            pid_path.display()
+        ));
+
+        let result = SystemCommandRunner.run_captured(&command, Duration::from_millis(500));
+
+        assert!(result.is_err());
+        let pid = std::fs::read_to_string(pid_path)?;
+        // A killed process may be left as a zombie until it's reaped by its new parent.
+        let running = || {
+            std::fs::read_to_string(format!("/proc/{}/stat", pid.trim()))
+                .is_ok_and(|stat| !stat.contains(") Z "))
+        };
+        let begin = Instant::now();
+        while running() && begin.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(50));
+        }
+        assert!(!running());
+        Ok(())
+    }
+
+    #[test]
+    fn run_captured_doesnt_wait_for_background_processes() -> Result<()> {
+        let command = HostCommand::shell("sleep 10 & echo out");
+
+        let begin = Instant::now();
+        let captured = SystemCommandRunner.run_captured(&command, Duration::from_millis(500))?;
+
+        assert!(begin.elapsed() < Duration::from_secs(5));
+        assert_eq!(
+            captured,
+            CapturedOutput {
+                success: true,
+                output: "out\n".to_string(),
+            }
+        );
+        Ok(())
+    }
+}
@@ -1,27 +1,180 @@
-use std::process::Command;
+use std::path::PathBuf;
+use std::{env, fs, io};
 
-use anyhow::Result;
+use anyhow::{Result, bail};
+use chrono::{DateTime, Local};
 
-use super::SuccessOr;
-use crate::options_types::OperatingSystem;
+use super::{CommandRunner, HostCommand, PendingAction, SuccessOr};
+use crate::options_types::{OperatingSystem, RebootAction, Schedule};
 use crate::text;
 
 pub mod configuration;
+pub mod doctor;
+mod grub_cfg;
+pub mod grub_hooks;
+mod partition;
 
-pub const HOST_OS: OperatingSystem = OperatingSystem::Linux;
+pub const HOST_OS: OperatingSystem = OperatingSystem::LINUX;
 pub(super) const DEFAULT_STATE_DIR_PATH: &str = "/boot/grub/grubenv.dir";
+/// Where systemd keeps the shutdown scheduled by the `shutdown` command.
+const SCHEDULED_SHUTDOWN_PATH: &str = "/run/systemd/shutdown/scheduled";
 
-pub fn reboot() -> Result<()> {
-    systemctl("reboot")
+/// Where the per-user configs are, as in the XDG Base Directory specification.
+pub(super) fn user_config_dir() -> Option<PathBuf> {
+    match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => Some(dir.into()),
+        _ => Some(PathBuf::from(env::var_os("HOME")?).join(".config")),
+    }
 }
 
-pub fn shutdown() -> Result<()> {
-    systemctl("poweroff")
+pub(crate) fn perform(action: RebootAction, runner: &dyn CommandRunner) -> Result<()> {
+    runner
+        .run(&action_command(action))?
+        .success_or(text::reboot_action::FAILED)
+}
+
+fn action_command(action: RebootAction) -> HostCommand {
+    let systemctl = |args: &[&str]| HostCommand::new("systemctl", args);
+
+    match action {
+        RebootAction::Reboot => systemctl(&["reboot"]),
+        RebootAction::Shutdown => systemctl(&["poweroff"]),
+        RebootAction::FirmwareSetup => systemctl(&["reboot", "--firmware-setup"]),
+        RebootAction::Suspend => systemctl(&["suspend"]),
+        RebootAction::Hibernate => systemctl(&["hibernate"]),
+        RebootAction::Kexec => systemctl(&["kexec"]),
+        RebootAction::Logout => {
+            // "self" is resolved by logind to the session of the caller.
+            let session = env::var("XDG_SESSION_ID").unwrap_or_else(|_| "self".to_string());
+            HostCommand::new("loginctl", ["terminate-session".to_string(), session])
+        }
+    }
 }
 
-fn systemctl(arg: &str) -> Result<()> {
-    Command::new("systemctl")
-        .arg(arg)
-        .status()?
+/// Schedules the action through systemd's `shutdown` command.
+pub(crate) fn schedule(
+    action: RebootAction,
+    schedule: Schedule,
+    runner: &dyn CommandRunner,
+) -> Result<()> {
+    let Some(args) = schedule_args(action, schedule) else {
+        bail!(text::reboot_action::cannot_be_scheduled(action));
+    };
+    runner
+        .run(&HostCommand::new("shutdown", args))?
         .success_or(text::reboot_action::FAILED)
 }
+
+fn schedule_args(action: RebootAction, schedule: Schedule) -> Option<[String; 2]> {
+    let mode = match action {
+        RebootAction::Reboot => "--reboot",
+        RebootAction::Shutdown => "--poweroff",
+        _ => return None,
+    };
+    let when = match schedule {
+        Schedule::In(minutes) => format!("+{minutes}"),
+        Schedule::At(time) => time.format("%H:%M").to_string(),
+    };
+    Some([mode.to_string(), when])
+}
+
+pub(crate) fn cancel(runner: &dyn CommandRunner) -> Result<()> {
+    runner
+        .run(&HostCommand::new("shutdown", ["-c"]))?
+        .success_or(text::reboot_action::CANCEL_FAILED)
+}
+
+pub(crate) fn pending_action() -> Result<Option<PendingAction>> {
+    match fs::read_to_string(SCHEDULED_SHUTDOWN_PATH) {
+        Ok(content) => Ok(parse_scheduled_shutdown(&content)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Parses the `KEY=value` lines written by systemd, like `USEC=1715389200000000` and
+/// `MODE=reboot`.
+fn parse_scheduled_shutdown(content: &str) -> Option<PendingAction> {
+    let mut usec = None;
+    let mut action = None;
+    for line in content.lines() {
+        match line.split_once('=') {
+            Some(("USEC", value)) => usec = value.parse::<i64>().ok(),
+            Some(("MODE", "reboot")) => action = Some(RebootAction::Reboot),
+            Some(("MODE", "poweroff")) => action = Some(RebootAction::Shutdown),
+            _ => {}
+        }
+    }
+
+    let at = DateTime::from_timestamp_micros(usec?)?.with_timezone(&Local);
+    Some(PendingAction {
+        action: action?,
+        at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveTime;
+
+    use super::*;
+
+    #[test]
+    fn action_commands() {
+        let cases = [
+            (RebootAction::Reboot, "systemctl reboot"),
+            (RebootAction::Shutdown, "systemctl poweroff"),
+            (
+                RebootAction::FirmwareSetup,
+                "systemctl reboot --firmware-setup",
+            ),
+            (RebootAction::Suspend, "systemctl suspend"),
+            (RebootAction::Hibernate, "systemctl hibernate"),
+            (RebootAction::Kexec, "systemctl kexec"),
+        ];
+
+        for (action, expected) in cases {
+            assert_eq!(action_command(action).to_string(), expected, "{action:?}");
+        }
+
+        let command = action_command(RebootAction::Logout);
+        assert_eq!(command.program, "loginctl");
+        assert_eq!(command.args[0], "terminate-session");
+    }
+
+    #[test]
+    fn schedule_args_for_shutdown() {
+        let at = Schedule::At(NaiveTime::from_hms_opt(23, 30, 0).unwrap());
+
+        assert_eq!(
+            schedule_args(RebootAction::Reboot, Schedule::In(10)),
+            Some(["--reboot".to_string(), "+10".to_string()])
+        );
+        assert_eq!(
+            schedule_args(RebootAction::Shutdown, at),
+            Some(["--poweroff".to_string(), "23:30".to_string()])
+        );
+        assert_eq!(schedule_args(RebootAction::Suspend, at), None);
+    }
+
+    #[test]
+    fn parse_systemd_scheduled_shutdown() {
+        let pending_action =
+            parse_scheduled_shutdown("USEC=1715389200000000\nWARN_WALL=1\nMODE=reboot\n");
+
+        assert_eq!(
+            pending_action,
+            Some(PendingAction {
+                action: RebootAction::Reboot,
+                at: DateTime::from_timestamp(1_715_389_200, 0)
+                    .unwrap()
+                    .with_timezone(&Local),
+            })
+        );
+        assert_eq!(
+            parse_scheduled_shutdown("USEC=1715389200000000\nMODE=halt\n"),
+            None
+        );
+        assert_eq!(parse_scheduled_shutdown(""), None);
+    }
+}
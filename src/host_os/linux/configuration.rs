@@ -1,48 +1,289 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fs;
+use std::path::{Path, PathBuf};
 
-use anyhow::{Result, anyhow};
-use regex::Regex;
+use anyhow::{Context, Result, bail};
+use indexmap::IndexMap;
 
+use super::grub_cfg::{self, MenuEntry};
+use super::partition::{Discovery, SystemPaths};
 use crate::configuration::Configurer;
-use crate::options_types::{OperatingSystem, Values as _};
+use crate::host_os::state_dir_path;
+use crate::options_types::OperatingSystem;
+use crate::text::localized;
+
+pub(super) const GRUB_CFG: &str = "/boot/grub/grub.cfg";
 
 pub fn configure(configurer: &mut Configurer) -> Result<()> {
-    const GRUB_CFG: &str = "/boot/grub/grub.cfg";
+    show_partition_discovery(configurer.options.fstab)?;
 
-    let grub_entry_re = Regex::new(r".*'([a-zA-Z0-9_-]+)'\s*\{.*")?;
-    let extract_os_and_grub_entry = |line: &str| -> Option<(OperatingSystem, String)> {
-        if !line.starts_with("menuentry ") {
-            return None;
-        }
+    let grub_cfg = configurer
+        .options
+        .grub_cfg
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(GRUB_CFG));
 
-        let uppercase_line = line.to_uppercase();
-        let os = OperatingSystem::values()
+    let operating_systems: Vec<_> = configurer
+        .configs
+        .operating_systems()
+        .into_iter()
+        .map(|os| {
+            let names = [
+                Some(os.to_string()),
+                configurer
+                    .configs
+                    .operating_system_label(&os)
+                    .map(str::to_string),
+            ]
             .into_iter()
-            .find(|os| uppercase_line.contains(&os.to_string().to_uppercase()));
+            .flatten()
+            .map(|name| name.to_uppercase())
+            .collect::<Vec<_>>();
+            (os, names)
+        })
+        .collect();
+
+    let path = grub_cfg.display();
+    println!(
+        "{}",
+        localized!(pt_br: "Lendo {path}...", en: "Reading {path}...")
+    );
+    let menu_entries = read_menu_entries(&grub_cfg)?;
+    let mut entries = find_grub_entries(&menu_entries, &operating_systems);
+
+    for (os, _) in &operating_systems {
+        let os_entries = entries.shift_remove(os).unwrap_or_default();
+        let grub_entry = match &os_entries[..] {
+            [entry] => {
+                let title = entry.full_title();
+                println!("{os}: {title}");
+                entry.path.clone()
+            }
+            [] if configurer.options.yes => bail!(localized!(
+                pt_br: "Entrada não encontrada para {os}",
+                en: "Entry not found for {os}",
+            )),
+            _ if configurer.options.yes => {
+                let titles = os_entries
+                    .iter()
+                    .map(|entry| format!("{:?}", entry.full_title()))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                bail!(localized!(
+                    pt_br: "Mais de uma entrada encontrada para {os}: {titles}. Defina um nome (label) que diferencie o sistema operacional nas configurações.",
+                    en: "More than one entry found for {os}: {titles}. Set a name (label) that tells the operating system apart in the configs.",
+                ))
+            }
+            _ => choose_grub_entry(configurer, os, &menu_entries)?,
+        };
+        configurer.configs.set_grub_entry(os, &grub_entry);
+    }
+
+    Ok(())
+}
+
+/// Shows what was found about the shared `grubenv` partition, and optionally the `/etc/fstab`
+/// line that mounts it at the state directory.
+fn show_partition_discovery(fstab: bool) -> Result<()> {
+    let discovery = Discovery::scan(&SystemPaths::default()).with_context(|| {
+        localized!(
+            pt_br: "Não foi possível procurar a partição com o grubenv",
+            en: "Couldn't look for the partition with the grubenv",
+        )
+    })?;
+    println!("{discovery}");
 
-        let grub_entry = grub_entry_re.captures(line).map(|caps| caps[1].to_string());
+    if fstab {
+        match discovery.fstab_line(&state_dir_path()) {
+            Some(line) => {
+                println!(
+                    "{}",
+                    localized!(
+                        pt_br: "Linha para o /etc/fstab:",
+                        en: "Line for /etc/fstab:",
+                    )
+                );
+                println!("{line}");
+                println!();
+            }
+            None => bail!(localized!(
+                pt_br: "Não foi possível determinar a partição a ser montada. Consulte GRUB-CONFIGURATION.md.",
+                en: "Couldn't tell the partition to be mounted. See GRUB-CONFIGURATION.md.",
+            )),
+        }
+    }
 
-        os.zip(grub_entry)
+    Ok(())
+}
+
+/// The given Grub configuration file, or the default one.
+pub fn grub_cfg_path(grub_cfg: Option<&Path>) -> &Path {
+    grub_cfg.unwrap_or(Path::new(GRUB_CFG))
+}
+
+/// The paths of the menu entries of the Grub configuration file, as they're saved in the configs.
+pub fn grub_entry_paths(grub_cfg: Option<&Path>) -> Result<Vec<String>> {
+    let menu_entries = read_menu_entries(grub_cfg_path(grub_cfg))?;
+    Ok(menu_entries.into_iter().map(|entry| entry.path).collect())
+}
+
+fn read_menu_entries(grub_cfg: &Path) -> Result<Vec<MenuEntry>> {
+    let path = grub_cfg.display();
+    let content = fs::read_to_string(grub_cfg).with_context(|| {
+        localized!(
+            pt_br: "Não foi possível ler {path}",
+            en: "Couldn't read {path}",
+        )
+    })?;
+    grub_cfg::parse(&content).with_context(|| {
+        localized!(
+            pt_br: "Não foi possível interpretar {path}",
+            en: "Couldn't parse {path}",
+        )
+    })
+}
+
+/// The menu entries that match each operating system, by its uppercase names.
+///
+/// An entry matches a name if its title, id or one of its classes contains it. Entries inside
+/// submenus (like the ones of older kernels) are only considered if there's no other match.
+fn find_grub_entries<'a>(
+    menu_entries: &'a [MenuEntry],
+    operating_systems: &[(OperatingSystem, Vec<String>)],
+) -> IndexMap<OperatingSystem, Vec<&'a MenuEntry>> {
+    let find_os = |entry: &MenuEntry| -> Option<OperatingSystem> {
+        let texts: Vec<_> = [&entry.title]
+            .into_iter()
+            .chain(&entry.id)
+            .chain(&entry.classes)
+            .map(|text| text.to_uppercase())
+            .collect();
+
+        // Entries of other Linux distros usually also contain "Linux" in their titles, so the
+        // builtin operating systems are the last ones to be considered.
+        operating_systems
+            .iter()
+            .filter(|(_, names)| {
+                names
+                    .iter()
+                    .any(|name| texts.iter().any(|text| text.contains(name)))
+            })
+            .min_by_key(|(os, _)| *os == OperatingSystem::WINDOWS || *os == OperatingSystem::LINUX)
+            .map(|(os, _)| os.clone())
     };
 
-    println!("Lendo {GRUB_CFG}...");
-    let reader = BufReader::new(File::open(GRUB_CFG)?);
+    let mut entries: IndexMap<_, Vec<&MenuEntry>> = IndexMap::new();
+    for entry in menu_entries {
+        if let Some(os) = find_os(entry) {
+            let os_entries = entries.entry(os).or_default();
+            if !os_entries.iter().any(|other| other.path == entry.path) {
+                os_entries.push(entry);
+            }
+        }
+    }
 
-    let mut entries = [None, None];
-    for line in reader.lines() {
-        let line = line?;
-        if let Some((os, grub_entry)) = extract_os_and_grub_entry(&line) {
-            entries[os as usize] = Some(grub_entry);
+    for os_entries in entries.values_mut() {
+        if os_entries.iter().any(|entry| !entry.is_in_submenu()) {
+            os_entries.retain(|entry| !entry.is_in_submenu());
         }
     }
 
-    for os in OperatingSystem::values() {
-        let grub_entry = entries[os as usize]
-            .as_ref()
-            .ok_or_else(|| anyhow!("Entrada não encontrada para {os}"))?;
-        configurer.configs.set_grub_entry(os, grub_entry);
+    entries
+}
+
+/// Lets the user choose among all the menu entries, when there isn't a single one matching the
+/// operating system.
+fn choose_grub_entry(
+    configurer: &mut Configurer,
+    os: &OperatingSystem,
+    entries: &[MenuEntry],
+) -> Result<String> {
+    if entries.is_empty() {
+        bail!(localized!(
+            pt_br: "Nenhuma entrada encontrada no arquivo de configuração do Grub",
+            en: "No entries found in the Grub configuration file",
+        ));
     }
 
-    Ok(())
+    loop {
+        println!();
+        println!(
+            "{}",
+            localized!(
+                pt_br: "Escolha a entrada do Grub para o {os}:",
+                en: "Choose the Grub entry for {os}:",
+            )
+        );
+        for (i, entry) in entries.iter().enumerate() {
+            println!("{}. {}", i + 1, entry.full_title());
+        }
+
+        let input = configurer.readline.readline("> ")?;
+        match input.trim().parse::<usize>() {
+            Ok(number) if (1..=entries.len()).contains(&number) => {
+                return Ok(entries[number - 1].path.clone());
+            }
+            _ => println!(
+                "{}",
+                localized!(
+                    pt_br: "Opção inválida: {input:?}",
+                    en: "Invalid option: {input:?}",
+                )
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UBUNTU_WINDOWS: &str = include_str!("../../../tests/fixtures/grub/ubuntu-windows.cfg");
+    const MULTI_DISTRO: &str = include_str!("../../../tests/fixtures/grub/multi-distro.cfg");
+
+    fn find_paths(
+        content: &str,
+        operating_systems: &[(OperatingSystem, Vec<String>)],
+    ) -> Result<IndexMap<OperatingSystem, Vec<String>>> {
+        let menu_entries = grub_cfg::parse(content)?;
+        Ok(find_grub_entries(&menu_entries, operating_systems)
+            .into_iter()
+            .map(|(os, entries)| (os, entries.iter().map(|entry| entry.path.clone()).collect()))
+            .collect())
+    }
+
+    #[test]
+    fn find_grub_entries_by_names() -> Result<()> {
+        let operating_systems = [
+            (OperatingSystem::WINDOWS, vec!["WINDOWS".to_string()]),
+            (OperatingSystem::LINUX, vec!["LINUX".to_string()]),
+        ];
+
+        let paths = find_paths(UBUNTU_WINDOWS, &operating_systems)?;
+
+        // "Ubuntu" is found by its "gnu-linux" class, and the entries of the submenu are ignored.
+        assert_eq!(paths[&OperatingSystem::LINUX], ["gnulinux-simple-9a8b7c6d"]);
+        assert_eq!(paths[&OperatingSystem::WINDOWS], ["osprober-efi-1A2B-3C4D"]);
+        assert_eq!(paths.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn find_grub_entries_of_custom_operating_systems() -> Result<()> {
+        let fedora = OperatingSystem::try_from("fedora".to_string()).unwrap();
+        let operating_systems = [
+            (OperatingSystem::WINDOWS, vec!["WINDOWS".to_string()]),
+            (OperatingSystem::LINUX, vec!["LINUX".to_string()]),
+            (fedora.clone(), vec!["FEDORA".to_string()]),
+        ];
+
+        let paths = find_paths(MULTI_DISTRO, &operating_systems)?;
+
+        assert_eq!(paths[&fedora], ["osprober-gnulinux-simple-2222"]);
+        assert_eq!(
+            paths[&OperatingSystem::LINUX],
+            ["gnulinux-simple-1111", r#"Arch Linux "rolling""#]
+        );
+        assert_eq!(paths[&OperatingSystem::WINDOWS], ["osprober-efi-4444"]);
+        Ok(())
+    }
 }
@@ -0,0 +1,419 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+
+use super::configuration::{GRUB_CFG, grub_entry_paths};
+use super::grub_hooks;
+use super::partition::{Discovery, GrubenvLink, SystemPaths};
+use crate::doctor::Check;
+use crate::host_os::state_path;
+use crate::persist::configs::Configs;
+use crate::persist::grubenv::{
+    GRUBENV_CONTENT_LENGTH, GRUBENV_FILENAME, GRUBENV_HEADER_LINE, Grubenv,
+};
+use crate::text::localized;
+
+const DEFAULT_GRUB: &str = "/etc/default/grub";
+/// Has files that are sourced after [`DEFAULT_GRUB`], overriding its settings.
+const DEFAULT_GRUB_D_DIR: &str = "/etc/default/grub.d";
+const SAVED_ENTRY: &str = "saved_entry";
+/// The settings of [`DEFAULT_GRUB`] that make GRUB boot the saved entry.
+const DEFAULT_GRUB_SETTINGS: [(&str, &str); 2] =
+    [("GRUB_DEFAULT", "saved"), ("GRUB_SAVEDEFAULT", "true")];
+
+/// The files checked by [`file_checks`].
+struct Files {
+    grubenv: PathBuf,
+    grub_cfg: PathBuf,
+    default_grub: PathBuf,
+    default_grub_d_dir: PathBuf,
+}
+impl Default for Files {
+    fn default() -> Self {
+        Files {
+            grubenv: state_path(GRUBENV_FILENAME),
+            grub_cfg: PathBuf::from(GRUB_CFG),
+            default_grub: PathBuf::from(DEFAULT_GRUB),
+            default_grub_d_dir: PathBuf::from(DEFAULT_GRUB_D_DIR),
+        }
+    }
+}
+
+pub(crate) fn checks(configs: Option<&Configs>) -> Vec<Check> {
+    let mut checks = file_checks(&Files::default(), configs);
+    checks.extend(partition_checks());
+    checks.push(Check::new(
+        localized!(
+            pt_br: "Os scripts do Grub estão alterados para usar o grubenv compartilhado",
+            en: "The Grub scripts are edited to use the shared grubenv",
+        ),
+        check_grub_hooks(),
+    ));
+    checks
+}
+
+fn file_checks(files: &Files, configs: Option<&Configs>) -> Vec<Check> {
+    let grubenv_path = files.grubenv.display();
+    let mut checks = vec![Check::new(
+        localized!(
+            pt_br: "{grubenv_path} tem {GRUBENV_CONTENT_LENGTH} bytes e o cabeçalho do Grub",
+            en: "{grubenv_path} has {GRUBENV_CONTENT_LENGTH} bytes and the Grub header",
+        ),
+        check_grubenv_layout(&files.grubenv),
+    )];
+
+    let Some(configs) = configs else {
+        return checks;
+    };
+
+    checks.push(Check::new(
+        localized!(
+            pt_br: "{SAVED_ENTRY} é a entrada de um sistema operacional configurado",
+            en: "{SAVED_ENTRY} is the entry of a configured operating system",
+        ),
+        check_saved_entry(&files.grubenv, configs),
+    ));
+
+    let grub_entries = grub_entry_paths(Some(&files.grub_cfg));
+    let grub_cfg = files.grub_cfg.display();
+    for (os, os_configs) in configs.operating_system.iter() {
+        let outcome = match &grub_entries {
+            Ok(grub_entries) if grub_entries.contains(&os_configs.grub_entry) => Ok(()),
+            Ok(_) => {
+                let grub_entry = &os_configs.grub_entry;
+                Err(anyhow!(localized!(
+                    pt_br: "a entrada {grub_entry:?} não existe",
+                    en: "the entry {grub_entry:?} doesn't exist",
+                )))
+            }
+            Err(e) => Err(anyhow!("{e:#}")),
+        };
+        checks.push(Check::new(
+            localized!(
+                pt_br: "A entrada do Grub do {os} existe em {grub_cfg}",
+                en: "The Grub entry of {os} exists in {grub_cfg}",
+            ),
+            outcome,
+        ));
+    }
+
+    let default_grub = files.default_grub.display();
+    let settings = read_default_grub(&files.default_grub, &files.default_grub_d_dir);
+    for (key, expected) in DEFAULT_GRUB_SETTINGS {
+        let outcome = match &settings {
+            Ok(settings) => match settings.iter().rfind(|(k, _)| k == key) {
+                Some((_, value)) if value == expected => Ok(()),
+                Some((_, value)) => Err(anyhow!(localized!(
+                    pt_br: "o valor é {value:?}",
+                    en: "the value is {value:?}",
+                ))),
+                None => Err(anyhow!(localized!(
+                    pt_br: "não definido",
+                    en: "not set",
+                ))),
+            },
+            Err(e) => Err(anyhow!("{e:#}")),
+        };
+        checks.push(Check::new(
+            localized!(
+                pt_br: "{key}={expected} em {default_grub}",
+                en: "{key}={expected} in {default_grub}",
+            ),
+            outcome,
+        ));
+    }
+
+    checks
+}
+
+fn partition_checks() -> Vec<Check> {
+    let discovery = Discovery::scan(&SystemPaths::default());
+
+    let mounted = match &discovery {
+        Ok(discovery) if discovery.grubenv_partitions.is_empty() => Err(anyhow!(localized!(
+            pt_br: "nenhuma partição FAT montada com um grubenv válido",
+            en: "no mounted FAT partition with a valid grubenv",
+        ))),
+        Ok(discovery) if !discovery.grubenv_partitions.iter().any(|p| p.writable) => {
+            Err(anyhow!(localized!(
+                pt_br: "não permite gravação",
+                en: "it isn't writable",
+            )))
+        }
+        Ok(_) => Ok(()),
+        Err(e) => Err(anyhow!("{e}")),
+    };
+    let linked = match discovery.map(|discovery| discovery.grubenv_link) {
+        Ok(GrubenvLink::Symlink {
+            to_partition: true, ..
+        }) => Ok(()),
+        Ok(GrubenvLink::Symlink { target, .. }) => {
+            let target = target.display();
+            Err(anyhow!(localized!(
+                pt_br: "aponta para {target}",
+                en: "it links to {target}",
+            )))
+        }
+        Ok(GrubenvLink::NotSymlink) => Err(anyhow!(localized!(
+            pt_br: "não é um link simbólico",
+            en: "it isn't a symlink",
+        ))),
+        Ok(GrubenvLink::Missing) => Err(anyhow!(localized!(
+            pt_br: "não existe",
+            en: "it doesn't exist",
+        ))),
+        Err(e) => Err(anyhow!("{e}")),
+    };
+
+    vec![
+        Check::new(
+            localized!(
+                pt_br: "A partição com o grubenv está montada e permite gravação",
+                en: "The partition with the grubenv is mounted and writable",
+            ),
+            mounted,
+        ),
+        Check::new(
+            localized!(
+                pt_br: "/boot/grub/grubenv é um link simbólico para o grubenv da partição",
+                en: "/boot/grub/grubenv is a symlink to the grubenv of the partition",
+            ),
+            linked,
+        ),
+    ]
+}
+
+fn check_grubenv_layout(path: &Path) -> Result<()> {
+    let content = fs::read(path)?;
+    if content.len() != GRUBENV_CONTENT_LENGTH {
+        let length = content.len();
+        bail!(localized!(
+            pt_br: "ele tem {length} bytes",
+            en: "it has {length} bytes",
+        ));
+    }
+    if !content.starts_with(GRUBENV_HEADER_LINE.as_bytes()) {
+        bail!(localized!(
+            pt_br: "sem o cabeçalho {GRUBENV_HEADER_LINE:?}",
+            en: "without the header {GRUBENV_HEADER_LINE:?}",
+        ));
+    }
+    Grubenv::load_from(path)?;
+    Ok(())
+}
+
+fn check_grub_hooks() -> Result<()> {
+    let pending = grub_hooks::pending_scripts()?;
+    if !pending.is_empty() {
+        let paths = pending
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        bail!(localized!(
+            pt_br: "alterações pendentes em {paths}",
+            en: "edits pending on {paths}",
+        ));
+    }
+    Ok(())
+}
+
+fn check_saved_entry(grubenv_path: &Path, configs: &Configs) -> Result<()> {
+    let grubenv = Grubenv::load_from(grubenv_path)?;
+    let Some(saved_entry) = grubenv.get(SAVED_ENTRY) else {
+        bail!(localized!(pt_br: "não definido", en: "not set"));
+    };
+    if configs
+        .operating_system_by_grub_entry(saved_entry)
+        .is_none()
+    {
+        bail!(localized!(
+            pt_br: "{saved_entry:?} não é a entrada de nenhum sistema operacional",
+            en: "{saved_entry:?} isn't the entry of any operating system",
+        ));
+    }
+    Ok(())
+}
+
+/// The variables assigned in the file and then in the `*.cfg` files of the directory, in order.
+fn read_default_grub(path: &Path, dir: &Path) -> Result<Vec<(String, String)>> {
+    let mut paths = vec![path.to_path_buf()];
+    if let Ok(entries) = fs::read_dir(dir) {
+        let mut cfg_paths: Vec<_> = entries
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "cfg"))
+            .collect();
+        cfg_paths.sort();
+        paths.extend(cfg_paths);
+    }
+
+    let mut settings = Vec::new();
+    for path in paths {
+        let content = fs::read_to_string(&path).with_context(|| {
+            let path = path.display();
+            localized!(
+                pt_br: "Não foi possível ler {path}",
+                en: "Couldn't read {path}",
+            )
+        })?;
+        settings.extend(parse_assignments(&content));
+    }
+    Ok(settings)
+}
+
+/// Parses the `KEY=value` lines of a shell script, with the values unquoted.
+fn parse_assignments(content: &str) -> Vec<(String, String)> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (key, value) = line.split_once('=')?;
+            let is_name =
+                !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !is_name || line.starts_with('#') {
+                return None;
+            }
+
+            let value = match value.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let value = &value[1..];
+                    &value[..value.find(quote).unwrap_or(value.len())]
+                }
+                _ => value
+                    .split([' ', '\t', '#', ';'])
+                    .next()
+                    .unwrap_or_default(),
+            };
+            Some((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIGS: &str = r#"
+        version = 2
+
+        [operating_system.windows]
+        grub_entry = "osprober-efi-1A2B-3C4D"
+
+        [operating_system.linux]
+        grub_entry = "gnulinux-simple-9a8b7c6d"
+
+        [profile]
+    "#;
+
+    struct Setup {
+        _dir: tempfile::TempDir,
+        files: Files,
+    }
+    impl Setup {
+        fn new() -> Result<Self> {
+            let dir = tempfile::tempdir()?;
+            let files = Files {
+                grubenv: dir.path().join(GRUBENV_FILENAME),
+                grub_cfg: dir.path().join("grub.cfg"),
+                default_grub: dir.path().join("grub"),
+                default_grub_d_dir: dir.path().join("grub.d"),
+            };
+            write_grubenv(&files.grubenv, "saved_entry=gnulinux-simple-9a8b7c6d\n")?;
+            fs::write(
+                &files.grub_cfg,
+                include_str!("../../../tests/fixtures/grub/ubuntu-windows.cfg"),
+            )?;
+            fs::write(
+                &files.default_grub,
+                "# If you change this file, run 'update-grub'.\nGRUB_DEFAULT=saved\nGRUB_SAVEDEFAULT=\"true\"\nGRUB_TIMEOUT=5\n",
+            )?;
+            Ok(Setup { _dir: dir, files })
+        }
+
+        /// The descriptions of the failed checks, with the reasons.
+        fn failures(&self) -> Result<Vec<String>> {
+            let configs = Configs::from_serialized(CONFIGS)?;
+            Ok(file_checks(&self.files, Some(&configs))
+                .iter()
+                .filter(|check| !check.passed())
+                .map(ToString::to_string)
+                .collect())
+        }
+    }
+
+    fn write_grubenv(path: &Path, entries: &str) -> Result<()> {
+        let mut content = format!("{GRUBENV_HEADER_LINE}{entries}");
+        content.push_str(&"#".repeat(GRUBENV_CONTENT_LENGTH - content.len()));
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    #[test]
+    fn all_file_checks_pass() -> Result<()> {
+        let setup = Setup::new()?;
+
+        assert_eq!(setup.failures()?, Vec::<String>::new());
+        let configs = Configs::from_serialized(CONFIGS)?;
+        assert_eq!(file_checks(&setup.files, Some(&configs)).len(), 6);
+        Ok(())
+    }
+
+    #[test]
+    fn file_checks_fail() -> Result<()> {
+        let setup = Setup::new()?;
+        fs::write(
+            &setup.files.grubenv,
+            "# GRUB Environment Block\nsaved_entry=hand-picked\n",
+        )?;
+        fs::write(
+            &setup.files.grub_cfg,
+            include_str!("../../../tests/fixtures/grub/multi-distro.cfg"),
+        )?;
+        fs::create_dir(&setup.files.default_grub_d_dir)?;
+        fs::write(
+            setup.files.default_grub_d_dir.join("50-cloudimg.cfg"),
+            "GRUB_DEFAULT=0\n",
+        )?;
+
+        let failures = setup.failures()?;
+
+        assert_eq!(failures.len(), 5, "{failures:#?}");
+        assert!(failures[0].contains(" 49 bytes"));
+        assert!(failures[1].contains("\"hand-picked\""));
+        assert!(failures[2].contains("\"osprober-efi-1A2B-3C4D\""));
+        assert!(failures[3].contains("\"gnulinux-simple-9a8b7c6d\""));
+        assert!(failures[4].contains("GRUB_DEFAULT=saved") && failures[4].contains("\"0\""));
+        Ok(())
+    }
+
+    #[test]
+    fn checks_without_configs() -> Result<()> {
+        let setup = Setup::new()?;
+        fs::remove_file(&setup.files.grubenv)?;
+
+        let checks = file_checks(&setup.files, None);
+
+        assert_eq!(checks.len(), 1);
+        assert!(!checks[0].passed());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_shell_assignments() {
+        let content = "# GRUB_DEFAULT=0\nexport GRUB_DEFAULT='saved' # comment\nGRUB_CMDLINE_LINUX_DEFAULT=\"quiet splash\"\nGRUB_SAVEDEFAULT=true ; x\nif [ x ]; then\n";
+
+        assert_eq!(
+            parse_assignments(content),
+            [
+                ("GRUB_DEFAULT".to_string(), "saved".to_string()),
+                (
+                    "GRUB_CMDLINE_LINUX_DEFAULT".to_string(),
+                    "quiet splash".to_string()
+                ),
+                ("GRUB_SAVEDEFAULT".to_string(), "true".to_string()),
+            ]
+        );
+    }
+}
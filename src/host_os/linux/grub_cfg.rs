@@ -0,0 +1,332 @@
+use anyhow::{Result, bail};
+
+use crate::text::localized;
+
+/// A `menuentry` of a `grub.cfg` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MenuEntry {
+    pub(crate) title: String,
+    /// Given by `--id` or `$menuentry_id_option`.
+    pub(crate) id: Option<String>,
+    pub(crate) classes: Vec<String>,
+    /// The titles of the enclosing submenus, from the outermost one.
+    pub(crate) submenu_titles: Vec<String>,
+    /// The value that identifies the entry in `saved_entry`: the ids (or the titles, if there is
+    /// no id) of the enclosing submenus and of the entry, joined by `>`.
+    pub(crate) path: String,
+}
+impl MenuEntry {
+    pub(crate) fn is_in_submenu(&self) -> bool {
+        !self.submenu_titles.is_empty()
+    }
+
+    /// The titles of the enclosing submenus and of the entry, as shown in the GRUB menu.
+    pub(crate) fn full_title(&self) -> String {
+        let mut titles = self.submenu_titles.clone();
+        titles.push(self.title.clone());
+        titles.join(" > ")
+    }
+}
+
+/// Extracts the menu entries of the content of a `grub.cfg` file, in the order they appear.
+pub(crate) fn parse(content: &str) -> Result<Vec<MenuEntry>> {
+    enum Block {
+        Submenu {
+            title: String,
+            path_component: String,
+        },
+        Other,
+    }
+
+    let mut entries = Vec::new();
+    let mut blocks = Vec::new();
+    let mut words = Vec::new();
+
+    for (token, line_number) in tokenize(content)? {
+        match token {
+            Token::Word(word) => words.push(word),
+            Token::Separator => words.clear(),
+            Token::Open => {
+                let block = match words.split_first() {
+                    Some((command, args)) if command == "menuentry" || command == "submenu" => {
+                        let args = MenuArgs::parse(args, line_number)?;
+                        let path_component = args.id.clone().unwrap_or_else(|| args.title.clone());
+
+                        if command == "submenu" {
+                            Block::Submenu {
+                                title: args.title,
+                                path_component,
+                            }
+                        } else {
+                            let mut path_components = Vec::new();
+                            let mut submenu_titles = Vec::new();
+                            for block in &blocks {
+                                if let Block::Submenu {
+                                    title,
+                                    path_component,
+                                } = block
+                                {
+                                    submenu_titles.push(title.clone());
+                                    path_components.push(path_component.clone());
+                                }
+                            }
+                            path_components.push(path_component);
+
+                            entries.push(MenuEntry {
+                                title: args.title,
+                                id: args.id,
+                                classes: args.classes,
+                                submenu_titles,
+                                path: path_components.join(">"),
+                            });
+                            Block::Other
+                        }
+                    }
+                    _ => Block::Other,
+                };
+                blocks.push(block);
+                words.clear();
+            }
+            Token::Close => {
+                if blocks.pop().is_none() {
+                    bail!(localized!(
+                        pt_br: "'}}' sem '{{' correspondente na linha {line_number}",
+                        en: "'}}' without a matching '{{' on line {line_number}",
+                    ));
+                }
+                words.clear();
+            }
+        }
+    }
+
+    if !blocks.is_empty() {
+        bail!(localized!(
+            pt_br: "Bloco não fechado no fim do arquivo",
+            en: "Unclosed block at the end of the file",
+        ));
+    }
+
+    Ok(entries)
+}
+
+struct MenuArgs {
+    title: String,
+    id: Option<String>,
+    classes: Vec<String>,
+}
+impl MenuArgs {
+    fn parse(args: &[String], line_number: usize) -> Result<Self> {
+        let mut title = None;
+        let mut id = None;
+        let mut classes = Vec::new();
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--class" => classes.extend(args.next().cloned()),
+                "--id" | "$menuentry_id_option" => id = args.next().cloned(),
+                "--users" | "--hotkey" => {
+                    args.next();
+                }
+                "--unrestricted" => {}
+                _ if title.is_none() => title = Some(arg.clone()),
+                // Arguments to be passed to the entry body.
+                _ => {}
+            }
+        }
+
+        let Some(title) = title else {
+            bail!(localized!(
+                pt_br: "Entrada sem título na linha {line_number}",
+                en: "Entry without a title on line {line_number}",
+            ));
+        };
+
+        Ok(MenuArgs { title, id, classes })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    /// An unquoted `{`.
+    Open,
+    /// An unquoted `}`.
+    Close,
+    /// A newline or a `;`.
+    Separator,
+}
+
+/// Splits the content in tokens, each one with its line number, following the quoting rules of
+/// the GRUB scripting language.
+fn tokenize(content: &str) -> Result<Vec<(Token, usize)>> {
+    let mut tokenizer = Tokenizer {
+        tokens: Vec::new(),
+        word: String::new(),
+        in_word: false,
+        quoted: false,
+        line_number: 1,
+    };
+
+    let mut chars = content.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' | '\r' => tokenizer.end_word(),
+            '\n' | ';' => tokenizer.end_command(c),
+            '#' if !tokenizer.in_word => {
+                // A comment, up to the end of the line.
+                if chars.by_ref().any(|c| c == '\n') {
+                    tokenizer.end_command('\n');
+                }
+            }
+            '\\' => match chars.next() {
+                Some('\n') => tokenizer.line_number += 1,
+                Some(c) => tokenizer.push_quoted(c),
+                None => {}
+            },
+            '\'' => {
+                tokenizer.start_quoted();
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => tokenizer.push_quoted(c),
+                        None => bail!(unclosed_quote_error(tokenizer.line_number)),
+                    }
+                }
+            }
+            '"' => {
+                tokenizer.start_quoted();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('\n') => tokenizer.line_number += 1,
+                            Some(c @ ('\\' | '"' | '$')) => tokenizer.push_quoted(c),
+                            Some(c) => {
+                                tokenizer.push_quoted('\\');
+                                tokenizer.push_quoted(c);
+                            }
+                            None => bail!(unclosed_quote_error(tokenizer.line_number)),
+                        },
+                        Some(c) => tokenizer.push_quoted(c),
+                        None => bail!(unclosed_quote_error(tokenizer.line_number)),
+                    }
+                }
+            }
+            c => {
+                tokenizer.word.push(c);
+                tokenizer.in_word = true;
+            }
+        }
+    }
+    tokenizer.end_word();
+
+    Ok(tokenizer.tokens)
+}
+
+struct Tokenizer {
+    tokens: Vec<(Token, usize)>,
+    word: String,
+    in_word: bool,
+    /// Whether any part of the current word is quoted or escaped.
+    quoted: bool,
+    line_number: usize,
+}
+impl Tokenizer {
+    fn start_quoted(&mut self) {
+        self.in_word = true;
+        self.quoted = true;
+    }
+
+    fn push_quoted(&mut self, c: char) {
+        self.start_quoted();
+        if c == '\n' {
+            self.line_number += 1;
+        }
+        self.word.push(c);
+    }
+
+    fn end_word(&mut self) {
+        if self.in_word {
+            let token = match self.word.as_str() {
+                "{" if !self.quoted => Token::Open,
+                "}" if !self.quoted => Token::Close,
+                _ => Token::Word(self.word.clone()),
+            };
+            self.tokens.push((token, self.line_number));
+        }
+        self.word.clear();
+        self.in_word = false;
+        self.quoted = false;
+    }
+
+    fn end_command(&mut self, separator: char) {
+        self.end_word();
+        self.tokens.push((Token::Separator, self.line_number));
+        if separator == '\n' {
+            self.line_number += 1;
+        }
+    }
+}
+
+fn unclosed_quote_error(line_number: usize) -> String {
+    localized!(
+        pt_br: "Aspas não fechadas a partir da linha {line_number}",
+        en: "Unclosed quotes from line {line_number}",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UBUNTU_WINDOWS: &str = include_str!("../../../tests/fixtures/grub/ubuntu-windows.cfg");
+    const MULTI_DISTRO: &str = include_str!("../../../tests/fixtures/grub/multi-distro.cfg");
+
+    #[test]
+    fn parse_submenus() -> Result<()> {
+        let entries = parse(UBUNTU_WINDOWS)?;
+
+        let paths: Vec<_> = entries.iter().map(|entry| entry.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "gnulinux-simple-9a8b7c6d",
+                "gnulinux-advanced-9a8b7c6d>gnulinux-6.8.0-45-generic-advanced-9a8b7c6d",
+                "gnulinux-advanced-9a8b7c6d>gnulinux-6.8.0-45-generic-recovery-9a8b7c6d",
+                "osprober-efi-1A2B-3C4D",
+                "uefi-firmware",
+            ]
+        );
+        assert_eq!(
+            entries[1].full_title(),
+            "Advanced options for Ubuntu > Ubuntu, with Linux 6.8.0-45-generic"
+        );
+        assert_eq!(entries[0].classes, ["ubuntu", "gnu-linux", "gnu", "os"]);
+        assert!(!entries[3].is_in_submenu());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_quoting() -> Result<()> {
+        let entries = parse(MULTI_DISTRO)?;
+
+        let arch = &entries[3];
+        assert_eq!(arch.title, r#"Arch Linux "rolling""#);
+        assert_eq!(arch.id, None);
+        assert_eq!(arch.path, arch.title);
+
+        let windows = &entries[4];
+        assert_eq!(windows.title, "Joe's Windows 11");
+        assert_eq!(windows.id.as_deref(), Some("osprober-efi-4444"));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(parse("menuentry 'Ubuntu {\n}\n").is_err());
+        assert!(parse("menuentry 'Ubuntu' {\n").is_err());
+        assert!(parse("}\n").is_err());
+        assert!(parse("menuentry --class os {\n}\n").is_err());
+    }
+}
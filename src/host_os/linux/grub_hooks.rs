@@ -0,0 +1,500 @@
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+
+use crate::args::GrubHooksAction;
+use crate::persist::atomic_write;
+use crate::text::localized;
+
+const GRUB_D_DIR: &str = "/etc/grub.d";
+/// The scripts in [`GRUB_D_DIR`] that are edited, as described in `GRUB-CONFIGURATION.md`.
+const SCRIPTS: [&str; 2] = [LOAD_ENV_SCRIPT, "10_linux"];
+/// The script with the block that loads the `grubenv`.
+const LOAD_ENV_SCRIPT: &str = "00_header";
+const BACKUP_SUFFIX: &str = ".bkp";
+
+const QUICK_BOOT_ENABLED: &str = r#"quick_boot="1""#;
+const QUICK_BOOT_DISABLED: &str = r#"quick_boot="0""#;
+/// The block that loads the `grubenv` of the boot partition, without its indentation.
+const LOAD_ENV_BLOCK: [&str; 4] = [
+    r"if [ -s \$prefix/grubenv ]; then",
+    "  set have_grubenv=true",
+    "  load_env",
+    "fi",
+];
+const HAVE_GRUBENV: &str = "set have_grubenv=true";
+const LOAD_ENV: &str = "load_env";
+const SAVE_ENV: &str = "save_env";
+const GRUB_ID_PLACEHOLDER: &str = "<GRUB-ID>";
+
+/// Edits the GRUB scripts so that they use the shared `grubenv`, checks whether they're edited, or
+/// undoes the edits.
+pub fn install_hooks(action: &GrubHooksAction) -> Result<()> {
+    run(Path::new(GRUB_D_DIR), action)
+}
+
+fn run(grub_d: &Path, action: &GrubHooksAction) -> Result<()> {
+    let mut pending = false;
+    let mut changed = false;
+
+    for script in SCRIPTS {
+        let path = grub_d.join(script);
+        match action {
+            GrubHooksAction::Install { grub_id } => changed |= install_script(&path, grub_id)?,
+            GrubHooksAction::Check => pending |= check_script(&path)?,
+            GrubHooksAction::Undo => changed |= restore_script(&path)?,
+        }
+    }
+
+    if pending {
+        bail!(localized!(
+            pt_br: "Os scripts do Grub não estão alterados. Execute: sudo my-reboot grub install-hooks --grub-id {GRUB_ID_PLACEHOLDER}",
+            en: "The Grub scripts aren't edited. Run: sudo my-reboot grub install-hooks --grub-id {GRUB_ID_PLACEHOLDER}",
+        ));
+    }
+    if changed {
+        println!(
+            "{}",
+            localized!(
+                pt_br: "Execute para aplicar: sudo update-grub",
+                en: "Run to apply: sudo update-grub",
+            )
+        );
+    }
+    Ok(())
+}
+
+/// Returns whether the script was changed.
+fn install_script(path: &Path, grub_id: &str) -> Result<bool> {
+    let patched = patch_script(path, Some(grub_id))?;
+    let display_path = path.display();
+    let lines = patched.changed_lines;
+    if lines == 0 {
+        let status = localized!(pt_br: "alterações já aplicadas", en: "edits already applied");
+        println!("{display_path}: {status}");
+        return Ok(false);
+    }
+
+    back_up(path)?;
+    write(path, &patched.content)?;
+    println!(
+        "{}",
+        localized!(
+            pt_br: "{display_path}: {lines} linha(s) alterada(s)",
+            en: "{display_path}: {lines} line(s) changed",
+        )
+    );
+    Ok(true)
+}
+
+/// Returns whether the edits are pending.
+fn check_script(path: &Path) -> Result<bool> {
+    let pending = patch_script(path, None)?.changed_lines > 0;
+    let status = if pending {
+        localized!(pt_br: "❌ Alterações pendentes", en: "❌ Edits pending")
+    } else {
+        localized!(pt_br: "✅ Alterações aplicadas", en: "✅ Edits applied")
+    };
+    println!("{}: {status}", path.display());
+    Ok(pending)
+}
+
+/// Restores the script from the backup made before the first edits, so that only what was there
+/// before is kept. The backup is then removed, so that another one is made by the next install.
+/// Returns whether the script was changed.
+fn restore_script(path: &Path) -> Result<bool> {
+    let display_path = path.display();
+    let backup_path = backup_path(path);
+    if !backup_path.exists() {
+        let status = localized!(
+            pt_br: "nenhum backup para restaurar",
+            en: "no backup to restore",
+        );
+        println!("{display_path}: {status}");
+        return Ok(false);
+    }
+
+    write(path, &read(&backup_path)?)?;
+    fs::remove_file(&backup_path).with_context(|| cannot_write_message(&backup_path))?;
+    let backup_path = backup_path.display();
+    println!(
+        "{}",
+        localized!(
+            pt_br: "{display_path}: restaurado de {backup_path}",
+            en: "{display_path}: restored from {backup_path}",
+        )
+    );
+    Ok(true)
+}
+
+/// The scripts whose edits aren't applied.
+pub(crate) fn pending_scripts() -> Result<Vec<PathBuf>> {
+    let mut pending = Vec::new();
+    for script in SCRIPTS {
+        let path = Path::new(GRUB_D_DIR).join(script);
+        if patch_script(&path, None)?.changed_lines > 0 {
+            pending.push(path);
+        }
+    }
+    Ok(pending)
+}
+
+/// Applies the edits. Without a GRUB-ID, the ones already in the script are kept.
+fn patch_script(path: &Path, grub_id: Option<&str>) -> Result<Patched> {
+    let content = read(path)?;
+    let patched = patch(&content, grub_id);
+    if path.ends_with(LOAD_ENV_SCRIPT) && patched.load_env_blocks == 0 {
+        let path = path.display();
+        bail!(localized!(
+            pt_br: "O bloco que carrega o grubenv não foi encontrado em {path}. Edite-o manualmente conforme GRUB-CONFIGURATION.md.",
+            en: "The block that loads the grubenv wasn't found in {path}. Edit it by hand as in GRUB-CONFIGURATION.md.",
+        ));
+    }
+    Ok(patched)
+}
+
+#[derive(Debug, Default)]
+struct Patched {
+    content: String,
+    changed_lines: usize,
+    /// How many blocks that load the `grubenv` were found, edited or not.
+    load_env_blocks: usize,
+}
+impl Patched {
+    fn push(&mut self, line: &str, new_line: &str) {
+        if line != new_line {
+            self.changed_lines += 1;
+        }
+        self.content.push_str(new_line);
+    }
+
+    fn push_indented(&mut self, indent: &str, line: &str) {
+        self.content.push_str(indent);
+        self.content.push_str(line);
+        self.content.push('\n');
+    }
+}
+
+fn patch(content: &str, grub_id: Option<&str>) -> Patched {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let mut patched = Patched::default();
+
+    let mut i = 0;
+    while i < lines.len() {
+        if let Some(indent) = original_load_env_block(&lines[i..]) {
+            patched.load_env_blocks += 1;
+            let file_option = file_option(grub_id.unwrap_or(GRUB_ID_PLACEHOLDER));
+            patched.push_indented(indent, HAVE_GRUBENV);
+            patched.push_indented(indent, &format!("{LOAD_ENV} {file_option}"));
+            patched.changed_lines += LOAD_ENV_BLOCK.len();
+            i += LOAD_ENV_BLOCK.len();
+            continue;
+        } else if edited_load_env_block(&lines[i..]).is_some() {
+            patched.load_env_blocks += 1;
+        }
+
+        let line = lines[i];
+        let new_line = line.replace(QUICK_BOOT_ENABLED, QUICK_BOOT_DISABLED);
+        let new_line = set_file_options(&new_line, LOAD_ENV, grub_id, false);
+        let new_line = set_file_options(&new_line, SAVE_ENV, grub_id, true);
+        patched.push(line, &new_line);
+        i += 1;
+    }
+
+    patched
+}
+
+/// The indentation of the [`LOAD_ENV_BLOCK`] the lines start with.
+fn original_load_env_block<'a>(lines: &[&'a str]) -> Option<&'a str> {
+    let first = lines.first()?;
+    let indent = indentation(first);
+    let matches = lines.len() >= LOAD_ENV_BLOCK.len()
+        && lines
+            .iter()
+            .zip(LOAD_ENV_BLOCK)
+            .all(|(line, block_line)| line.trim_end() == format!("{indent}{block_line}"));
+    matches.then_some(indent)
+}
+
+/// The indentation of the edited [`LOAD_ENV_BLOCK`] the lines start with.
+fn edited_load_env_block<'a>(lines: &[&'a str]) -> Option<&'a str> {
+    let [first, second, ..] = lines else {
+        return None;
+    };
+    let indent = indentation(first);
+    let load_env = second.trim_end().strip_prefix(indent)?;
+    let matches = first.trim_end() == format!("{indent}{HAVE_GRUBENV}")
+        && load_env
+            .strip_prefix(LOAD_ENV)
+            .and_then(parse_file_option)
+            .is_some_and(|(_, rest)| rest.is_empty());
+    matches.then_some(indent)
+}
+
+fn indentation(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+fn file_option(grub_id: &str) -> String {
+    format!("--file ({grub_id})/grubenv")
+}
+
+/// Parses the ` --file (<GRUB-ID>)/grubenv` that follows a command, returning the GRUB-ID and what
+/// follows the option.
+fn parse_file_option(text: &str) -> Option<(&str, &str)> {
+    let text = text.strip_prefix(" --file (")?;
+    let end = text.find(")/grubenv")?;
+    Some((&text[..end], &text[end + ")/grubenv".len()..]))
+}
+
+/// Sets the GRUB-ID of the `--file` option of the command, or adds the option if `insert` is set.
+fn set_file_options(line: &str, command: &str, grub_id: Option<&str>, insert: bool) -> String {
+    edit_file_options(line, command, |current_grub_id| match current_grub_id {
+        Some(current_grub_id) => Some(file_option(grub_id.unwrap_or(current_grub_id))),
+        None if insert => Some(file_option(grub_id.unwrap_or(GRUB_ID_PLACEHOLDER))),
+        None => None,
+    })
+}
+
+/// Replaces the `--file` option of each occurrence of the command with the one returned by `f`,
+/// which is given the current GRUB-ID, if the option is there.
+fn edit_file_options(
+    line: &str,
+    command: &str,
+    f: impl Fn(Option<&str>) -> Option<String>,
+) -> String {
+    let mut edited = String::new();
+    let mut rest = line;
+    while let Some(index) = find_command(rest, command) {
+        let end = index + command.len();
+        edited.push_str(&rest[..end]);
+        rest = &rest[end..];
+
+        let current_grub_id = match parse_file_option(rest) {
+            Some((current_grub_id, after)) => {
+                rest = after;
+                Some(current_grub_id)
+            }
+            None => None,
+        };
+        if let Some(option) = f(current_grub_id) {
+            edited.push(' ');
+            edited.push_str(&option);
+        }
+    }
+    edited.push_str(rest);
+    edited
+}
+
+/// Finds the command as a whole word.
+fn find_command(text: &str, command: &str) -> Option<usize> {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+    text.match_indices(command)
+        .map(|(index, _)| index)
+        .find(|&index| {
+            let before = text[..index].chars().next_back();
+            let after = text[index + command.len()..].chars().next();
+            !before.is_some_and(is_word_char) && !after.is_some_and(is_word_char)
+        })
+}
+
+fn read(path: &Path) -> Result<String> {
+    fs::read_to_string(path).with_context(|| {
+        let path = path.display();
+        localized!(
+            pt_br: "Não foi possível ler {path}",
+            en: "Couldn't read {path}",
+        )
+    })
+}
+
+fn write(path: &Path, content: &str) -> Result<()> {
+    atomic_write::write(path, content).with_context(|| cannot_write_message(path))
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+    let mut backup_path = path.as_os_str().to_owned();
+    backup_path.push(BACKUP_SUFFIX);
+    PathBuf::from(backup_path)
+}
+
+/// Copies the script to a non-executable backup, unless there's already one, which then keeps the
+/// script as it was before any edits.
+fn back_up(path: &Path) -> Result<()> {
+    let backup_path = backup_path(path);
+    if backup_path.exists() {
+        return Ok(());
+    }
+
+    let mut permissions = fs::metadata(path)?.permissions();
+    permissions.set_mode(permissions.mode() & !0o111);
+    fs::copy(path, &backup_path)
+        .and_then(|_| fs::set_permissions(&backup_path, permissions))
+        .with_context(|| cannot_write_message(&backup_path))
+}
+
+fn cannot_write_message(path: &Path) -> String {
+    let path = path.display();
+    localized!(
+        pt_br: "Não foi possível gravar {path}. É preciso executar como root.",
+        en: "Couldn't write {path}. It must be run as root.",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = include_str!("../../../tests/fixtures/grub.d/00_header");
+    const LINUX: &str = include_str!("../../../tests/fixtures/grub.d/10_linux");
+
+    fn grub_d() -> Result<tempfile::TempDir> {
+        let dir = tempfile::tempdir()?;
+        for (script, content) in [("00_header", HEADER), ("10_linux", LINUX)] {
+            let path = dir.path().join(script);
+            fs::write(&path, content)?;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+        }
+        Ok(dir)
+    }
+
+    fn install() -> GrubHooksAction {
+        GrubHooksAction::Install {
+            grub_id: "hd0,gpt5".to_string(),
+        }
+    }
+
+    #[test]
+    fn install_hooks() -> Result<()> {
+        let dir = grub_d()?;
+
+        assert!(run(dir.path(), &GrubHooksAction::Check).is_err());
+        run(dir.path(), &install())?;
+
+        let header = fs::read_to_string(dir.path().join("00_header"))?;
+        assert!(header.contains(
+            "cat << EOF\nset have_grubenv=true\nload_env --file (hd0,gpt5)/grubenv\nEOF\n"
+        ));
+        assert!(header.contains(r#"quick_boot="0""#));
+        assert!(!header.contains(r#"quick_boot="1""#));
+        assert_eq!(header.matches("save_env ").count(), 6);
+        assert_eq!(
+            header
+                .matches("save_env --file (hd0,gpt5)/grubenv ")
+                .count(),
+            6
+        );
+        assert!(header.contains("then save_env --file (hd0,gpt5)/grubenv recordfail; fi; fi"));
+        let linux = fs::read_to_string(dir.path().join("10_linux"))?;
+        assert_eq!(
+            linux,
+            LINUX.replace(r#"quick_boot="1""#, r#"quick_boot="0""#)
+        );
+
+        // The backups are the original scripts, and aren't executable.
+        let backup_path = dir.path().join("00_header.bkp");
+        assert_eq!(fs::read_to_string(&backup_path)?, HEADER);
+        assert_eq!(
+            fs::metadata(&backup_path)?.permissions().mode() & 0o777,
+            0o644
+        );
+        assert_eq!(
+            fs::metadata(dir.path().join("00_header"))?
+                .permissions()
+                .mode()
+                & 0o777,
+            0o755
+        );
+
+        run(dir.path(), &GrubHooksAction::Check)?;
+        Ok(())
+    }
+
+    #[test]
+    fn install_hooks_is_idempotent() -> Result<()> {
+        let dir = grub_d()?;
+        run(dir.path(), &install())?;
+        let header = fs::read_to_string(dir.path().join("00_header"))?;
+
+        assert_eq!(patch(&header, Some("hd0,gpt5")).changed_lines, 0);
+        run(dir.path(), &install())?;
+        assert_eq!(fs::read_to_string(dir.path().join("00_header"))?, header);
+
+        // Another GRUB-ID replaces the one in use, and the backup is kept.
+        run(
+            dir.path(),
+            &GrubHooksAction::Install {
+                grub_id: "hd1,gpt2".to_string(),
+            },
+        )?;
+        let header = fs::read_to_string(dir.path().join("00_header"))?;
+        assert!(!header.contains("hd0,gpt5"));
+        assert_eq!(header.matches("(hd1,gpt2)/grubenv").count(), 7);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("00_header.bkp"))?,
+            HEADER
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn undo_hooks() -> Result<()> {
+        let dir = grub_d()?;
+        run(dir.path(), &install())?;
+
+        run(dir.path(), &GrubHooksAction::Undo)?;
+
+        assert_eq!(fs::read_to_string(dir.path().join("00_header"))?, HEADER);
+        assert_eq!(fs::read_to_string(dir.path().join("10_linux"))?, LINUX);
+        assert!(!dir.path().join("00_header.bkp").exists());
+        assert_eq!(
+            fs::metadata(dir.path().join("00_header"))?
+                .permissions()
+                .mode()
+                & 0o777,
+            0o755
+        );
+        assert!(run(dir.path(), &GrubHooksAction::Check).is_err());
+
+        run(dir.path(), &GrubHooksAction::Undo)?;
+        assert_eq!(fs::read_to_string(dir.path().join("00_header"))?, HEADER);
+        Ok(())
+    }
+
+    #[test]
+    fn undo_hooks_keeps_lines_not_edited_by_install() -> Result<()> {
+        // Lines like the ones the edits produce, but that were already in the original script.
+        let original =
+            format!("{LINUX}quick_boot=\"0\"\nsave_env --file (hd9,gpt9)/grubenv custom\n");
+        let dir = grub_d()?;
+        fs::write(dir.path().join("10_linux"), &original)?;
+        run(dir.path(), &install())?;
+
+        run(dir.path(), &GrubHooksAction::Undo)?;
+
+        assert_eq!(fs::read_to_string(dir.path().join("10_linux"))?, original);
+        Ok(())
+    }
+
+    #[test]
+    fn load_env_block_not_found() -> Result<()> {
+        let dir = grub_d()?;
+        fs::write(
+            dir.path().join("00_header"),
+            HEADER.replace("  load_env\n", "  load_env -f /boot/grubenv\n"),
+        )?;
+
+        assert!(run(dir.path(), &install()).is_err());
+        assert!(!dir.path().join("10_linux.bkp").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn commands_are_found_as_whole_words() {
+        assert_eq!(find_command("x save_env y", SAVE_ENV), Some(2));
+        assert_eq!(find_command("save_env;", SAVE_ENV), Some(0));
+        assert_eq!(find_command("grub-save_env save_envs", SAVE_ENV), None);
+    }
+}
@@ -0,0 +1,489 @@
+use std::fmt::Display;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::persist::configs::CONFIGS_FILENAME;
+use crate::persist::grubenv::{GRUBENV_FILENAME, Grubenv};
+use crate::text::localized;
+
+const FAT_FILESYSTEMS: [&str; 2] = ["vfat", "msdos"];
+/// Created and removed to tell whether a mounted partition is writable.
+const WRITE_TEST_FILENAME: &str = ".my-reboot-write-test";
+
+/// Where the information about the partitions is read from.
+pub(crate) struct SystemPaths {
+    pub(crate) proc_mounts: PathBuf,
+    pub(crate) disk_by_uuid_dir: PathBuf,
+    pub(crate) grub_dir: PathBuf,
+}
+impl Default for SystemPaths {
+    fn default() -> Self {
+        Self {
+            proc_mounts: PathBuf::from("/proc/mounts"),
+            disk_by_uuid_dir: PathBuf::from("/dev/disk/by-uuid"),
+            grub_dir: PathBuf::from("/boot/grub"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Partition {
+    pub(crate) device: PathBuf,
+    pub(crate) uuid: Option<String>,
+}
+
+/// A mounted FAT partition that holds a valid `grubenv`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct GrubenvPartition {
+    pub(crate) partition: Partition,
+    pub(crate) mount_point: PathBuf,
+    pub(crate) writable: bool,
+    pub(crate) has_configs: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum GrubenvLink {
+    Missing,
+    /// A regular file, so GRUB doesn't use the shared one.
+    NotSymlink,
+    Symlink {
+        target: PathBuf,
+        /// Whether it resolves to the `grubenv` of a [`GrubenvPartition`].
+        to_partition: bool,
+    },
+}
+
+/// What was found about the shared `grubenv` partition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Discovery {
+    pub(crate) grubenv_partitions: Vec<GrubenvPartition>,
+    /// The partitions that aren't mounted and whose UUIDs are like the ones of FAT file systems,
+    /// so they may be the shared partition, but their content can't be checked.
+    pub(crate) unmounted_fat_partitions: Vec<Partition>,
+    pub(crate) grubenv_link: GrubenvLink,
+}
+impl Discovery {
+    pub(crate) fn scan(paths: &SystemPaths) -> io::Result<Discovery> {
+        let mounts = parse_mounts(&fs::read_to_string(&paths.proc_mounts)?);
+        let uuids = read_uuids(&paths.disk_by_uuid_dir)?;
+        let uuid_of = |device: &Path| {
+            uuids
+                .iter()
+                .find(|(_, uuid_device)| uuid_device == device)
+                .map(|(uuid, _)| uuid.clone())
+        };
+
+        let grubenv_partitions: Vec<_> = mounts
+            .iter()
+            .filter(|mount| FAT_FILESYSTEMS.contains(&mount.fs_type.as_str()))
+            .filter(|mount| Grubenv::load_from(&mount.path.join(GRUBENV_FILENAME)).is_ok())
+            .map(|mount| GrubenvPartition {
+                partition: Partition {
+                    device: mount.device.clone(),
+                    uuid: uuid_of(&mount.device),
+                },
+                mount_point: mount.path.clone(),
+                writable: mount.read_write && is_writable(&mount.path),
+                has_configs: mount.path.join(CONFIGS_FILENAME).is_file(),
+            })
+            .collect();
+
+        let unmounted_fat_partitions = uuids
+            .into_iter()
+            .filter(|(uuid, _)| is_fat_uuid(uuid))
+            .filter(|(_, device)| !mounts.iter().any(|mount| mount.device == *device))
+            .map(|(uuid, device)| Partition {
+                device,
+                uuid: Some(uuid),
+            })
+            .collect();
+
+        let grubenv_link = grubenv_link(&paths.grub_dir, &grubenv_partitions);
+
+        Ok(Discovery {
+            grubenv_partitions,
+            unmounted_fat_partitions,
+            grubenv_link,
+        })
+    }
+
+    /// The partition to be mounted at the state directory: the one found, or the single
+    /// candidate.
+    fn partition(&self) -> Option<&Partition> {
+        match (
+            &self.grubenv_partitions[..],
+            &self.unmounted_fat_partitions[..],
+        ) {
+            ([grubenv_partition], _) => Some(&grubenv_partition.partition),
+            ([], [partition]) => Some(partition),
+            _ => None,
+        }
+    }
+
+    /// The `/etc/fstab` line that mounts the partition at the given directory.
+    pub(crate) fn fstab_line(&self, mount_point: &Path) -> Option<String> {
+        let uuid = self.partition()?.uuid.as_ref()?;
+        Some(format!(
+            "UUID={uuid}  {}  vfat  defaults,umask=0000  0  1",
+            escape_mount_path(mount_point)
+        ))
+    }
+}
+impl Display for Discovery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for grubenv_partition in &self.grubenv_partitions {
+            let partition = &grubenv_partition.partition;
+            let mount_point = grubenv_partition.mount_point.display();
+            writeln!(
+                f,
+                "{}",
+                localized!(
+                    pt_br: "Partição com o grubenv: {partition}, montada em {mount_point}",
+                    en: "Partition with the grubenv: {partition}, mounted at {mount_point}",
+                )
+            )?;
+            if !grubenv_partition.writable {
+                writeln!(
+                    f,
+                    "  {}",
+                    localized!(pt_br: "Não permite gravação", en: "Not writable")
+                )?;
+            }
+            if !grubenv_partition.has_configs {
+                writeln!(
+                    f,
+                    "  {}",
+                    localized!(
+                        pt_br: "Sem o arquivo {CONFIGS_FILENAME}",
+                        en: "Without the file {CONFIGS_FILENAME}",
+                    )
+                )?;
+            }
+        }
+
+        if self.grubenv_partitions.is_empty() {
+            writeln!(
+                f,
+                "{}",
+                localized!(
+                    pt_br: "Nenhuma partição FAT montada com um grubenv válido",
+                    en: "No mounted FAT partition with a valid grubenv",
+                )
+            )?;
+            for partition in &self.unmounted_fat_partitions {
+                writeln!(
+                    f,
+                    "  {}",
+                    localized!(
+                        pt_br: "Partição FAT não montada: {partition}",
+                        en: "Unmounted FAT partition: {partition}",
+                    )
+                )?;
+            }
+        }
+
+        match &self.grubenv_link {
+            GrubenvLink::Missing => writeln!(
+                f,
+                "{}",
+                localized!(
+                    pt_br: "/boot/grub/grubenv não existe",
+                    en: "/boot/grub/grubenv doesn't exist",
+                )
+            ),
+            GrubenvLink::NotSymlink => writeln!(
+                f,
+                "{}",
+                localized!(
+                    pt_br: "/boot/grub/grubenv não é um link simbólico para a partição",
+                    en: "/boot/grub/grubenv isn't a symlink to the partition",
+                )
+            ),
+            GrubenvLink::Symlink {
+                target,
+                to_partition,
+            } => {
+                let target = target.display();
+                if *to_partition {
+                    writeln!(f, "/boot/grub/grubenv -> {target}")
+                } else {
+                    writeln!(
+                        f,
+                        "{}",
+                        localized!(
+                            pt_br: "/boot/grub/grubenv -> {target}, que não é o grubenv da partição",
+                            en: "/boot/grub/grubenv -> {target}, which isn't the grubenv of the partition",
+                        )
+                    )
+                }
+            }
+        }
+    }
+}
+impl Display for Partition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.device.display())?;
+        if let Some(uuid) = &self.uuid {
+            write!(f, " (UUID={uuid})")?;
+        }
+        Ok(())
+    }
+}
+
+struct Mount {
+    device: PathBuf,
+    path: PathBuf,
+    fs_type: String,
+    read_write: bool,
+}
+
+/// Parses the content of `/proc/mounts`, which has the same format as `/etc/fstab`.
+fn parse_mounts(content: &str) -> Vec<Mount> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let device = unescape_mount_path(fields.next()?);
+            let path = unescape_mount_path(fields.next()?);
+            let fs_type = fields.next()?.to_string();
+            let read_write = fields.next()?.split(',').any(|option| option == "rw");
+            Some(Mount {
+                device: fs::canonicalize(&device).unwrap_or(device),
+                path,
+                fs_type,
+                read_write,
+            })
+        })
+        .collect()
+}
+
+/// The UUIDs in the directory, with the devices their links resolve to.
+fn read_uuids(dir: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut uuids = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        if let (Ok(uuid), Ok(device)) = (
+            entry.file_name().into_string(),
+            fs::canonicalize(entry.path()),
+        ) {
+            uuids.push((uuid, device));
+        }
+    }
+    uuids.sort();
+    Ok(uuids)
+}
+
+/// FAT file systems have 32-bit volume ids, shown like `1A2B-3C4D`.
+fn is_fat_uuid(uuid: &str) -> bool {
+    uuid.len() == 9
+        && uuid.char_indices().all(|(i, c)| {
+            if i == 4 {
+                c == '-'
+            } else {
+                c.is_ascii_hexdigit()
+            }
+        })
+}
+
+fn is_writable(dir: &Path) -> bool {
+    let path = dir.join(WRITE_TEST_FILENAME);
+    let created = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .is_ok();
+    if created {
+        let _ = fs::remove_file(&path);
+    }
+    created
+}
+
+fn grubenv_link(grub_dir: &Path, grubenv_partitions: &[GrubenvPartition]) -> GrubenvLink {
+    let path = grub_dir.join(GRUBENV_FILENAME);
+    match fs::read_link(&path) {
+        Ok(target) => {
+            let resolved = fs::canonicalize(&path).ok();
+            let to_partition = grubenv_partitions.iter().any(|grubenv_partition| {
+                fs::canonicalize(grubenv_partition.mount_point.join(GRUBENV_FILENAME)).ok()
+                    == resolved
+            });
+            GrubenvLink::Symlink {
+                target,
+                to_partition: resolved.is_some() && to_partition,
+            }
+        }
+        Err(_) if path.exists() => GrubenvLink::NotSymlink,
+        Err(_) => GrubenvLink::Missing,
+    }
+}
+
+/// Undoes the octal escapes of spaces, tabs, newlines and backslashes in `/proc/mounts`.
+fn unescape_mount_path(field: &str) -> PathBuf {
+    let mut unescaped = String::new();
+    let mut rest = field;
+    while let Some(index) = rest.find('\\') {
+        unescaped.push_str(&rest[..index]);
+        let escape = rest.get(index + 1..index + 4);
+        if let Some(byte) = escape.and_then(|octal| u8::from_str_radix(octal, 8).ok()) {
+            unescaped.push(char::from(byte));
+            rest = &rest[index + 4..];
+        } else {
+            unescaped.push('\\');
+            rest = &rest[index + 1..];
+        }
+    }
+    unescaped.push_str(rest);
+    PathBuf::from(unescaped)
+}
+
+fn escape_mount_path(path: &Path) -> String {
+    path.display()
+        .to_string()
+        .replace('\\', "\\134")
+        .replace(' ', "\\040")
+        .replace('\t', "\\011")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::symlink;
+
+    use super::*;
+
+    const GRUBENV_CONTENT: &str = "# GRUB Environment Block\nsaved_entry=linux\n";
+
+    struct System {
+        dir: tempfile::TempDir,
+        paths: SystemPaths,
+    }
+    impl System {
+        fn new() -> Self {
+            let dir = tempfile::tempdir().unwrap();
+            let paths = SystemPaths {
+                proc_mounts: dir.path().join("mounts"),
+                disk_by_uuid_dir: dir.path().join("by-uuid"),
+                grub_dir: dir.path().join("grub"),
+            };
+            for dir in [&paths.disk_by_uuid_dir, &paths.grub_dir] {
+                fs::create_dir(dir).unwrap();
+            }
+            fs::write(&paths.proc_mounts, "").unwrap();
+            System { dir, paths }
+        }
+
+        /// Creates a device file and its link by UUID.
+        fn device(&self, name: &str, uuid: &str) -> PathBuf {
+            let device = self.dir.path().join(name);
+            fs::write(&device, "").unwrap();
+            symlink(&device, self.paths.disk_by_uuid_dir.join(uuid)).unwrap();
+            device
+        }
+
+        /// Creates a mount point and its line in the mounts file.
+        fn mount(&self, device: &Path, name: &str, fs_type: &str) -> PathBuf {
+            let mount_point = self.dir.path().join(name);
+            fs::create_dir(&mount_point).unwrap();
+            let mut mounts = fs::read_to_string(&self.paths.proc_mounts).unwrap();
+            let line = format!(
+                "{} {} {fs_type} rw,relatime 0 0\n",
+                device.display(),
+                escape_mount_path(&mount_point),
+            );
+            mounts.push_str(&line);
+            fs::write(&self.paths.proc_mounts, mounts).unwrap();
+            mount_point
+        }
+    }
+
+    #[test]
+    fn discover_mounted_grubenv_partition() -> io::Result<()> {
+        let system = System::new();
+        let device = system.device("sda5", "1A2B-3C4D");
+        let mount_point = system.mount(&device, "grubenv dir", "vfat");
+        fs::write(mount_point.join(GRUBENV_FILENAME), GRUBENV_CONTENT)?;
+        fs::write(mount_point.join(CONFIGS_FILENAME), "")?;
+        symlink(
+            mount_point.join(GRUBENV_FILENAME),
+            system.paths.grub_dir.join(GRUBENV_FILENAME),
+        )?;
+        // A FAT partition without a grubenv, like the EFI one.
+        let efi_device = system.device("sda1", "ABCD-0123");
+        let efi_mount_point = system.mount(&efi_device, "efi", "vfat");
+        fs::write(efi_mount_point.join("other"), "")?;
+
+        let discovery = Discovery::scan(&system.paths)?;
+
+        assert_eq!(
+            discovery.grubenv_partitions,
+            [GrubenvPartition {
+                partition: Partition {
+                    device,
+                    uuid: Some("1A2B-3C4D".to_string()),
+                },
+                mount_point: mount_point.clone(),
+                writable: true,
+                has_configs: true,
+            }]
+        );
+        assert!(discovery.unmounted_fat_partitions.is_empty());
+        assert!(matches!(
+            discovery.grubenv_link,
+            GrubenvLink::Symlink {
+                to_partition: true,
+                ..
+            }
+        ));
+        assert!(!mount_point.join(WRITE_TEST_FILENAME).exists());
+        assert_eq!(
+            discovery.fstab_line(Path::new("/boot/grub/grubenv.dir")),
+            Some(
+                "UUID=1A2B-3C4D  /boot/grub/grubenv.dir  vfat  defaults,umask=0000  0  1"
+                    .to_string()
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn discover_unmounted_partition() -> io::Result<()> {
+        let system = System::new();
+        let device = system.device("sda5", "1A2B-3C4D");
+        system.device("sda2", "0a1b2c3d-0000-1111-2222-333344445555");
+        fs::write(
+            system.paths.grub_dir.join(GRUBENV_FILENAME),
+            GRUBENV_CONTENT,
+        )?;
+
+        let discovery = Discovery::scan(&system.paths)?;
+
+        assert!(discovery.grubenv_partitions.is_empty());
+        assert_eq!(
+            discovery.unmounted_fat_partitions,
+            [Partition {
+                device,
+                uuid: Some("1A2B-3C4D".to_string()),
+            }]
+        );
+        assert_eq!(discovery.grubenv_link, GrubenvLink::NotSymlink);
+        assert_eq!(
+            discovery.fstab_line(Path::new("/mnt/grub env")),
+            Some("UUID=1A2B-3C4D  /mnt/grub\\040env  vfat  defaults,umask=0000  0  1".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unescape_mount_paths() {
+        assert_eq!(
+            unescape_mount_path(r"/mnt/grub\040env\134x\9"),
+            PathBuf::from(r"/mnt/grub env\x\9")
+        );
+    }
+}
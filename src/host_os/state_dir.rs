@@ -0,0 +1,242 @@
+use std::error::Error;
+use std::ffi::OsString;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::{env, fs, io};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{DEFAULT_STATE_DIR_PATH, user_config_dir};
+use crate::text::localized;
+
+/// Overrides the state directory of the per-user config file and of the build.
+const STATE_DIR_ENV_VAR: &str = "MY_REBOOT_STATE_DIR";
+const USER_CONFIG_DIRNAME: &str = "my-reboot";
+const USER_CONFIG_FILENAME: &str = "config.toml";
+
+static STATE_DIR: OnceLock<StateDir> = OnceLock::new();
+
+/// The directory with the state files, shared by the operating systems.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct StateDir {
+    pub(crate) path: PathBuf,
+    #[serde(flatten)]
+    pub(crate) source: StateDirSource,
+}
+
+/// Where the state directory was chosen from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "source", content = "file")]
+pub(crate) enum StateDirSource {
+    /// The `--state-dir` option.
+    Argument,
+    /// The [`STATE_DIR_ENV_VAR`] environment variable.
+    EnvironmentVariable,
+    /// The `state_dir` key of the per-user config file.
+    UserConfig(PathBuf),
+    /// The path the executable was built with.
+    Build,
+}
+impl Display for StateDirSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StateDirSource::Argument => write!(f, "--state-dir"),
+            StateDirSource::EnvironmentVariable => write!(f, "{STATE_DIR_ENV_VAR}"),
+            StateDirSource::UserConfig(path) => write!(f, "{}", path.display()),
+            StateDirSource::Build => write!(
+                f,
+                "{}",
+                localized!(pt_br: "padrão da compilação", en: "build default")
+            ),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct UserConfig {
+    state_dir: Option<PathBuf>,
+}
+
+/// The per-user config file can't be read.
+///
+/// Its message is only localized when it's shown, because detecting the locale may need the state
+/// directory, which would then be resolved again.
+#[derive(Debug)]
+pub(crate) struct UserConfigError {
+    path: PathBuf,
+    source: Box<dyn Error + Send + Sync>,
+}
+impl Error for UserConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+impl Display for UserConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = self.path.display();
+        f.write_str(&localized!(
+            pt_br: "Arquivo de configuração do usuário inválido: {path}",
+            en: "Invalid user config file: {path}",
+        ))
+    }
+}
+
+/// Resolves the state directory from the `--state-dir` option, the environment and the per-user
+/// config file. Must be called before the state files are accessed.
+pub(crate) fn init_state_dir(option: Option<PathBuf>) -> Result<()> {
+    let state_dir = resolve(option, env::var_os(STATE_DIR_ENV_VAR), user_config_path())?;
+    // It's only set before if the state directory was needed while parsing the `--state-dir`
+    // option.
+    let _ = STATE_DIR.set(state_dir);
+    Ok(())
+}
+
+/// The state directory, if it's already resolved.
+#[cfg(not(test))]
+pub(crate) fn resolved_state_dir() -> Option<&'static StateDir> {
+    STATE_DIR.get()
+}
+
+/// The resolved state directory, or the one of the build if it can't be resolved.
+pub(crate) fn state_dir() -> &'static StateDir {
+    STATE_DIR.get_or_init(|| {
+        resolve(None, env::var_os(STATE_DIR_ENV_VAR), user_config_path())
+            .unwrap_or_else(|_| build_state_dir())
+    })
+}
+
+fn resolve(
+    option: Option<PathBuf>,
+    env_var: Option<OsString>,
+    user_config_path: Option<PathBuf>,
+) -> Result<StateDir, UserConfigError> {
+    if let Some(path) = option {
+        return Ok(StateDir {
+            path,
+            source: StateDirSource::Argument,
+        });
+    }
+
+    if let Some(path) = env_var.filter(|value| !value.is_empty()) {
+        return Ok(StateDir {
+            path: path.into(),
+            source: StateDirSource::EnvironmentVariable,
+        });
+    }
+
+    if let Some(user_config_path) = user_config_path
+        && let Some(path) = read_user_config(&user_config_path)?
+    {
+        return Ok(StateDir {
+            path,
+            source: StateDirSource::UserConfig(user_config_path),
+        });
+    }
+
+    Ok(build_state_dir())
+}
+
+fn read_user_config(path: &Path) -> Result<Option<PathBuf>, UserConfigError> {
+    let error = |source: Box<dyn Error + Send + Sync>| UserConfigError {
+        path: path.to_path_buf(),
+        source,
+    };
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(error(e.into())),
+    };
+    let user_config: UserConfig = toml::from_str(&content).map_err(|e| error(e.into()))?;
+    Ok(user_config.state_dir)
+}
+
+fn user_config_path() -> Option<PathBuf> {
+    Some(
+        user_config_dir()?
+            .join(USER_CONFIG_DIRNAME)
+            .join(USER_CONFIG_FILENAME),
+    )
+}
+
+fn build_state_dir() -> StateDir {
+    let path = option_env!("STATE_DIR_PATH").unwrap_or(DEFAULT_STATE_DIR_PATH);
+    StateDir {
+        path: PathBuf::from(path),
+        source: StateDirSource::Build,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_in_order() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let user_config_path = dir.path().join(USER_CONFIG_FILENAME);
+        fs::write(&user_config_path, "state_dir = \"/from/user/config\"\n")?;
+
+        let resolve = |option: Option<&str>, env_var: Option<&str>| {
+            resolve(
+                option.map(PathBuf::from),
+                env_var.map(OsString::from),
+                Some(user_config_path.clone()),
+            )
+        };
+
+        assert_eq!(
+            resolve(Some("/from/option"), Some("/from/env"))?,
+            StateDir {
+                path: PathBuf::from("/from/option"),
+                source: StateDirSource::Argument,
+            }
+        );
+        assert_eq!(
+            resolve(None, Some("/from/env"))?,
+            StateDir {
+                path: PathBuf::from("/from/env"),
+                source: StateDirSource::EnvironmentVariable,
+            }
+        );
+        // An empty variable is the same as an unset one.
+        assert_eq!(
+            resolve(None, Some(""))?,
+            StateDir {
+                path: PathBuf::from("/from/user/config"),
+                source: StateDirSource::UserConfig(user_config_path.clone()),
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_falls_back_to_build() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let missing_path = dir.path().join(USER_CONFIG_FILENAME);
+        assert_eq!(resolve(None, None, Some(missing_path))?, build_state_dir());
+
+        let path_without_key = dir.path().join("other.toml");
+        fs::write(&path_without_key, "")?;
+        assert_eq!(
+            resolve(None, None, Some(path_without_key))?,
+            build_state_dir()
+        );
+
+        assert_eq!(resolve(None, None, None)?, build_state_dir());
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_with_invalid_user_config() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(USER_CONFIG_FILENAME);
+        fs::write(&path, "state_dir = 1\n")?;
+
+        let error = resolve(None, None, Some(path.clone())).unwrap_err();
+        assert!(error.to_string().contains(&path.display().to_string()));
+        assert!(error.source().is_some());
+        Ok(())
+    }
+}
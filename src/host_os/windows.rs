@@ -1,36 +1,90 @@
-use std::process::Command;
+use std::path::PathBuf;
 use std::thread;
 use std::time::{Duration, Instant};
 
 use anyhow::{Result, bail};
+use chrono::Local;
 use display_profile_lib::{Profile, SetProfileAction};
 
-use crate::host_os::SuccessOr;
-use crate::options_types::{OperatingSystem, ProfileId};
+use crate::host_os::{CommandRunner, HostCommand, PendingAction, SuccessOr};
+use crate::options_types::{OperatingSystem, ProfileId, RebootAction, Schedule};
 use crate::persist::configs::Configs;
+use crate::persist::options::Options;
 use crate::text;
 
 pub mod configuration;
+pub mod doctor;
 
-pub const HOST_OS: OperatingSystem = OperatingSystem::Windows;
+pub const HOST_OS: OperatingSystem = OperatingSystem::WINDOWS;
 pub(super) const DEFAULT_STATE_DIR_PATH: &str = r"C:\grubenv.dir";
 
-pub fn reboot() -> Result<()> {
-    shutdown_now("/g")
+/// Where the per-user configs are, which is the roaming application data directory.
+pub(super) fn user_config_dir() -> Option<PathBuf> {
+    std::env::var_os("APPDATA").map(PathBuf::from)
 }
 
-pub fn shutdown() -> Result<()> {
-    shutdown_now("/sg")
+pub(crate) fn perform(action: RebootAction, runner: &dyn CommandRunner) -> Result<()> {
+    let command = match action {
+        RebootAction::Reboot => shutdown_in("/g", 0),
+        RebootAction::Shutdown => shutdown_in("/sg", 0),
+        RebootAction::FirmwareSetup => HostCommand::new("shutdown", ["/r", "/fw", "/t", "0"]),
+        RebootAction::Suspend => {
+            HostCommand::new("rundll32.exe", ["powrprof.dll,SetSuspendState", "0,1,0"])
+        }
+        RebootAction::Hibernate => HostCommand::new("shutdown", ["/h"]),
+        RebootAction::Logout => HostCommand::new("shutdown", ["/l"]),
+        RebootAction::Kexec => bail!(text::reboot_action::unsupported(action)),
+    };
+    runner
+        .run(&command)?
+        .success_or(text::reboot_action::FAILED)
 }
 
-fn shutdown_now(arg: &str) -> Result<()> {
-    Command::new("shutdown")
-        .arg(arg)
-        .args(["/t", "0"])
-        .status()?
+fn shutdown_in(arg: &str, seconds: i64) -> HostCommand {
+    HostCommand::new(
+        "shutdown",
+        [arg.to_string(), "/t".to_string(), seconds.to_string()],
+    )
+}
+
+/// Schedules the action through a `shutdown` timeout. As Windows doesn't tell which action is
+/// pending, the caller records it with `StateProvider::set_pending_action`.
+pub(crate) fn schedule(
+    action: RebootAction,
+    schedule: Schedule,
+    runner: &dyn CommandRunner,
+) -> Result<()> {
+    let now = Local::now();
+    let at = schedule.resolve(now);
+    let arg = match action {
+        RebootAction::Reboot => "/g",
+        RebootAction::Shutdown => "/sg",
+        _ => bail!(text::reboot_action::cannot_be_scheduled(action)),
+    };
+    runner
+        .run(&shutdown_in(arg, (at - now).num_seconds().max(0)))?
         .success_or(text::reboot_action::FAILED)
 }
 
+pub(crate) fn cancel(runner: &dyn CommandRunner) -> Result<()> {
+    runner
+        .run(&HostCommand::new("shutdown", ["/a"]))?
+        .success_or(text::reboot_action::CANCEL_FAILED)?;
+
+    let mut options = Options::load()?;
+    options.operating_system.windows.pending_action = None;
+    options.save()
+}
+
+pub(crate) fn pending_action() -> Result<Option<PendingAction>> {
+    let options = Options::load()?;
+    Ok(options
+        .operating_system
+        .windows
+        .pending_action
+        .filter(|pending_action| pending_action.at > Local::now()))
+}
+
 pub(crate) struct CurrentProfileHandler<'a> {
     configs: &'a Configs,
 }
@@ -65,7 +119,7 @@ impl<'a> CurrentProfileHandler<'a> {
         Ok(profile_id)
     }
 
-    pub(crate) fn switch_to(&self, profile_id: ProfileId) -> Result<()> {
+    pub(crate) fn switch_to(&self, profile_id: &ProfileId) -> Result<()> {
         const WAIT_SECONDS: u64 = 10;
 
         let profile = self.configs.profile[profile_id].display_configs()?;
@@ -1,22 +1,75 @@
+use std::fs;
+use std::path::Path;
+
 use ansi_term::Color;
-use anyhow::Result;
+use anyhow::{Context, Result, bail};
 use display_profile_lib::{Profile, Rotation, SetProfileAction, get_profile, set_profile};
 use rustyline::Prompt;
 
 use crate::configuration::Configurer;
 use crate::options_types::{LabeledProfile, ProfileId};
 use crate::persist::configs::ConfigsWriter;
+use crate::text::{Localized, localized};
 
 pub(crate) fn configure(configurer: &mut Configurer) -> Result<()> {
+    if let Some(path) = configurer.options.profiles.clone() {
+        return import_profiles(configurer, &path);
+    }
+    if configurer.options.yes {
+        bail!(localized!(
+            pt_br: "Para configurar sem perguntas, os perfis devem ser importados com --profiles",
+            en: "To configure without questions, the profiles must be imported with --profiles",
+        ));
+    }
+
     let mut configurer = WindowsConfigurer::new(configurer)?;
     configurer.configure()?;
     configurer.finalize()?;
     Ok(())
 }
 
+fn import_profiles(configurer: &mut Configurer, path: &Path) -> Result<()> {
+    let display_path = path.display();
+    println!(
+        "{}",
+        localized!(
+            pt_br: "Importando perfis de {display_path}...",
+            en: "Importing profiles from {display_path}...",
+        )
+    );
+
+    let serialized = fs::read_to_string(path).with_context(|| {
+        localized!(
+            pt_br: "Não foi possível abrir {display_path}",
+            en: "Couldn't open {display_path}",
+        )
+    })?;
+    let ids = configurer
+        .configs
+        .import_profiles(&serialized, |display_configs| {
+            serde_json::from_str::<Profile>(display_configs)?;
+            Ok(())
+        })?;
+
+    let ids = ids
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+    println!(
+        "{}",
+        localized!(pt_br: "Perfis importados: {ids}", en: "Imported profiles: {ids}")
+    );
+    Ok(())
+}
+
 macro_rules! print_error {
     ($($tt:tt)*) => {
-        println!("{} {}", Color::Red.paint("Erro:"), format_args!($($tt)*))
+        println!(
+            "{} {}",
+            Color::Red.paint(Localized::new("Erro:", "Error:").get()),
+            format_args!($($tt)*)
+        )
     };
 }
 
@@ -34,63 +87,137 @@ impl<'a> WindowsConfigurer<'a> {
         })
     }
 
-    #[expect(clippy::similar_names)]
     fn configure(&mut self) -> Result<()> {
         println!(
-            "Configuraremos perfis {} e {} de telas do Windows.",
-            ProfileId::A,
-            ProfileId::B
+            "{}",
+            localized!(
+                pt_br: "Configuraremos os perfis de telas do Windows.",
+                en: "We will configure the Windows display profiles.",
+            )
         );
 
-        let profile_a = self.configure_profile(ProfileId::A, accept_anything)?;
-        let profile_a_label = self.ask_label(ProfileId::A, accept_anything)?;
+        let mut profiles: Vec<(ProfileId, String, Profile)> = Vec::new();
+        loop {
+            let id = self.ask_id(|id| {
+                if profiles.iter().any(|(other_id, _, _)| other_id == id) {
+                    Err(localized!(
+                        pt_br: "Já existe um perfil {id}",
+                        en: "There is already a profile {id}",
+                    ))
+                } else {
+                    Ok(())
+                }
+            })?;
 
-        let profile_b = self.configure_profile(ProfileId::B, |profile| {
-            if *profile == profile_a {
-                Err(format!(
-                    "A configuração não pode ser igual à do perfil {}",
-                    ProfileId::A
-                ))
-            } else {
-                Ok(())
-            }
-        })?;
-        let profile_b_label = self.ask_label(ProfileId::B, |label| {
-            if *label == profile_a_label {
-                Err(format!(
-                    "O nome não pode ser igual ao do perfil {}",
-                    ProfileId::A
-                ))
-            } else {
-                Ok(())
+            let profile = self.configure_profile(&id, |profile| {
+                match profiles.iter().find(|(_, _, other)| other == profile) {
+                    Some((other_id, _, _)) => Err(localized!(
+                        pt_br: "A configuração não pode ser igual à do perfil {other_id}",
+                        en: "The configuration can't be the same as the one of the profile {other_id}",
+                    )),
+                    None => Ok(()),
+                }
+            })?;
+
+            let label = self.ask_label(&id, |label| {
+                match profiles.iter().find(|(_, other, _)| other == label) {
+                    Some((other_id, _, _)) => Err(localized!(
+                        pt_br: "O nome não pode ser igual ao do perfil {other_id}",
+                        en: "The name can't be the same as the one of the profile {other_id}",
+                    )),
+                    None => Ok(()),
+                }
+            })?;
+
+            profiles.push((id, label, profile));
+
+            println!();
+            if !self.ask_yes_no(&localized!(
+                pt_br: "Deseja configurar mais um perfil? (s/N) ",
+                en: "Do you want to configure one more profile? (y/N) ",
+            ))? {
+                break;
             }
-        })?;
+        }
 
         println!();
 
-        println!("Resumo dos perfis:");
-        Self::print_profile_summary(ProfileId::A, &profile_a_label, &profile_a);
-        Self::print_profile_summary(ProfileId::B, &profile_b_label, &profile_b);
+        println!(
+            "{}",
+            localized!(pt_br: "Resumo dos perfis:", en: "Profiles summary:")
+        );
+        for (index, (id, label, profile)) in profiles.iter().enumerate() {
+            Self::print_profile_summary(LabeledProfile::new(id, label, index), profile);
+        }
         println!();
 
-        self.configs()
-            .set_profile_configs(ProfileId::A, &profile_a_label, &profile_a)?;
-        self.configs()
-            .set_profile_configs(ProfileId::B, &profile_b_label, &profile_b)?;
+        self.configs().remove_profiles();
+        for (id, label, profile) in &profiles {
+            self.configs().set_profile_configs(id, label, profile)?;
+        }
 
         Ok(())
     }
 
+    fn ask_id(&mut self, validate: impl Fn(&ProfileId) -> Result<(), String>) -> Result<ProfileId> {
+        loop {
+            println!();
+            let id = self.readline(&localized!(
+                pt_br: "Digite um identificador para o perfil (ex.: tv, monitor): ",
+                en: "Type an identifier for the profile (e.g.: tv, monitor): ",
+            ))?;
+            match ProfileId::try_from(id) {
+                Ok(id) => match validate(&id) {
+                    Ok(()) => return Ok(id),
+                    Err(msg) => print_error!("{msg}"),
+                },
+                Err(msg) => print_error!("{msg}"),
+            }
+        }
+    }
+
+    fn ask_yes_no(&mut self, prompt: &str) -> Result<bool> {
+        loop {
+            // The answers are accepted in all locales.
+            match self.readline(prompt)?.to_lowercase().as_str() {
+                "s" | "sim" | "y" | "yes" => return Ok(true),
+                "" | "n" | "não" | "nao" | "no" => return Ok(false),
+                other => print_error!(
+                    "{}",
+                    localized!(pt_br: "Opção inválida: {other:?}", en: "Invalid option: {other:?}")
+                ),
+            }
+        }
+    }
+
     fn configure_profile(
         &mut self,
-        id: ProfileId,
+        id: &ProfileId,
         validate: impl Fn(&Profile) -> Result<(), String>,
     ) -> Result<Profile> {
         loop {
             println!();
-            println!("Escolha uma das opções para configurar o perfil {id}:");
-            println!("1. A configuração de tela atual corresponde ao perfil {id}");
-            println!("2. Abrir as configurações de tela do Windows");
+            println!(
+                "{}",
+                localized!(
+                    pt_br: "Escolha uma das opções para configurar o perfil {id}:",
+                    en: "Choose one of the options to configure the profile {id}:",
+                )
+            );
+            println!(
+                "{}",
+                localized!(
+                    pt_br: "1. A configuração de tela atual corresponde ao perfil {id}",
+                    en: "1. The current display configuration matches the profile {id}",
+                )
+            );
+            println!(
+                "{}",
+                localized!(
+                    pt_br: "2. Abrir as configurações de tela do Windows",
+                    en: "2. Open the Windows display settings",
+                )
+            );
 
             match self.readline("> ")?.as_str() {
                 "1" => {
@@ -104,21 +231,30 @@ impl<'a> WindowsConfigurer<'a> {
                     }
                 }
                 "2" => open::that("ms-settings:display")?,
-                other => print_error!("Opção inválida: {other:?}"),
+                other => print_error!(
+                    "{}",
+                    localized!(pt_br: "Opção inválida: {other:?}", en: "Invalid option: {other:?}")
+                ),
             }
         }
     }
 
     fn ask_label(
         &mut self,
-        id: ProfileId,
+        id: &ProfileId,
         validate: impl Fn(&str) -> Result<(), String>,
     ) -> Result<String> {
         loop {
             println!();
-            let label = self.readline(&format!("Digite um nome para o perfil {id}: "))?;
+            let label = self.readline(&localized!(
+                pt_br: "Digite um nome para o perfil {id}: ",
+                en: "Type a name for the profile {id}: ",
+            ))?;
             if label.is_empty() {
-                print_error!("O nome não pode ser vazio");
+                print_error!(
+                    "{}",
+                    Localized::new("O nome não pode ser vazio", "The name can't be empty")
+                );
             } else if let Err(msg) = validate(&label) {
                 print_error!("{msg}");
             } else {
@@ -148,18 +284,22 @@ impl<'a> WindowsConfigurer<'a> {
         Ok(())
     }
 
-    fn print_profile_summary(id: ProfileId, label: &str, profile: &Profile) {
-        println!("  {}", LabeledProfile::new(id, label));
+    fn print_profile_summary(labeled_profile: LabeledProfile, profile: &Profile) {
+        println!("  {labeled_profile}");
         for monitor in profile {
+            let name = &monitor.friendly_device_name;
+            let width = monitor.dimensions.width;
+            let height = monitor.dimensions.height;
+            let refresh_rate = f64::from(monitor.refresh_rate.numerator)
+                / f64::from(monitor.refresh_rate.denominator);
+            let x = monitor.position.x;
+            let y = monitor.position.y;
             print!(
-                "    {}: {}x{}; {:.2}Hz; em {},{}",
-                monitor.friendly_device_name,
-                monitor.dimensions.width,
-                monitor.dimensions.height,
-                f64::from(monitor.refresh_rate.numerator)
-                    / f64::from(monitor.refresh_rate.denominator),
-                monitor.position.x,
-                monitor.position.y,
+                "{}",
+                localized!(
+                    pt_br: "    {name}: {width}x{height}; {refresh_rate:.2}Hz; em {x},{y}",
+                    en: "    {name}: {width}x{height}; {refresh_rate:.2}Hz; at {x},{y}",
+                )
             );
 
             let rotation = match monitor.rotation {
@@ -169,15 +309,13 @@ impl<'a> WindowsConfigurer<'a> {
                 Rotation::ROTATE270 => Some(270),
             };
             if let Some(rotation) = rotation {
-                print!("; rotação de {rotation}°");
+                print!(
+                    "{}",
+                    localized!(pt_br: "; rotação de {rotation}°", en: "; rotation of {rotation}°")
+                );
             }
 
             println!();
         }
     }
 }
-
-#[expect(clippy::unnecessary_wraps)]
-fn accept_anything<T: ?Sized>(_: &T) -> Result<(), String> {
-    Ok(())
-}
@@ -0,0 +1,40 @@
+use anyhow::anyhow;
+
+use crate::doctor::Check;
+use crate::persist::configs::Configs;
+use crate::text::localized;
+
+pub(crate) fn checks(configs: Option<&Configs>) -> Vec<Check> {
+    let Some(configs) = configs else {
+        return Vec::new();
+    };
+
+    let count = configs.profile.len();
+    let mut checks = vec![Check::new(
+        localized!(
+            pt_br: "Há ao menos dois perfis de tela configurados",
+            en: "There are at least two display profiles configured",
+        ),
+        if count >= 2 {
+            Ok(())
+        } else {
+            Err(anyhow!(localized!(
+                pt_br: "há {count}",
+                en: "there are {count}",
+            )))
+        },
+    )];
+
+    for (id, profile_configs) in configs.profile.iter() {
+        let label = &profile_configs.label;
+        checks.push(Check::new(
+            localized!(
+                pt_br: "As configurações de tela do perfil {label} ({id}) são válidas",
+                en: "The display configs of the profile {label} ({id}) are valid",
+            ),
+            profile_configs.display_configs().map(|_| ()),
+        ));
+    }
+
+    checks
+}
@@ -1,46 +1,84 @@
 mod args;
 mod configuration;
 mod dialog;
+mod doctor;
 mod host_os;
 mod options_types;
+mod output;
 mod script;
 mod state;
 mod text;
 
 mod persist {
+    pub(crate) mod atomic_write;
+    pub(crate) mod backup;
     pub(crate) mod configs;
     pub(crate) mod grubenv;
     pub(crate) mod options;
 }
 
 use std::num::NonZeroUsize;
+use std::path::Path;
 
 use anyhow::{Context, Result, bail};
+use chrono::Local;
 use dialog::Mode;
-use script::Script;
+use rustyline::DefaultEditor;
+use rustyline::error::ReadlineError;
 #[cfg(all(windows, not(test)))]
 use script::SwitchToProfile;
+use script::{ExecutionOptions, Script, ScriptHooks};
 
-use crate::args::{ParsedArgs, PredefinedScriptParsedArgs};
-use crate::host_os::HOST_OS;
-use crate::options_types::{LabeledProfile, ProfileId, SerializeToString, Values as _};
-use crate::persist::configs::Configs;
-use crate::state::StateProvider;
-use crate::text::Capitalized;
+use crate::args::{
+    ConfigParsedArgs, GrubHooksAction, GrubParsedArgs, OutputFormat, ParsedArgs,
+    PredefinedScriptParsedArgs, RestoreParsedArgs,
+};
+use crate::configuration::ConfigureOptions;
+use crate::host_os::{HOST_OS, SystemCommandRunner};
+use crate::options_types::{
+    LabeledOperatingSystem, LabeledProfile, OperatingSystem, SerializeToString,
+};
+use crate::persist::backup::{Backups, back_up_state_files};
+use crate::persist::configs::{
+    self, CURRENT_VERSION, Configs, ConfigsWriter, Diagnostic, ScriptEdit, ScriptsWriter,
+};
+use crate::state::{NextBootEntry, StateProvider};
+use crate::text::{Capitalized, Localized, localized};
 
 fn main() -> Result<()> {
-    let args = args::parse()
-        .with_context(|| "Argumentos inválidos.\nPara ajuda, execute: my-reboot --help")?;
+    let invalid_arguments = || {
+        localized!(
+            pt_br: "Argumentos inválidos.\nPara ajuda, execute: my-reboot --help",
+            en: "Invalid arguments.\nFor help, run: my-reboot --help",
+        )
+    };
+    let state_dir = args::parse_state_dir().with_context(invalid_arguments)?;
+    host_os::init_state_dir(state_dir)?;
+    let args = args::parse().with_context(invalid_arguments)?;
 
     match args {
         ParsedArgs::Dialog(mode) => show_dialog(mode),
-        ParsedArgs::Script(script) => execute_script(script),
-        ParsedArgs::PredefinedScript(PredefinedScriptParsedArgs::Number(number)) => {
-            execute_predefined_script(number)
+        ParsedArgs::Script(script, options) => execute_script(&script, options),
+        ParsedArgs::PredefinedScript(PredefinedScriptParsedArgs::Number(number, options)) => {
+            execute_predefined_script(number, options)
+        }
+        ParsedArgs::PredefinedScript(PredefinedScriptParsedArgs::Edit(os, edit)) => {
+            edit_predefined_scripts(&os, edit)
+        }
+        ParsedArgs::PredefinedScript(PredefinedScriptParsedArgs::List(format)) => {
+            list_predefined_scripts(format)
         }
-        ParsedArgs::PredefinedScript(PredefinedScriptParsedArgs::List) => list_predefined_scripts(),
-        ParsedArgs::ShowState => show_state(),
-        ParsedArgs::Configure => configure(),
+        ParsedArgs::Restore(RestoreParsedArgs::Number(number)) => restore_backup(number),
+        ParsedArgs::Restore(RestoreParsedArgs::List) => list_backups(),
+        ParsedArgs::ShowState(format) => show_state(format),
+        ParsedArgs::Configure(options) => configure(options),
+        ParsedArgs::Config(ConfigParsedArgs::Migrate { check }) => migrate_configs(check),
+        ParsedArgs::Config(ConfigParsedArgs::Validate { grub_cfg }) => {
+            validate_configs(grub_cfg.as_deref())
+        }
+        ParsedArgs::Grub(GrubParsedArgs::InstallHooks(action)) => install_grub_hooks(&action),
+        ParsedArgs::Doctor => doctor::doctor(),
+        ParsedArgs::Cancel => cancel_pending_action(),
         ParsedArgs::Usage => {
             show_usage();
             Ok(())
@@ -68,33 +106,68 @@ fn show_dialog(mode: Mode) -> Result<()> {
 
     let provider = StateProvider::new()?;
 
-    let labels: Vec<_> = provider.configs().operating_system[HOST_OS]
+    let predefined_scripts: Vec<_> = provider.configs().operating_system[&HOST_OS]
         .scripts
         .iter()
-        .map(|ps| ps.resolve_label(provider.configs()))
+        .map(|ps| dialog::PredefinedScriptChoice {
+            label: ps.resolve_label(provider.configs()),
+            confirm: ps.needs_confirmation(),
+        })
         .collect();
 
     let state = provider.state()?;
+    // A pending one-shot boot is what will actually be booted next.
+    let one_shot_boot_operating_system = state
+        .one_shot_boot_operating_system
+        .and_then(NextBootEntry::into_operating_system);
     let script_options = dialog::ScriptOptions {
-        next_boot_operating_system: state.next_boot_operating_system,
+        boot_once: one_shot_boot_operating_system.is_some(),
+        next_boot_operating_system: one_shot_boot_operating_system.or_else(|| {
+            state
+                .next_boot_operating_system
+                .and_then(NextBootEntry::into_operating_system)
+        }),
         next_windows_boot_profile: state.next_windows_boot_profile,
         #[cfg(windows)]
         switch_profile: false,
         reboot_action: None,
     };
-    let profile_labels =
-        ProfileId::values().map(|id| LabeledProfile::get(id, provider.configs()).to_string());
+    let operating_systems = provider
+        .configs()
+        .operating_system
+        .keys()
+        .map(|os| {
+            let labeled_os = LabeledOperatingSystem::get(os, provider.configs()).unwrap();
+            (os.clone(), labeled_os.to_string())
+        })
+        .collect();
+    let profiles = provider
+        .configs()
+        .profile
+        .keys()
+        .map(|id| {
+            let labeled_profile = LabeledProfile::get(id, provider.configs()).unwrap();
+            (id.clone(), labeled_profile.to_string())
+        })
+        .collect();
 
-    let outcome = dialog::show(mode, labels, script_options, profile_labels)?;
+    let outcome = dialog::show(
+        mode,
+        predefined_scripts,
+        script_options,
+        operating_systems,
+        profiles,
+        state.pending_action,
+    )?;
 
     match outcome {
         Some(dialog::Outcome::PredefinedScriptIndex(index)) => {
-            provider.configs().operating_system[HOST_OS].scripts[index]
-                .script
-                .execute()
+            provider.configs().operating_system[&HOST_OS].scripts[index]
+                .execute_with(ExecutionOptions::default())
         }
         Some(dialog::Outcome::ScriptOptions(options)) => {
             let script = Script {
+                boot_once: options.boot_once && options.next_boot_operating_system.is_some(),
                 next_boot_operating_system: Some(options.next_boot_operating_system.into()),
                 next_windows_boot_profile: Some(options.next_windows_boot_profile.into()),
                 switch_to_profile: cfg_select! {
@@ -102,49 +175,111 @@ fn show_dialog(mode: Mode) -> Result<()> {
                     _ => None,
                 },
                 reboot_action: options.reboot_action,
+                schedule: None,
             };
             script.execute()
         }
+        Some(dialog::Outcome::CancelPendingAction) => host_os::cancel(&SystemCommandRunner),
         None => Ok(()),
     }
 }
 
-fn execute_predefined_script(number: NonZeroUsize) -> Result<()> {
+fn execute_predefined_script(number: NonZeroUsize, options: ExecutionOptions) -> Result<()> {
     let index = number.get() - 1;
 
     let configs = Configs::load()?;
-    let predef_scripts = &configs.operating_system[HOST_OS].scripts;
+    let predef_scripts = &configs.operating_system[&HOST_OS].scripts;
     let Some(predef_script) = predef_scripts.get(index) else {
-        bail!(
-            "Número inválido de script para o sistema operacional atual (mín: 1; máx: {})",
-            predef_scripts.len()
-        );
+        let max = predef_scripts.len();
+        bail!(localized!(
+            pt_br: "Número inválido de script para o sistema operacional atual (mín: 1; máx: {max})",
+            en: "Invalid script number for the current operating system (min: 1; max: {max})",
+        ));
     };
 
+    let label = predef_script.resolve_label(&configs);
+    if predef_script.needs_confirmation()
+        || options.confirm && predef_script.script.reboot_action.is_some()
+    {
+        let prompt = localized!(
+            pt_br: "Executar o script '{label}'? [s/N] ",
+            en: "Execute the script '{label}'? [y/N] ",
+        );
+        if !confirm_execution(&prompt, options)? {
+            return Ok(());
+        }
+    }
     println!(
-        "Executando script '{}'",
-        predef_script.resolve_label(&configs)
+        "{}",
+        localized!(pt_br: "Executando script '{label}'", en: "Executing script '{label}'")
     );
-    predef_script.script.execute()
+    predef_script.execute_with(options)
 }
 
-fn list_predefined_scripts() -> Result<()> {
+/// Asks whether the script is to be executed, unless `--yes` or `--dry-run` is given, and tells
+/// when it's canceled.
+fn confirm_execution(prompt: &str, options: ExecutionOptions) -> Result<bool> {
+    if options.yes || options.dry_run {
+        return Ok(true);
+    }
+    let confirmed = ask_yes_or_no(prompt)?;
+    if !confirmed {
+        println!("{}", Localized::new("Cancelado.", "Canceled."));
+    }
+    Ok(confirmed)
+}
+
+/// Interrupting or closing the input answers no.
+fn ask_yes_or_no(prompt: &str) -> Result<bool> {
+    let mut readline = DefaultEditor::new()?;
+    loop {
+        let answer = match readline.readline(prompt) {
+            Ok(answer) => answer,
+            Err(ReadlineError::Interrupted | ReadlineError::Eof) => return Ok(false),
+            Err(e) => return Err(e.into()),
+        };
+        // The answers are accepted in all locales.
+        match answer.trim().to_lowercase().as_str() {
+            "s" | "sim" | "y" | "yes" => return Ok(true),
+            "" | "n" | "não" | "nao" | "no" => return Ok(false),
+            other => eprintln!(
+                "{}",
+                localized!(pt_br: "Opção inválida: {other:?}", en: "Invalid option: {other:?}")
+            ),
+        }
+    }
+}
+
+fn list_predefined_scripts(format: OutputFormat) -> Result<()> {
     let configs = Configs::load()?;
 
-    for (i, predef_script) in configs.operating_system[HOST_OS].scripts.iter().enumerate() {
+    if format != OutputFormat::Text {
+        let predef_scripts = &configs.operating_system[&HOST_OS].scripts;
+        let output = output::PredefinedScriptsOutput::new(predef_scripts, &configs);
+        println!("{}", output::serialize(format, &output)?);
+        return Ok(());
+    }
+
+    for (i, predef_script) in configs.operating_system[&HOST_OS]
+        .scripts
+        .iter()
+        .enumerate()
+    {
         let number = i + 1;
 
         let label = predef_script.resolve_label(&configs);
         let Script {
             next_boot_operating_system,
+            boot_once,
             next_windows_boot_profile,
             switch_to_profile,
             reboot_action,
+            schedule,
         } = &predef_script.script;
 
         macro_rules! print_option {
             ($name:ident) => {
-                $name.inspect(|value| {
+                $name.as_ref().inspect(|value| {
                     println!("  {}: {}", stringify!($name), value.serialize_to_string())
                 });
             };
@@ -152,37 +287,108 @@ fn list_predefined_scripts() -> Result<()> {
 
         println!("{number}: '{label}'");
         print_option!(next_boot_operating_system);
+        if *boot_once {
+            println!("  boot_once: true");
+        }
         print_option!(next_windows_boot_profile);
         print_option!(switch_to_profile);
         print_option!(reboot_action);
+        print_option!(schedule);
+        if predef_script.confirm {
+            println!("  confirm: true");
+        }
+        let hooks = &predef_script.hooks;
+        for (name, hooks) in [
+            ("pre_hooks", &hooks.pre_hooks),
+            ("post_hooks", &hooks.post_hooks),
+        ] {
+            if !hooks.is_empty() {
+                println!("  {name}:");
+                for hook in hooks {
+                    println!("    {hook}");
+                }
+            }
+        }
+        if let Some(hook_timeout) = hooks.hook_timeout {
+            println!("  hook_timeout: {hook_timeout}");
+        }
         println!();
     }
 
     Ok(())
 }
 
-fn execute_script(script: Script) -> Result<()> {
-    script.execute()
+fn execute_script(script: &Script, options: ExecutionOptions) -> Result<()> {
+    if options.confirm
+        && let Some(reboot_action) = script.reboot_action
+    {
+        let action = match script.schedule {
+            Some(schedule) => format!("{reboot_action} {schedule}"),
+            None => reboot_action.to_string(),
+        };
+        let action = Capitalized(action);
+        let prompt = localized!(pt_br: "{action}? [s/N] ", en: "{action}? [y/N] ");
+        if !confirm_execution(&prompt, options)? {
+            return Ok(());
+        }
+    }
+    script.execute_with(options, &ScriptHooks::default())
 }
 
-fn show_state() -> Result<()> {
+fn show_state(format: OutputFormat) -> Result<()> {
     let provider = StateProvider::new()?;
     let state = provider.state()?;
 
-    println!(
-        "{}: {}",
-        Capitalized(text::operating_system::ON_NEXT_BOOT_DESCRIPTION),
-        text::operating_system::value_text(state.next_boot_operating_system)
-    );
+    if format != OutputFormat::Text {
+        let output = output::StateOutput::new(&state, provider.configs(), host_os::state_dir());
+        println!("{}", output::serialize(format, &output)?);
+        return Ok(());
+    }
+
+    let entry_value_text = |entry: &Option<NextBootEntry>| match entry {
+        Some(NextBootEntry::Unknown(grub_entry)) => {
+            text::operating_system::unknown_entry_value_text(grub_entry)
+        }
+        Some(NextBootEntry::OperatingSystem(os)) => {
+            text::operating_system::value_text(LabeledOperatingSystem::get(os, provider.configs()))
+        }
+        None => text::operating_system::value_text(None),
+    };
+    if state.one_shot_boot_operating_system.is_some() {
+        println!(
+            "{}: {}",
+            Capitalized(text::operating_system::ONE_SHOT_DESCRIPTION),
+            entry_value_text(&state.one_shot_boot_operating_system)
+        );
+        println!(
+            "{}: {}",
+            Capitalized(text::operating_system::AFTER_ONE_SHOT_DESCRIPTION),
+            entry_value_text(&state.next_boot_operating_system)
+        );
+    } else {
+        println!(
+            "{}: {}",
+            Capitalized(text::operating_system::ON_NEXT_BOOT_DESCRIPTION),
+            entry_value_text(&state.next_boot_operating_system)
+        );
+    }
     println!(
         "{}: {}",
         Capitalized(text::profile::ON_NEXT_WINDOWS_BOOT_DESCRIPTION),
         text::profile::next_boot_value_text(
             state
                 .next_windows_boot_profile
-                .map(|id| LabeledProfile::get(id, provider.configs()))
+                .as_ref()
+                .and_then(|id| LabeledProfile::get(id, provider.configs()))
         )
     );
+    if let Some(pending_action) = &state.pending_action {
+        println!(
+            "{}: {}",
+            Capitalized(text::reboot_action::SCHEDULED_DESCRIPTION),
+            text::reboot_action::pending_text(pending_action, Local::now())
+        );
+    }
     #[cfg(windows)]
     println!(
         "{}: {}",
@@ -190,22 +396,174 @@ fn show_state() -> Result<()> {
         text::profile::current_value_text(
             state
                 .current_profile
-                .map(|id| LabeledProfile::get(id, provider.configs()))
+                .as_ref()
+                .and_then(|id| LabeledProfile::get(id, provider.configs()))
         )
     );
+    let state_dir = host_os::state_dir();
+    println!(
+        "{}: {} ({})",
+        Capitalized(Localized::new("diretório de estado", "state directory")),
+        state_dir.path.display(),
+        state_dir.source,
+    );
 
     Ok(())
 }
 
-fn configure() -> Result<()> {
-    configuration::configure()
+const DATE_TIME_FORMAT: Localized = Localized::new("%d/%m/%Y %H:%M:%S", "%Y-%m-%d %H:%M:%S");
+
+fn restore_backup(number: NonZeroUsize) -> Result<()> {
+    let backup = Backups::new().restore(number)?;
+
+    match backup.created_at() {
+        Some(created_at) => {
+            let created_at = created_at.format(DATE_TIME_FORMAT.get());
+            println!(
+                "{}",
+                localized!(
+                    pt_br: "Restaurado o backup de {created_at}",
+                    en: "Restored the backup from {created_at}",
+                )
+            );
+        }
+        None => println!(
+            "{}",
+            localized!(
+                pt_br: "Restaurado o backup {number}",
+                en: "Restored the backup {number}",
+            )
+        ),
+    }
+    Ok(())
 }
 
-fn show_usage() {
-    let profile_labels =
-        Configs::load().map(|configs| [configs.profile.a.label, configs.profile.b.label]);
+fn list_backups() -> Result<()> {
+    for (i, backup) in Backups::new().list()?.iter().enumerate() {
+        let number = i + 1;
+        match backup.created_at() {
+            Some(created_at) => println!("{number}: {}", created_at.format(DATE_TIME_FORMAT.get())),
+            None => println!("{number}: ?"),
+        }
+    }
+
+    Ok(())
+}
+
+fn cancel_pending_action() -> Result<()> {
+    if host_os::pending_action()?.is_none() {
+        println!("{}", text::reboot_action::NONE_SCHEDULED);
+        return Ok(());
+    }
+
+    host_os::cancel(&SystemCommandRunner)?;
+    println!("{}", text::reboot_action::CANCELED);
+    Ok(())
+}
+
+fn edit_predefined_scripts(os: &OperatingSystem, edit: ScriptEdit) -> Result<()> {
+    back_up_state_files()?;
+    let mut scripts = ScriptsWriter::load()?;
+    scripts.edit(os, edit)?;
+    scripts.save()?;
+    println!(
+        "{}",
+        localized!(
+            pt_br: "Scripts pré-definidos do {os} atualizados",
+            en: "Predefined scripts of {os} updated",
+        )
+    );
+    Ok(())
+}
+
+fn migrate_configs(check: bool) -> Result<()> {
+    let current_version = CURRENT_VERSION;
+    let version = Configs::file_version()?;
+    if version == current_version {
+        println!(
+            "{}",
+            localized!(
+                pt_br: "O arquivo de configurações já está na versão {current_version}",
+                en: "The configs file is already at version {current_version}",
+            )
+        );
+        return Ok(());
+    }
+    if check {
+        return Err(configs::outdated_version_error(version));
+    }
 
-    let usage = args::Usage::new(profile_labels);
+    back_up_state_files()?;
+    ConfigsWriter::load()?.save()?;
+    println!(
+        "{}",
+        localized!(
+            pt_br: "O arquivo de configurações foi atualizado da versão {version} para a versão {current_version}",
+            en: "The configs file was upgraded from the version {version} to the version {current_version}",
+        )
+    );
+    Ok(())
+}
+
+fn validate_configs(grub_cfg: Option<&Path>) -> Result<()> {
+    if grub_cfg.is_some() {
+        configuration::only_on("--grub-cfg", &OperatingSystem::LINUX)?;
+    }
+    // The Grub configuration file isn't reachable from Windows.
+    let (grub_entries, grub_cfg_diagnostic): (Option<Vec<String>>, Option<Diagnostic>) = cfg_select! {
+        windows => (None, None),
+        _ => match host_os::configuration::grub_entry_paths(grub_cfg) {
+            Ok(grub_entries) => (Some(grub_entries), None),
+            Err(e) => {
+                let grub_cfg = host_os::configuration::grub_cfg_path(grub_cfg);
+                (None, Some(Diagnostic::unreadable_grub_cfg(grub_cfg, &e)))
+            }
+        },
+    };
+
+    let diagnostics: Vec<_> = grub_cfg_diagnostic
+        .into_iter()
+        .chain(Configs::validate_file(grub_entries.as_deref())?)
+        .collect();
+    for diagnostic in &diagnostics {
+        println!("{diagnostic}");
+    }
+    match diagnostics.len() {
+        0 => {
+            println!(
+                "{}",
+                Localized::new("Nenhum problema encontrado", "No problems found")
+            );
+            Ok(())
+        }
+        count => bail!(localized!(
+            pt_br: "{count} problema(s) encontrado(s)",
+            en: "{count} problem(s) found",
+        )),
+    }
+}
+
+fn install_grub_hooks(action: &GrubHooksAction) -> Result<()> {
+    #[cfg(windows)]
+    {
+        let _ = action;
+        let linux = OperatingSystem::LINUX;
+        bail!(localized!(
+            pt_br: "Os scripts do Grub só podem ser alterados no {linux}",
+            en: "The Grub scripts can only be edited on {linux}",
+        ));
+    }
+    #[cfg(not(windows))]
+    host_os::grub_hooks::install_hooks(action)
+}
+
+fn configure(options: ConfigureOptions) -> Result<()> {
+    back_up_state_files()?;
+    configuration::configure(options)
+}
+
+fn show_usage() {
+    let usage = args::Usage::new(Configs::load());
 
     println!("{usage}");
 }
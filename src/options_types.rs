@@ -1,83 +1,173 @@
+use std::borrow::Cow;
 use std::fmt::{Debug, Display};
 
+use chrono::{DateTime, Local, NaiveTime, TimeDelta};
 use serde::de::value::StrDeserializer;
 use serde::{Deserialize, Serialize};
 
 use crate::persist::configs::Configs;
+use crate::text::{Localized, localized};
 
-pub(crate) trait Values: Copy {
-    // We're assuming that all options have only two possible values.
-    fn values() -> [Self; 2];
+pub(crate) trait Values: Copy + 'static {
+    /// The possible values, in the order they are to be shown.
+    fn values() -> &'static [Self];
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
-pub enum OperatingSystem {
-    #[serde(rename = "windows")]
-    Windows,
-    #[serde(rename = "linux")]
-    Linux,
+/// Whether the string can be used as an identifier in the configs file and in the command line.
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
 }
-impl Values for OperatingSystem {
-    fn values() -> [Self; 2] {
-        [OperatingSystem::Windows, OperatingSystem::Linux]
+
+/// Identifies one of the boot targets defined in the configs file (e.g. `windows`, `linux`,
+/// `fedora`).
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct OperatingSystem(Cow<'static, str>);
+impl OperatingSystem {
+    pub const WINDOWS: OperatingSystem = OperatingSystem(Cow::Borrowed("windows"));
+    pub const LINUX: OperatingSystem = OperatingSystem(Cow::Borrowed("linux"));
+
+    pub(crate) fn id(&self) -> &str {
+        &self.0
+    }
+}
+impl TryFrom<String> for OperatingSystem {
+    type Error = String;
+
+    fn try_from(id: String) -> Result<Self, Self::Error> {
+        if is_valid_id(&id) && id != "unset" {
+            Ok(OperatingSystem(Cow::Owned(id)))
+        } else {
+            Err(localized!(
+                pt_br: "Identificador inválido de sistema operacional: {id:?}",
+                en: "Invalid operating system identifier: {id:?}",
+            ))
+        }
+    }
+}
+impl From<OperatingSystem> for String {
+    fn from(os: OperatingSystem) -> Self {
+        os.0.into_owned()
     }
 }
 impl Display for OperatingSystem {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                OperatingSystem::Windows => "Windows",
-                OperatingSystem::Linux => "Linux",
-            }
-        )
+        if *self == OperatingSystem::WINDOWS {
+            write!(f, "Windows")
+        } else if *self == OperatingSystem::LINUX {
+            write!(f, "Linux")
+        } else {
+            write!(f, "{}", self.0)
+        }
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
-pub(crate) enum ProfileId {
-    #[serde(rename = "a")]
-    A,
-    #[serde(rename = "b")]
-    B,
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct LabeledOperatingSystem<'a> {
+    operating_system: &'a OperatingSystem,
+    label: &'a str,
+    index: usize,
 }
-impl Values for ProfileId {
-    fn values() -> [Self; 2] {
-        [Self::A, Self::B]
+impl<'a> LabeledOperatingSystem<'a> {
+    pub(crate) fn get(operating_system: &'a OperatingSystem, configs: &'a Configs) -> Option<Self> {
+        let (index, _, os_configs) = configs.operating_system.get_full(operating_system)?;
+        let label = os_configs.label.as_deref().unwrap_or_default();
+        Some(Self::new(operating_system, label, index))
+    }
+
+    pub(crate) fn new(operating_system: &'a OperatingSystem, label: &'a str, index: usize) -> Self {
+        Self {
+            operating_system,
+            label,
+            index,
+        }
+    }
+
+    pub(crate) fn operating_system(self) -> &'a OperatingSystem {
+        self.operating_system
+    }
+
+    pub(crate) fn index(self) -> usize {
+        self.index
+    }
+}
+impl Display for LabeledOperatingSystem<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.label.is_empty() {
+            write!(f, "{}", self.operating_system)
+        } else {
+            write!(f, "{}", self.label)
+        }
+    }
+}
+
+/// Identifies one of the display profiles defined in the configs file (e.g. `tv`, `monitor`).
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub(crate) struct ProfileId(Cow<'static, str>);
+impl ProfileId {
+    // The profiles created by default.
+    pub(crate) const A: ProfileId = ProfileId(Cow::Borrowed("a"));
+    pub(crate) const B: ProfileId = ProfileId(Cow::Borrowed("b"));
+
+    /// These names have special meanings in the profile switching options.
+    const RESERVED_IDS: [&str; 3] = ["other", "saved", "unset"];
+}
+impl TryFrom<String> for ProfileId {
+    type Error = String;
+
+    fn try_from(id: String) -> Result<Self, Self::Error> {
+        if is_valid_id(&id) && !Self::RESERVED_IDS.contains(&id.as_str()) {
+            Ok(ProfileId(Cow::Owned(id)))
+        } else {
+            Err(localized!(
+                pt_br: "Identificador inválido de perfil: {id:?}",
+                en: "Invalid profile identifier: {id:?}",
+            ))
+        }
+    }
+}
+impl From<ProfileId> for String {
+    fn from(id: ProfileId) -> Self {
+        id.0.into_owned()
     }
 }
 impl Display for ProfileId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                ProfileId::A => "A",
-                ProfileId::B => "B",
-            }
-        )
+        write!(f, "{}", self.0)
     }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub(crate) struct LabeledProfile<'a> {
-    profile_id: ProfileId,
+    profile_id: &'a ProfileId,
     label: &'a str,
+    index: usize,
 }
 impl<'a> LabeledProfile<'a> {
-    pub(crate) fn get(profile_id: ProfileId, configs: &'a Configs) -> Self {
-        let label = &configs.profile[profile_id].label;
-        Self::new(profile_id, label)
+    pub(crate) fn get(profile_id: &'a ProfileId, configs: &'a Configs) -> Option<Self> {
+        let (index, _, profile_configs) = configs.profile.get_full(profile_id)?;
+        Some(Self::new(profile_id, &profile_configs.label, index))
     }
 
-    pub(crate) fn new(profile_id: ProfileId, label: &'a str) -> Self {
-        Self { profile_id, label }
+    pub(crate) fn new(profile_id: &'a ProfileId, label: &'a str, index: usize) -> Self {
+        Self {
+            profile_id,
+            label,
+            index,
+        }
     }
 
-    pub(crate) fn profile_id(self) -> ProfileId {
+    pub(crate) fn profile_id(self) -> &'a ProfileId {
         self.profile_id
     }
+
+    pub(crate) fn index(self) -> usize {
+        self.index
+    }
 }
 impl Display for LabeledProfile<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -91,10 +181,37 @@ pub enum RebootAction {
     Reboot,
     #[serde(rename = "shutdown")]
     Shutdown,
+    /// Reboots into the UEFI firmware setup.
+    #[serde(rename = "firmware-setup")]
+    FirmwareSetup,
+    #[serde(rename = "suspend")]
+    Suspend,
+    #[serde(rename = "hibernate")]
+    Hibernate,
+    #[serde(rename = "logout")]
+    Logout,
+    /// Loads the next Linux kernel directly, skipping the firmware and GRUB.
+    #[serde(rename = "kexec")]
+    Kexec,
+}
+impl RebootAction {
+    /// Whether the action can be performed at a later time, through [`Schedule`].
+    pub(crate) fn can_be_scheduled(self) -> bool {
+        matches!(self, RebootAction::Reboot | RebootAction::Shutdown)
+    }
 }
 impl Values for RebootAction {
-    fn values() -> [Self; 2] {
-        [RebootAction::Reboot, RebootAction::Shutdown]
+    fn values() -> &'static [Self] {
+        &[
+            RebootAction::Reboot,
+            RebootAction::Shutdown,
+            RebootAction::FirmwareSetup,
+            RebootAction::Suspend,
+            RebootAction::Hibernate,
+            RebootAction::Logout,
+            #[cfg(not(windows))]
+            RebootAction::Kexec,
+        ]
     }
 }
 impl Display for RebootAction {
@@ -103,13 +220,104 @@ impl Display for RebootAction {
             f,
             "{}",
             match self {
-                RebootAction::Reboot => "reiniciar",
-                RebootAction::Shutdown => "desligar",
+                RebootAction::Reboot => Localized::new("reiniciar", "reboot"),
+                RebootAction::Shutdown => Localized::new("desligar", "shut down"),
+                RebootAction::FirmwareSetup => Localized::new(
+                    "reiniciar na configuração do firmware",
+                    "reboot into the firmware setup",
+                ),
+                RebootAction::Suspend => Localized::new("suspender", "suspend"),
+                RebootAction::Hibernate => Localized::new("hibernar", "hibernate"),
+                RebootAction::Logout => Localized::new("encerrar a sessão", "log out"),
+                RebootAction::Kexec => Localized::new("reiniciar via kexec", "reboot via kexec"),
             }
         )
     }
 }
 
+/// When a [`RebootAction`] is to be performed, instead of immediately.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub(crate) enum Schedule {
+    /// After a number of minutes, written as `+10`, `+10m` or `+2h`.
+    In(u32),
+    /// At the next occurrence of a time of the day, written as `23:30`.
+    At(NaiveTime),
+}
+impl Schedule {
+    /// The date and time of the schedule, as seen from `now`.
+    pub(crate) fn resolve(self, now: DateTime<Local>) -> DateTime<Local> {
+        match self {
+            Schedule::In(minutes) => now + TimeDelta::minutes(minutes.into()),
+            Schedule::At(time) => {
+                let mut date = now.date_naive();
+                if time <= now.time() {
+                    date = date.succ_opt().unwrap_or(date);
+                }
+                date.and_time(time)
+                    .and_local_timezone(Local)
+                    .earliest()
+                    .unwrap_or(now)
+            }
+        }
+    }
+}
+impl TryFrom<String> for Schedule {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let schedule = if let Some(delay) = s.strip_prefix('+') {
+            let (number, factor) = if let Some(hours) = delay.strip_suffix('h') {
+                (hours, 60)
+            } else {
+                (delay.strip_suffix('m').unwrap_or(delay), 1)
+            };
+            number
+                .parse::<u32>()
+                .ok()
+                .and_then(|number| number.checked_mul(factor))
+                .map(Schedule::In)
+        } else {
+            NaiveTime::parse_from_str(&s, "%H:%M")
+                .ok()
+                .map(Schedule::At)
+        };
+
+        schedule.ok_or_else(|| {
+            localized!(
+                pt_br: "Agendamento inválido: {s:?} (exemplos: +10m, +2h, 23:30)",
+                en: "Invalid schedule: {s:?} (examples: +10m, +2h, 23:30)",
+            )
+        })
+    }
+}
+impl From<Schedule> for String {
+    fn from(schedule: Schedule) -> Self {
+        match schedule {
+            Schedule::In(minutes) if minutes > 0 && minutes % 60 == 0 => {
+                format!("+{}h", minutes / 60)
+            }
+            Schedule::In(minutes) => format!("+{minutes}m"),
+            Schedule::At(time) => time.format("%H:%M").to_string(),
+        }
+    }
+}
+impl Display for Schedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Schedule::In(minutes) => write!(
+                f,
+                "{}",
+                localized!(pt_br: "em {minutes} min", en: "in {minutes} min")
+            ),
+            Schedule::At(time) => {
+                let time = time.format("%H:%M");
+                write!(f, "{}", localized!(pt_br: "às {time}", en: "at {time}"))
+            }
+        }
+    }
+}
+
 pub(crate) trait SerializeToString {
     fn serialize_to_string(&self) -> String;
 }
@@ -128,3 +336,54 @@ impl<'de, T: Deserialize<'de>> DeserializeFromString for T {
         T::deserialize(deserializer).ok()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone as _;
+
+    use super::*;
+
+    #[test]
+    fn schedule_from_string() {
+        let at = |h, m| Schedule::At(NaiveTime::from_hms_opt(h, m, 0).unwrap());
+        let cases = [
+            ("+10", Some(Schedule::In(10))),
+            ("+10m", Some(Schedule::In(10))),
+            ("+2h", Some(Schedule::In(120))),
+            ("23:30", Some(at(23, 30))),
+            ("7:05", Some(at(7, 5))),
+            ("+", None),
+            ("+10s", None),
+            ("-10m", None),
+            ("24:00", None),
+            ("", None),
+        ];
+
+        for (s, expected) in cases {
+            assert_eq!(Schedule::try_from(s.to_string()).ok(), expected, "{s:?}");
+        }
+
+        assert_eq!(String::from(Schedule::In(120)), "+2h");
+        assert_eq!(String::from(Schedule::In(90)), "+90m");
+        assert_eq!(String::from(at(7, 5)), "07:05");
+    }
+
+    #[test]
+    fn schedule_resolve() {
+        let now = Local.with_ymd_and_hms(2024, 5, 10, 22, 0, 0).unwrap();
+        let at = |h, m| Schedule::At(NaiveTime::from_hms_opt(h, m, 0).unwrap());
+
+        assert_eq!(
+            Schedule::In(90).resolve(now),
+            Local.with_ymd_and_hms(2024, 5, 10, 23, 30, 0).unwrap()
+        );
+        assert_eq!(
+            at(23, 30).resolve(now),
+            Local.with_ymd_and_hms(2024, 5, 10, 23, 30, 0).unwrap()
+        );
+        assert_eq!(
+            at(6, 0).resolve(now),
+            Local.with_ymd_and_hms(2024, 5, 11, 6, 0, 0).unwrap()
+        );
+    }
+}
@@ -0,0 +1,318 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::args::OutputFormat;
+use crate::host_os::StateDir;
+use crate::options_types::{LabeledOperatingSystem, OperatingSystem, ProfileId, RebootAction};
+use crate::persist::configs::{Configs, PredefinedScript};
+use crate::script::{Script, ScriptHooks};
+use crate::state::{NextBootEntry, State};
+
+/// The state, as emitted by `my-reboot show --format ...`.
+#[derive(Serialize)]
+pub(crate) struct StateOutput<'a> {
+    next_boot_operating_system: Option<LabeledValue<'a, OperatingSystem>>,
+    saved_entry: Option<&'a str>,
+    one_shot_boot_operating_system: Option<LabeledValue<'a, OperatingSystem>>,
+    next_entry: Option<&'a str>,
+    next_windows_boot_profile: Option<LabeledValue<'a, ProfileId>>,
+    #[cfg(windows)]
+    current_profile: Option<LabeledValue<'a, ProfileId>>,
+    pending_action: Option<PendingActionOutput>,
+    state_dir: &'a StateDir,
+}
+impl<'a> StateOutput<'a> {
+    pub(crate) fn new(state: &'a State, configs: &'a Configs, state_dir: &'a StateDir) -> Self {
+        StateOutput {
+            next_boot_operating_system: LabeledValue::next_boot_entry(
+                state.next_boot_operating_system.as_ref(),
+                configs,
+            ),
+            saved_entry: state.saved_entry.as_deref(),
+            one_shot_boot_operating_system: LabeledValue::next_boot_entry(
+                state.one_shot_boot_operating_system.as_ref(),
+                configs,
+            ),
+            next_entry: state.next_entry.as_deref(),
+            next_windows_boot_profile: state
+                .next_windows_boot_profile
+                .as_ref()
+                .map(|id| LabeledValue::profile(id, configs)),
+            #[cfg(windows)]
+            current_profile: state
+                .current_profile
+                .as_ref()
+                .map(|id| LabeledValue::profile(id, configs)),
+            pending_action: state
+                .pending_action
+                .map(|pending_action| PendingActionOutput {
+                    action: pending_action.action,
+                    at: pending_action.at.to_rfc3339(),
+                }),
+            state_dir,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct PendingActionOutput {
+    action: RebootAction,
+    at: String,
+}
+
+#[derive(Serialize)]
+struct LabeledValue<'a, T> {
+    id: &'a T,
+    label: String,
+}
+impl<'a> LabeledValue<'a, OperatingSystem> {
+    fn next_boot_entry(entry: Option<&'a NextBootEntry>, configs: &Configs) -> Option<Self> {
+        match entry? {
+            NextBootEntry::OperatingSystem(os) => Some(LabeledValue {
+                id: os,
+                label: LabeledOperatingSystem::get(os, configs)
+                    .map_or_else(|| os.to_string(), |labeled_os| labeled_os.to_string()),
+            }),
+            NextBootEntry::Unknown(_) => None,
+        }
+    }
+}
+impl<'a> LabeledValue<'a, ProfileId> {
+    fn profile(id: &'a ProfileId, configs: &Configs) -> Self {
+        let label = configs
+            .profile
+            .get(id)
+            .map_or_else(|| id.to_string(), |profile| profile.label.clone());
+        LabeledValue { id, label }
+    }
+}
+
+/// The predefined scripts, as emitted by `my-reboot script list --format ...`.
+#[derive(Serialize)]
+pub(crate) struct PredefinedScriptsOutput<'a> {
+    scripts: Vec<PredefinedScriptOutput<'a>>,
+}
+impl<'a> PredefinedScriptsOutput<'a> {
+    pub(crate) fn new(predef_scripts: &'a [PredefinedScript], configs: &Configs) -> Self {
+        let scripts = predef_scripts
+            .iter()
+            .enumerate()
+            .map(|(i, predef_script)| PredefinedScriptOutput {
+                number: i + 1,
+                label: predef_script.resolve_label(configs),
+                script: &predef_script.script,
+                confirm: predef_script.confirm,
+                hooks: &predef_script.hooks,
+            })
+            .collect();
+
+        PredefinedScriptsOutput { scripts }
+    }
+}
+
+#[derive(Serialize)]
+struct PredefinedScriptOutput<'a> {
+    number: usize,
+    label: String,
+    #[serde(flatten)]
+    script: &'a Script,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    confirm: bool,
+    #[serde(flatten)]
+    hooks: &'a ScriptHooks,
+}
+
+/// Serializes the value in the given format, which must not be [`OutputFormat::Text`].
+pub(crate) fn serialize(format: OutputFormat, value: &impl Serialize) -> Result<String> {
+    let serialized = match format {
+        OutputFormat::Json => serde_json::to_string_pretty(value)?,
+        OutputFormat::Toml => toml::to_string(value)?,
+        OutputFormat::Text => unreachable!("Text output is not serialized"),
+    };
+    Ok(serialized)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::host_os::StateDirSource;
+    use crate::script::SetOrUnset;
+
+    fn state_dir() -> StateDir {
+        StateDir {
+            path: PathBuf::from("/state-dir"),
+            source: StateDirSource::UserConfig(PathBuf::from(
+                "/home/user/.config/my-reboot/config.toml",
+            )),
+        }
+    }
+
+    #[test]
+    fn state_output() -> Result<()> {
+        let configs = configs()?;
+        let state = State {
+            next_boot_operating_system: Some(NextBootEntry::OperatingSystem(
+                OperatingSystem::LINUX,
+            )),
+            saved_entry: Some("linux-grub-entry".to_string()),
+            one_shot_boot_operating_system: Some(NextBootEntry::OperatingSystem(
+                OperatingSystem::WINDOWS,
+            )),
+            next_entry: Some("windows-grub-entry".to_string()),
+            next_windows_boot_profile: Some(ProfileId::B),
+            #[cfg(windows)]
+            current_profile: None,
+            pending_action: None,
+        };
+
+        let state_dir = state_dir();
+        let output = StateOutput::new(&state, &configs, &state_dir);
+
+        assert_eq!(
+            serialize(OutputFormat::Json, &output)?,
+            r#"{
+  "next_boot_operating_system": {
+    "id": "linux",
+    "label": "Linux"
+  },
+  "saved_entry": "linux-grub-entry",
+  "one_shot_boot_operating_system": {
+    "id": "windows",
+    "label": "Windows"
+  },
+  "next_entry": "windows-grub-entry",
+  "next_windows_boot_profile": {
+    "id": "b",
+    "label": "Monitor"
+  },
+  "pending_action": null,
+  "state_dir": {
+    "path": "/state-dir",
+    "source": "user_config",
+    "file": "/home/user/.config/my-reboot/config.toml"
+  }
+}"#
+        );
+        assert_eq!(
+            serialize(OutputFormat::Toml, &output)?,
+            r#"saved_entry = "linux-grub-entry"
+next_entry = "windows-grub-entry"
+
+[next_boot_operating_system]
+id = "linux"
+label = "Linux"
+
+[one_shot_boot_operating_system]
+id = "windows"
+label = "Windows"
+
+[next_windows_boot_profile]
+id = "b"
+label = "Monitor"
+
+[state_dir]
+path = "/state-dir"
+source = "user_config"
+file = "/home/user/.config/my-reboot/config.toml"
+"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn state_output_unknown_entry() -> Result<()> {
+        let configs = configs()?;
+        let state = State {
+            next_boot_operating_system: Some(NextBootEntry::Unknown("hand-picked".to_string())),
+            saved_entry: Some("hand-picked".to_string()),
+            one_shot_boot_operating_system: None,
+            next_entry: None,
+            next_windows_boot_profile: None,
+            #[cfg(windows)]
+            current_profile: None,
+            pending_action: None,
+        };
+
+        let state_dir = state_dir();
+        let output = StateOutput::new(&state, &configs, &state_dir);
+
+        assert_eq!(
+            serialize(OutputFormat::Toml, &output)?,
+            "saved_entry = \"hand-picked\"\n\n[state_dir]\npath = \"/state-dir\"\nsource = \"user_config\"\nfile = \"/home/user/.config/my-reboot/config.toml\"\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn predefined_scripts_output() -> Result<()> {
+        let configs = configs()?;
+        let scripts = &configs.operating_system[&OperatingSystem::LINUX].scripts;
+        assert_eq!(
+            scripts[0].script,
+            Script {
+                next_boot_operating_system: Some(SetOrUnset::Set(OperatingSystem::WINDOWS)),
+                reboot_action: Some(RebootAction::Reboot),
+                ..Script::new()
+            }
+        );
+
+        let output = PredefinedScriptsOutput::new(scripts, &configs);
+
+        assert_eq!(
+            serialize(OutputFormat::Json, &output)?,
+            r#"{
+  "scripts": [
+    {
+      "number": 1,
+      "label": "Reiniciar no Windows",
+      "next_boot_operating_system": "windows",
+      "next_windows_boot_profile": null,
+      "switch_to_profile": null,
+      "reboot_action": "reboot"
+    }
+  ]
+}"#
+        );
+        assert_eq!(
+            serialize(OutputFormat::Toml, &output)?,
+            r#"[[scripts]]
+number = 1
+label = "Reiniciar no Windows"
+next_boot_operating_system = "windows"
+reboot_action = "reboot"
+"#
+        );
+
+        Ok(())
+    }
+
+    fn configs() -> Result<Configs> {
+        let configs = toml::from_str(
+            r#"
+            [operating_system.windows]
+            grub_entry = "windows-grub-entry"
+
+            [operating_system.linux]
+            grub_entry = "linux-grub-entry"
+
+            [[operating_system.linux.scripts]]
+            next_boot_operating_system = "windows"
+            reboot_action = "reboot"
+            label_template = "{reboot_action} no {next_boot_operating_system}"
+
+            [profile.a]
+            label = "TV"
+            display_configs = "{}"
+
+            [profile.b]
+            label = "Monitor"
+            display_configs = "{}"
+            "#,
+        )?;
+        Ok(configs)
+    }
+}
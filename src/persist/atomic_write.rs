@@ -0,0 +1,137 @@
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::text::localized;
+
+/// Writes the content to the file in a way that a crash or power cut never leaves it truncated.
+///
+/// The content is written to a temporary file in the same directory, flushed to disk and then
+/// renamed over the target file, whose permissions are kept. Finally, the file is re-read and
+/// compared with the content.
+pub(crate) fn write(path: &Path, content: impl AsRef<[u8]>) -> io::Result<()> {
+    let content = content.as_ref();
+    let temp_path = temp_path(path);
+    let permissions = fs::metadata(path)
+        .ok()
+        .map(|metadata| metadata.permissions());
+
+    let result =
+        write_temp(&temp_path, content, permissions).and_then(|()| fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result?;
+
+    sync_parent_dir(path)?;
+    verify(path, content)
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().map(OsString::from).unwrap_or_default();
+    file_name.push(".tmp");
+    path.with_file_name(file_name)
+}
+
+fn write_temp(
+    temp_path: &Path,
+    content: &[u8],
+    permissions: Option<fs::Permissions>,
+) -> io::Result<()> {
+    let mut file = File::create(temp_path)?;
+    file.write_all(content)?;
+    if let Some(permissions) = permissions {
+        file.set_permissions(permissions)?;
+    }
+    file.sync_all()
+}
+
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => File::open(dir)?.sync_all(),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+#[expect(clippy::unnecessary_wraps)]
+fn sync_parent_dir(_path: &Path) -> io::Result<()> {
+    // Directories can't be opened for syncing on Windows. The rename is already durable there.
+    Ok(())
+}
+
+fn verify(path: &Path, content: &[u8]) -> io::Result<()> {
+    if fs::read(path)? == content {
+        Ok(())
+    } else {
+        Err(io::Error::new(io::ErrorKind::InvalidData, {
+            let path = path.display();
+            localized!(
+                pt_br: "O conteúdo de {path} difere do que foi escrito",
+                en: "The content of {path} differs from what was written",
+            )
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::write;
+
+    #[test]
+    fn write_creates_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.txt");
+
+        write(&path, "content").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "content");
+    }
+
+    #[test]
+    fn write_replaces_file_without_leftovers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.txt");
+        fs::write(&path, "a longer previous content").unwrap();
+
+        write(&path, "new content").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new content");
+        let entries: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(entries, ["file.txt"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_keeps_the_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("script");
+        fs::write(&path, "previous content").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+
+        write(&path, "new content").unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+    }
+
+    #[test]
+    fn write_fails_without_touching_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("missing-dir").join("file.txt");
+
+        let result = write(&path, "content");
+
+        assert!(result.is_err());
+        assert!(!path.exists());
+    }
+}
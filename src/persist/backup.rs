@@ -0,0 +1,332 @@
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Local, Utc};
+
+use crate::host_os::state_dir_path;
+use crate::persist::atomic_write;
+use crate::persist::configs::CONFIGS_FILENAME;
+use crate::persist::grubenv::GRUBENV_FILENAME;
+use crate::persist::options::OPTIONS_FILENAME;
+use crate::text::localized;
+
+const BACKUPS_DIRNAME: &str = "my-reboot-backups";
+const MAX_BACKUPS: usize = 10;
+/// Of the snapshots still being written.
+const TEMP_EXTENSION: &str = "tmp";
+const FILENAMES: [&str; 3] = [GRUBENV_FILENAME, OPTIONS_FILENAME, CONFIGS_FILENAME];
+
+/// The content of each state file, or `None` if the file doesn't exist.
+type Contents = Vec<(&'static str, Option<Vec<u8>>)>;
+
+/// Takes a snapshot of the state files before they're changed.
+pub(crate) fn back_up_state_files() -> Result<()> {
+    Backups::new().snapshot().with_context(|| {
+        localized!(
+            pt_br: "Falha ao fazer backup dos arquivos de estado",
+            en: "Failed to back up the state files",
+        )
+    })
+}
+
+/// Rotating snapshots of the files in the state directory.
+pub(crate) struct Backups {
+    state_dir: PathBuf,
+}
+impl Backups {
+    pub(crate) fn new() -> Self {
+        Self::at(state_dir_path())
+    }
+
+    fn at(state_dir: PathBuf) -> Self {
+        Self { state_dir }
+    }
+
+    /// The existing backups, from the newest to the oldest.
+    pub(crate) fn list(&self) -> Result<Vec<Backup>> {
+        let entries = match fs::read_dir(self.dir()) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut backups = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            // Snapshots still being written have a suffix and are skipped.
+            if let Some(timestamp_millis) = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.parse().ok())
+            {
+                backups.push(Backup {
+                    path: entry.path(),
+                    timestamp_millis,
+                });
+            }
+        }
+
+        backups.sort_by_key(|backup| std::cmp::Reverse(backup.timestamp_millis));
+        Ok(backups)
+    }
+
+    /// Saves the current state files as a new backup, unless they are identical to the newest
+    /// backup. The oldest backups are removed so that at most `MAX_BACKUPS` are kept.
+    pub(crate) fn snapshot(&self) -> Result<()> {
+        let contents = read_contents(&self.state_dir)?;
+        if contents.iter().all(|(_, content)| content.is_none()) {
+            return Ok(());
+        }
+
+        let backups = self.list()?;
+        let latest = backups.first();
+        if let Some(latest) = latest
+            && latest.contents()? == contents
+        {
+            return Ok(());
+        }
+
+        let timestamp_millis = match latest {
+            Some(latest) => Utc::now()
+                .timestamp_millis()
+                .max(latest.timestamp_millis + 1),
+            None => Utc::now().timestamp_millis(),
+        };
+
+        self.remove_unfinished_snapshots()?;
+        // The snapshot is only visible after all of its files are written.
+        let path = self.dir().join(timestamp_millis.to_string());
+        let temp_path = path.with_extension(TEMP_EXTENSION);
+        fs::create_dir_all(&temp_path)?;
+        for (filename, content) in &contents {
+            if let Some(content) = content {
+                atomic_write::write(&temp_path.join(filename), content)?;
+            }
+        }
+        fs::rename(&temp_path, &path)?;
+
+        for old_backup in backups.iter().skip(MAX_BACKUPS - 1) {
+            fs::remove_dir_all(&old_backup.path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Brings back the state files from the backup with the given number, where 1 is the newest.
+    ///
+    /// The current state is saved as a new backup before, so that the restoration can be undone.
+    pub(crate) fn restore(&self, number: NonZeroUsize) -> Result<Backup> {
+        let mut backups = self.list()?;
+        if backups.is_empty() {
+            bail!(localized!(
+                pt_br: "Não há backups para restaurar",
+                en: "There are no backups to restore",
+            ));
+        }
+        if number.get() > backups.len() {
+            let max = backups.len();
+            bail!(localized!(
+                pt_br: "Número inválido de backup (mín: 1; máx: {max})",
+                en: "Invalid backup number (min: 1; max: {max})",
+            ));
+        }
+
+        let backup = backups.swap_remove(number.get() - 1);
+        let contents = backup.contents()?;
+
+        self.snapshot()?;
+
+        for (filename, content) in contents {
+            let path = self.state_dir.join(filename);
+            match content {
+                Some(content) => atomic_write::write(&path, content)?,
+                None => match fs::remove_file(&path) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                    _ => {}
+                },
+            }
+        }
+
+        Ok(backup)
+    }
+
+    /// Removes the snapshots left behind by interrupted executions.
+    fn remove_unfinished_snapshots(&self) -> Result<()> {
+        let entries = match fs::read_dir(self.dir()) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+
+        for entry in entries {
+            let path = entry?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == TEMP_EXTENSION)
+            {
+                fs::remove_dir_all(&path)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn dir(&self) -> PathBuf {
+        self.state_dir.join(BACKUPS_DIRNAME)
+    }
+}
+
+pub(crate) struct Backup {
+    path: PathBuf,
+    timestamp_millis: i64,
+}
+impl Backup {
+    pub(crate) fn created_at(&self) -> Option<DateTime<Local>> {
+        DateTime::from_timestamp_millis(self.timestamp_millis).map(|utc| utc.with_timezone(&Local))
+    }
+
+    fn contents(&self) -> Result<Contents> {
+        read_contents(&self.path)
+    }
+}
+
+fn read_contents(dir: &Path) -> Result<Contents> {
+    FILENAMES
+        .into_iter()
+        .map(|filename| match fs::read(dir.join(filename)) {
+            Ok(content) => Ok((filename, Some(content))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok((filename, None)),
+            Err(e) => Err(e.into()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::num::NonZeroUsize;
+    use std::path::Path;
+
+    use tempfile::TempDir;
+
+    use super::{BACKUPS_DIRNAME, Backups, MAX_BACKUPS};
+    use crate::persist::configs::CONFIGS_FILENAME;
+    use crate::persist::grubenv::GRUBENV_FILENAME;
+    use crate::persist::options::OPTIONS_FILENAME;
+
+    #[test]
+    fn snapshot_and_restore() {
+        let (dir, backups) = create_backups();
+        write_state(dir.path(), "grubenv 1", "configs 1");
+        backups.snapshot().unwrap();
+        write_state(dir.path(), "grubenv 2", "configs 2");
+
+        backups.restore(number(1)).unwrap();
+
+        assert_state(dir.path(), "grubenv 1", "configs 1");
+        assert!(!dir.path().join(OPTIONS_FILENAME).exists());
+    }
+
+    #[test]
+    fn restore_can_be_undone() {
+        let (dir, backups) = create_backups();
+        write_state(dir.path(), "grubenv 1", "configs 1");
+        backups.snapshot().unwrap();
+        write_state(dir.path(), "grubenv 2", "configs 2");
+
+        backups.restore(number(1)).unwrap();
+        backups.restore(number(1)).unwrap();
+
+        assert_state(dir.path(), "grubenv 2", "configs 2");
+    }
+
+    #[test]
+    fn restore_removes_files_missing_from_backup() {
+        let (dir, backups) = create_backups();
+        write_state(dir.path(), "grubenv", "configs");
+        backups.snapshot().unwrap();
+        fs::write(dir.path().join(OPTIONS_FILENAME), "options").unwrap();
+
+        backups.restore(number(1)).unwrap();
+
+        assert!(!dir.path().join(OPTIONS_FILENAME).exists());
+    }
+
+    #[test]
+    fn restore_invalid_number() {
+        let (dir, backups) = create_backups();
+        assert!(backups.restore(number(1)).is_err());
+
+        write_state(dir.path(), "grubenv", "configs");
+        backups.snapshot().unwrap();
+        assert!(backups.restore(number(2)).is_err());
+
+        assert_state(dir.path(), "grubenv", "configs");
+    }
+
+    #[test]
+    fn snapshot_skips_unchanged_state() {
+        let (dir, backups) = create_backups();
+        write_state(dir.path(), "grubenv", "configs");
+
+        backups.snapshot().unwrap();
+        backups.snapshot().unwrap();
+
+        assert_eq!(backups.list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn snapshot_removes_unfinished_snapshots() {
+        let (dir, backups) = create_backups();
+        let unfinished_path = dir.path().join(BACKUPS_DIRNAME).join("1000.tmp");
+        fs::create_dir_all(&unfinished_path).unwrap();
+        fs::write(unfinished_path.join(GRUBENV_FILENAME), "grubenv").unwrap();
+        write_state(dir.path(), "grubenv", "configs");
+
+        backups.snapshot().unwrap();
+
+        assert!(!unfinished_path.exists());
+        assert_eq!(backups.list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn snapshot_rotates_backups() {
+        let (dir, backups) = create_backups();
+
+        for i in 0..MAX_BACKUPS + 3 {
+            write_state(dir.path(), &format!("grubenv {i}"), "configs");
+            backups.snapshot().unwrap();
+        }
+
+        assert_eq!(backups.list().unwrap().len(), MAX_BACKUPS);
+        backups.restore(number(MAX_BACKUPS)).unwrap();
+        assert_state(dir.path(), "grubenv 3", "configs");
+    }
+
+    fn create_backups() -> (TempDir, Backups) {
+        let dir = tempfile::tempdir().unwrap();
+        let backups = Backups::at(dir.path().to_path_buf());
+        (dir, backups)
+    }
+
+    fn write_state(dir: &Path, grubenv: &str, configs: &str) {
+        fs::write(dir.join(GRUBENV_FILENAME), grubenv).unwrap();
+        fs::write(dir.join(CONFIGS_FILENAME), configs).unwrap();
+    }
+
+    fn assert_state(dir: &Path, grubenv: &str, configs: &str) {
+        assert_eq!(
+            fs::read_to_string(dir.join(GRUBENV_FILENAME)).unwrap(),
+            grubenv
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join(CONFIGS_FILENAME)).unwrap(),
+            configs
+        );
+    }
+
+    fn number(n: usize) -> NonZeroUsize {
+        NonZeroUsize::new(n).unwrap()
+    }
+}
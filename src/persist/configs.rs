@@ -1,20 +1,31 @@
+mod migrations;
+mod validation;
+
+use std::num::NonZeroUsize;
 use std::ops::Index;
 use std::path::PathBuf;
 use std::{fs, io};
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, anyhow, bail};
 #[cfg(windows)]
 use display_profile_lib::Profile;
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
+pub(crate) use self::migrations::CURRENT_VERSION;
+pub(crate) use self::validation::Diagnostic;
 use crate::host_os::{TemplateResolver, state_path};
 use crate::options_types::{
-    LabeledProfile, OperatingSystem, ProfileId, RebootAction, SerializeToString, Values as _,
+    LabeledOperatingSystem, LabeledProfile, OperatingSystem, ProfileId, RebootAction,
+    SerializeToString,
 };
-use crate::script::{Script, SetOrUnset};
-use crate::text::{self, Capitalized};
+use crate::persist::atomic_write;
+use crate::script::{ExecutionOptions, Script, ScriptHooks, SetOrUnset};
+#[cfg(not(test))]
+use crate::text::Locale;
+use crate::text::{self, Capitalized, Localized, localized};
 
-const CONFIGS_FILENAME: &str = "my-reboot-configs.toml";
+pub(crate) const CONFIGS_FILENAME: &str = "my-reboot-configs.toml";
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub(crate) struct Configs {
@@ -23,103 +34,248 @@ pub(crate) struct Configs {
 }
 impl Configs {
     pub(crate) fn load() -> Result<Configs> {
-        match fs::read_to_string(Self::path()) {
-            Ok(content) => {
-                let configs =
-                    Self::from_serialized(&content).with_context(|| "O conteúdo do arquivo de configurações está incompleto ou é inválido.")?;
-                Ok(configs)
+        let configs = Self::from_serialized(&Self::read_file()?).with_context(|| {
+            localized!(
+                pt_br: "O conteúdo do arquivo de configurações está incompleto ou é inválido.",
+                en: "The content of the configs file is incomplete or invalid.",
+            )
+        })?;
+        Ok(configs)
+    }
+
+    /// Reads the configs, migrating them from the layout of older versions.
+    pub(crate) fn from_serialized(serialized: &str) -> Result<Self> {
+        let mut content: toml::Table = toml::from_str(serialized)?;
+        migrations::migrate(&mut content)?;
+        let configs: Configs = content.try_into()?;
+
+        for os in [OperatingSystem::WINDOWS, OperatingSystem::LINUX] {
+            if configs.operating_system.get(&os).is_none() {
+                bail!(localized!(
+                    pt_br: "Configurações do sistema operacional {os} não encontradas",
+                    en: "Configs of the operating system {os} not found",
+                ));
             }
-            Err(e) if e.kind() == io::ErrorKind::NotFound => {
-                Err(e).context("Arquivo de configurações não encontrado. Execute 'my-reboot configure' no Windows e no Linux para criar o arquivo com todo o conteúdo necessário.")
-            },
-            Err(e) => Err(e.into()),
         }
-    }
 
-    fn from_serialized(serialized: &str) -> Result<Self> {
-        let configs = toml::from_str(serialized)?;
         Ok(configs)
     }
 
-    pub(crate) fn operating_system_by_grub_entry(&self, grub_entry: &str) -> OperatingSystem {
-        OperatingSystem::values()
-            .into_iter()
-            .find(|os| self.operating_system[*os].grub_entry == grub_entry)
-            .unwrap()
+    pub(crate) fn operating_system_by_grub_entry(
+        &self,
+        grub_entry: &str,
+    ) -> Option<OperatingSystem> {
+        self.operating_system
+            .iter()
+            .find(|(_, os_configs)| os_configs.grub_entry == grub_entry)
+            .map(|(os, _)| os.clone())
+    }
+
+    pub(crate) fn labeled_operating_system<'a>(
+        &'a self,
+        os: &'a OperatingSystem,
+    ) -> Result<LabeledOperatingSystem<'a>> {
+        LabeledOperatingSystem::get(os, self).ok_or_else(|| {
+            let id = os.id();
+            anyhow!(localized!(
+                pt_br: "Sistema operacional desconhecido: {id}",
+                en: "Unknown operating system: {id}",
+            ))
+        })
+    }
+
+    pub(crate) fn labeled_profile<'a>(&'a self, id: &'a ProfileId) -> Result<LabeledProfile<'a>> {
+        LabeledProfile::get(id, self).ok_or_else(|| {
+            anyhow!(localized!(
+                pt_br: "Perfil desconhecido: {id}",
+                en: "Unknown profile: {id}",
+            ))
+        })
+    }
+
+    /// The profile that follows the given one, wrapping around to the first one.
+    #[cfg(any(windows, test))]
+    pub(crate) fn profile_after(&self, id: &ProfileId) -> Option<&ProfileId> {
+        let (index, _, _) = self.profile.get_full(id)?;
+        let (next_id, _) = self.profile.get_index((index + 1) % self.profile.len())?;
+        Some(next_id)
     }
 
     #[cfg(windows)]
     pub(crate) fn profile_id_by_config(&self, profile: &Profile) -> Result<Option<ProfileId>> {
-        for id in ProfileId::values() {
-            let config: Profile = serde_json::from_str(&self.profile[id].display_configs)?;
-            if config == *profile {
-                return Ok(Some(id));
+        for (id, profile_configs) in self.profile.iter() {
+            if profile_configs.display_configs()? == *profile {
+                return Ok(Some(id.clone()));
             }
         }
 
         Ok(None)
     }
 
+    /// The layout version of the configs file.
+    pub(crate) fn file_version() -> Result<i64> {
+        let content: toml::Table = toml::from_str(&fs::read_to_string(Self::path())?)?;
+        migrations::version(&content)
+    }
+
+    /// The problems of the configs file. The Grub entries are only checked if `grub_entries` is
+    /// given.
+    pub(crate) fn validate_file(grub_entries: Option<&[String]>) -> Result<Vec<Diagnostic>> {
+        let serialized = fs::read_to_string(Self::path())?;
+        Ok(validation::validate(&serialized, grub_entries))
+    }
+
+    /// The `locale` key of the configs file, if it can be read.
+    #[cfg(not(test))]
+    pub(crate) fn configured_locale(state_dir: &std::path::Path) -> Option<Locale> {
+        #[derive(Deserialize)]
+        struct LocaleConfigs {
+            locale: Option<Locale>,
+        }
+
+        let content = fs::read_to_string(state_dir.join(CONFIGS_FILENAME)).ok()?;
+        toml::from_str::<LocaleConfigs>(&content).ok()?.locale
+    }
+
     fn path() -> PathBuf {
         state_path(CONFIGS_FILENAME)
     }
+
+    fn read_file() -> Result<String> {
+        match fs::read_to_string(Self::path()) {
+            Ok(content) => Ok(content),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                Err(e).context(localized!(
+                    pt_br: "Arquivo de configurações não encontrado. Execute 'my-reboot configure' no Windows e no Linux para criar o arquivo com todo o conteúdo necessário.",
+                    en: "Configs file not found. Run 'my-reboot configure' on Windows and on Linux to create the file with all the needed content.",
+                ))
+            },
+            Err(e) => Err(e.into()),
+        }
+    }
 }
 
+/// The configs file has an older layout version, and must be migrated before being edited.
+pub(crate) fn outdated_version_error(version: i64) -> anyhow::Error {
+    anyhow!(localized!(
+        pt_br: "O arquivo de configurações tem a versão {version} e precisa ser atualizado para a versão {CURRENT_VERSION}. Execute: my-reboot config migrate",
+        en: "The configs file has the version {version} and needs to be upgraded to the version {CURRENT_VERSION}. Run: my-reboot config migrate",
+    ))
+}
+
+/// The boot targets, in the order they are defined in the configs file.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct OperatingSystemsConfigs {
-    windows: OperatingSystemConfigs,
-    linux: OperatingSystemConfigs,
+#[serde(transparent)]
+pub(crate) struct OperatingSystemsConfigs(IndexMap<OperatingSystem, OperatingSystemConfigs>);
+impl OperatingSystemsConfigs {
+    pub(crate) fn get(&self, os: &OperatingSystem) -> Option<&OperatingSystemConfigs> {
+        self.0.get(os)
+    }
+
+    pub(crate) fn get_full(
+        &self,
+        os: &OperatingSystem,
+    ) -> Option<(usize, &OperatingSystem, &OperatingSystemConfigs)> {
+        self.0.get_full(os)
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&OperatingSystem, &OperatingSystemConfigs)> {
+        self.0.iter()
+    }
+
+    pub(crate) fn keys(&self) -> impl Iterator<Item = &OperatingSystem> {
+        self.0.keys()
+    }
 }
-impl Index<OperatingSystem> for OperatingSystemsConfigs {
+impl Index<&OperatingSystem> for OperatingSystemsConfigs {
     type Output = OperatingSystemConfigs;
 
-    fn index(&self, index: OperatingSystem) -> &Self::Output {
-        match index {
-            OperatingSystem::Windows => &self.windows,
-            OperatingSystem::Linux => &self.linux,
-        }
+    fn index(&self, index: &OperatingSystem) -> &Self::Output {
+        &self.0[index]
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub(crate) struct OperatingSystemConfigs {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) label: Option<String>,
     pub(crate) grub_entry: String,
+    #[serde(default)]
     pub(crate) scripts: Vec<PredefinedScript>,
 }
 
+/// The placeholders resolved by [`PredefinedScript::resolve_label`].
+const LABEL_PLACEHOLDERS: [&str; 5] = [
+    "next_boot_operating_system",
+    "next_windows_boot_profile",
+    "switch_to_profile",
+    "reboot_action",
+    "schedule",
+];
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub(crate) struct PredefinedScript {
     #[serde(flatten)]
     pub(crate) script: Script,
-    pub(crate) label_template: String,
+    /// Absent in the default scripts, which are labeled in the locale of whoever shows them, as
+    /// the configs file is shared by both operating systems.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) label_template: Option<String>,
+    /// Whether the execution is to be confirmed, by a prompt in the CLI or by a countdown in the
+    /// dialog.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) confirm: bool,
+    #[serde(flatten)]
+    pub(crate) hooks: ScriptHooks,
 }
 impl PredefinedScript {
+    pub(crate) fn execute_with(&self, options: ExecutionOptions) -> Result<()> {
+        self.script.execute_with(options, &self.hooks)
+    }
+
+    /// Only scripts that end in a reboot action are confirmed.
+    pub(crate) fn needs_confirmation(&self) -> bool {
+        self.confirm && self.script.reboot_action.is_some()
+    }
+
     pub(crate) fn resolve_label(&self, configs: &Configs) -> String {
-        let profile_label = |profile_id| LabeledProfile::get(profile_id, configs).to_string();
+        let profile_label = |profile_id: ProfileId| match LabeledProfile::get(&profile_id, configs)
+        {
+            Some(labeled_profile) => labeled_profile.to_string(),
+            None => profile_id.to_string(),
+        };
 
-        let mut template_resolver = TemplateResolver::new(&self.label_template);
+        let label_template = match &self.label_template {
+            Some(label_template) => label_template,
+            None => self.default_label_template(),
+        };
+        let mut template_resolver = TemplateResolver::new(label_template);
 
-        template_resolver.resolve_set_or_unset_option(
+        template_resolver.resolve_set_or_unset_option_with(
             "next_boot_operating_system",
-            self.script.next_boot_operating_system,
+            self.script.next_boot_operating_system.clone(),
+            |os| match LabeledOperatingSystem::get(&os, configs) {
+                Some(labeled_os) => labeled_os.to_string(),
+                None => os.to_string(),
+            },
             text::operating_system::UNDEFINED,
         );
         template_resolver.resolve_set_or_unset_option_with(
             "next_windows_boot_profile",
-            self.script.next_windows_boot_profile,
+            self.script.next_windows_boot_profile.clone(),
             profile_label,
             text::profile::UNDEFINED,
         );
-        #[cfg(windows)]
+        // Also resolved on Linux, where it's undefined, so that it doesn't show up as it is.
         template_resolver.resolve_option_with(
             "switch_to_profile",
-            self.script.switch_to_profile,
+            self.script.switch_to_profile.clone(),
             |switch_to| {
                 use crate::script::SwitchToProfile;
                 match switch_to {
-                    SwitchToProfile::Other => "outro".to_string(),
+                    SwitchToProfile::Other => localized!(pt_br: "outro", en: "other"),
                     SwitchToProfile::Profile(profile_id) => profile_label(profile_id),
-                    SwitchToProfile::Saved => "salvo".to_string(),
+                    SwitchToProfile::Saved => localized!(pt_br: "salvo", en: "saved"),
                 }
             },
             text::profile::UNDEFINED,
@@ -129,24 +285,70 @@ impl PredefinedScript {
             self.script.reboot_action,
             text::reboot_action::UNDEFINED,
         );
+        template_resolver.resolve_option(
+            "schedule",
+            self.script.schedule,
+            text::reboot_action::IMMEDIATELY,
+        );
 
         Capitalized(template_resolver.into_label()).to_string()
     }
+
+    fn default_label_template(&self) -> &'static str {
+        if self.script.next_windows_boot_profile.is_some() {
+            Localized::new(
+                "{reboot_action} no {next_boot_operating_system} usando o perfil {next_windows_boot_profile}",
+                "{reboot_action} into {next_boot_operating_system} using the profile {next_windows_boot_profile}",
+            )
+            .get()
+        } else if self.script.next_boot_operating_system.is_some() {
+            Localized::new(
+                "{reboot_action} no {next_boot_operating_system}",
+                "{reboot_action} into {next_boot_operating_system}",
+            )
+            .get()
+        } else {
+            "{reboot_action}"
+        }
+    }
 }
 
+/// The display profiles, in the order they are defined in the configs file.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct ProfilesConfigs {
-    pub(crate) a: ProfileConfigs,
-    pub(crate) b: ProfileConfigs,
+#[serde(transparent)]
+pub(crate) struct ProfilesConfigs(IndexMap<ProfileId, ProfileConfigs>);
+impl ProfilesConfigs {
+    pub(crate) fn get(&self, id: &ProfileId) -> Option<&ProfileConfigs> {
+        self.0.get(id)
+    }
+
+    pub(crate) fn get_full(&self, id: &ProfileId) -> Option<(usize, &ProfileId, &ProfileConfigs)> {
+        self.0.get_full(id)
+    }
+
+    #[cfg(any(windows, test))]
+    pub(crate) fn get_index(&self, index: usize) -> Option<(&ProfileId, &ProfileConfigs)> {
+        self.0.get_index(index)
+    }
+
+    #[cfg(any(windows, test))]
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&ProfileId, &ProfileConfigs)> {
+        self.0.iter()
+    }
+
+    pub(crate) fn keys(&self) -> impl Iterator<Item = &ProfileId> {
+        self.0.keys()
+    }
 }
-impl Index<ProfileId> for ProfilesConfigs {
+impl Index<&ProfileId> for ProfilesConfigs {
     type Output = ProfileConfigs;
 
-    fn index(&self, index: ProfileId) -> &Self::Output {
-        match index {
-            ProfileId::A => &self.a,
-            ProfileId::B => &self.b,
-        }
+    fn index(&self, index: &ProfileId) -> &Self::Output {
+        &self.0[index]
     }
 }
 
@@ -165,8 +367,29 @@ impl ProfileConfigs {
 
 const OPERATING_SYSTEM_KEY: &str = "operating_system";
 const PROFILE_KEY: &str = "profile";
+const LABEL_KEY: &str = "label";
 const GRUB_ENTRY_KEY: &str = "grub_entry";
 const SCRIPTS_KEY: &str = "scripts";
+const LABEL_TEMPLATE_KEY: &str = "label_template";
+const BOOT_ONCE_KEY: &str = "boot_once";
+const SCHEDULE_KEY: &str = "schedule";
+
+/// A change to the predefined scripts of an operating system. The scripts are numbered from 1.
+pub(crate) enum ScriptEdit {
+    Add(PredefinedScript),
+    Remove(NonZeroUsize),
+    Move {
+        from: NonZeroUsize,
+        to: NonZeroUsize,
+    },
+    /// Replaces the label template, if given, and the options that are set in the script,
+    /// keeping the other ones.
+    Edit {
+        number: NonZeroUsize,
+        label_template: Option<String>,
+        script: Script,
+    },
+}
 
 pub(crate) struct ConfigsWriter {
     content: Content,
@@ -174,11 +397,7 @@ pub(crate) struct ConfigsWriter {
 impl ConfigsWriter {
     pub(crate) fn load() -> Result<ConfigsWriter> {
         match fs::read_to_string(Configs::path()) {
-            Ok(content) => {
-                let content: toml::Table = toml::from_str(&content)?;
-                let content = Content::from(content);
-                Ok(ConfigsWriter { content })
-            }
+            Ok(content) => Self::from_serialized(&content),
             Err(e) if e.kind() == io::ErrorKind::NotFound => {
                 let content = Content::default();
                 Ok(ConfigsWriter { content })
@@ -187,23 +406,54 @@ impl ConfigsWriter {
         }
     }
 
+    /// Reads the configs to be changed, migrating them from the layout of older versions.
+    fn from_serialized(serialized: &str) -> Result<ConfigsWriter> {
+        let mut content: toml::Table = toml::from_str(serialized)?;
+        migrations::migrate(&mut content)?;
+        let content = Content::from(content);
+        Ok(ConfigsWriter { content })
+    }
+
+    /// The operating systems that have a table in the configs file, in file order.
+    pub(crate) fn operating_systems(&self) -> Vec<OperatingSystem> {
+        self.content.operating_systems()
+    }
+
+    #[cfg(not(windows))]
+    pub(crate) fn operating_system_label(&self, os: &OperatingSystem) -> Option<&str> {
+        self.content
+            .operating_system_configs_table(os)?
+            .get(LABEL_KEY)?
+            .as_str()
+    }
+
     #[cfg(any(not(windows), test))]
-    pub(crate) fn set_grub_entry(&mut self, os: OperatingSystem, grub_entry: &str) {
+    pub(crate) fn set_grub_entry(&mut self, os: &OperatingSystem, grub_entry: &str) {
         self.content
             .ensure_operating_system_configs_table(os)
             .insert(GRUB_ENTRY_KEY.to_string(), grub_entry.into());
     }
 
-    pub(crate) fn has_grub_entry(&self, os: OperatingSystem) -> bool {
+    pub(crate) fn has_grub_entry(&self, os: &OperatingSystem) -> bool {
         self.content
             .operating_system_configs_table(os)
             .is_some_and(|os_configs| os_configs.contains_key(GRUB_ENTRY_KEY))
     }
 
+    pub(crate) fn profile_ids(&self) -> Vec<ProfileId> {
+        self.content.profile_ids()
+    }
+
+    /// Removes all profiles, so that they can be reconfigured from scratch.
+    #[cfg(any(windows, test))]
+    pub(crate) fn remove_profiles(&mut self) {
+        self.content.0.remove(PROFILE_KEY);
+    }
+
     #[cfg(windows)]
     pub(crate) fn set_profile_configs(
         &mut self,
-        id: ProfileId,
+        id: &ProfileId,
         label: &str,
         display_configs: &Profile,
     ) -> Result<()> {
@@ -213,7 +463,7 @@ impl ConfigsWriter {
     #[cfg(any(windows, test))]
     fn set_profile_configs_strs(
         &mut self,
-        id: ProfileId,
+        id: &ProfileId,
         label: &str,
         configs: &str,
     ) -> Result<()> {
@@ -226,7 +476,63 @@ impl ConfigsWriter {
         Ok(())
     }
 
-    pub(crate) fn has_profile_configs(&self, id: ProfileId) -> bool {
+    /// Replaces all profiles with the ones in the `[profile.*]` tables of the serialized content,
+    /// which has the same format as the configs file.
+    #[cfg(any(windows, test))]
+    pub(crate) fn import_profiles(
+        &mut self,
+        serialized: &str,
+        validate_display_configs: impl Fn(&str) -> Result<()>,
+    ) -> Result<Vec<ProfileId>> {
+        #[derive(Deserialize)]
+        struct ImportedProfiles {
+            profile: Option<ProfilesConfigs>,
+        }
+
+        let imported: ImportedProfiles = toml::from_str(serialized)?;
+        let Some(profiles) = imported.profile.filter(|profiles| profiles.len() > 0) else {
+            bail!(localized!(
+                pt_br: "Nenhum perfil encontrado para importar",
+                en: "No profile found to import",
+            ));
+        };
+
+        let mut labels = std::collections::HashSet::new();
+        for (id, profile_configs) in profiles.iter() {
+            let label = &profile_configs.label;
+            if label.is_empty() {
+                bail!(localized!(
+                    pt_br: "O nome do perfil {id} não pode ser vazio",
+                    en: "The name of the profile {id} can't be empty",
+                ));
+            }
+            if !labels.insert(label) {
+                bail!(localized!(
+                    pt_br: "O nome {label:?} é usado em mais de um perfil",
+                    en: "The name {label:?} is used by more than one profile",
+                ));
+            }
+            validate_display_configs(&profile_configs.display_configs).with_context(|| {
+                localized!(
+                    pt_br: "Configuração de tela inválida no perfil {id}",
+                    en: "Invalid display configuration in the profile {id}",
+                )
+            })?;
+        }
+
+        self.remove_profiles();
+        for (id, profile_configs) in profiles.iter() {
+            self.set_profile_configs_strs(
+                id,
+                &profile_configs.label,
+                &profile_configs.display_configs,
+            )?;
+        }
+
+        Ok(profiles.keys().cloned().collect())
+    }
+
+    pub(crate) fn has_profile_configs(&self, id: &ProfileId) -> bool {
         self.content
             .profile_configs_table(id)
             .is_some_and(|profile_configs| {
@@ -235,7 +541,7 @@ impl ConfigsWriter {
     }
 
     pub(crate) fn save(&self) -> Result<()> {
-        fs::write(Configs::path(), self.serialized()?)?;
+        atomic_write::write(&Configs::path(), self.serialized()?)?;
         Ok(())
     }
 
@@ -245,58 +551,230 @@ impl ConfigsWriter {
     }
 }
 
+/// Edits the predefined scripts in place, keeping the other content of the configs file, its
+/// comments and its formatting.
+pub(crate) struct ScriptsWriter {
+    document: toml_edit::DocumentMut,
+}
+impl ScriptsWriter {
+    /// The configs file must already be in the current layout version.
+    pub(crate) fn load() -> Result<ScriptsWriter> {
+        let serialized = Configs::read_file()?;
+        let version = migrations::version(&toml::from_str(&serialized)?)?;
+        if version != CURRENT_VERSION {
+            return Err(outdated_version_error(version));
+        }
+        Ok(ScriptsWriter {
+            document: serialized.parse()?,
+        })
+    }
+
+    /// Only the keys of the changed scripts are touched.
+    pub(crate) fn edit(&mut self, os: &OperatingSystem, edit: ScriptEdit) -> Result<()> {
+        let scripts = self.scripts_mut(os)?;
+        let index_of = |number: NonZeroUsize, scripts: &toml_edit::ArrayOfTables| {
+            let max = scripts.len();
+            if number.get() <= max {
+                Ok(number.get() - 1)
+            } else {
+                Err(anyhow!(localized!(
+                    pt_br: "Número inválido de script para o {os} (mín: 1; máx: {max})",
+                    en: "Invalid script number for {os} (min: 1; max: {max})",
+                )))
+            }
+        };
+
+        match edit {
+            ScriptEdit::Add(predefined_script) => {
+                let mut script_table = toml_edit::Table::new();
+                for (key, value) in toml::Table::try_from(predefined_script)? {
+                    set_value(&mut script_table, &key, &value)?;
+                }
+                scripts.push(script_table);
+            }
+            ScriptEdit::Remove(number) => {
+                scripts.remove(index_of(number, scripts)?);
+            }
+            ScriptEdit::Move { from, to } => {
+                let from = index_of(from, scripts)?;
+                let to = index_of(to, scripts)?;
+                // The tables are written in the order of their positions in the file, so each one
+                // takes the position of the one that was at its new index.
+                let positions: Vec<_> = scripts.iter().map(toml_edit::Table::position).collect();
+                let mut script_tables: Vec<_> = std::mem::take(scripts).into_iter().collect();
+                let script_table = script_tables.remove(from);
+                script_tables.insert(to, script_table);
+                for (script_table, position) in script_tables.iter_mut().zip(positions) {
+                    if let Some(position) = position {
+                        script_table.set_position(position);
+                    }
+                }
+                *scripts = script_tables.into_iter().collect();
+            }
+            ScriptEdit::Edit {
+                number,
+                label_template,
+                script,
+            } => {
+                let index = index_of(number, scripts)?;
+                let script_table = scripts.get_mut(index).expect("the index should be valid");
+                // An operating system or an action without its modifier resets it.
+                if script.next_boot_operating_system.is_some() {
+                    script_table.remove(BOOT_ONCE_KEY);
+                }
+                if script.reboot_action.is_some() {
+                    script_table.remove(SCHEDULE_KEY);
+                }
+                for (key, value) in toml::Table::try_from(script)? {
+                    set_value(script_table, &key, &value)?;
+                }
+                if let Some(label_template) = label_template {
+                    set_value(script_table, LABEL_TEMPLATE_KEY, &label_template.into())?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn save(&self) -> Result<()> {
+        atomic_write::write(&Configs::path(), self.document.to_string())?;
+        Ok(())
+    }
+
+    fn scripts_mut(&mut self, os: &OperatingSystem) -> Result<&mut toml_edit::ArrayOfTables> {
+        let os_table = self
+            .document
+            .entry(OPERATING_SYSTEM_KEY)
+            .or_insert_with(|| {
+                let mut os_tables = toml_edit::Table::new();
+                os_tables.set_implicit(true);
+                os_tables.into()
+            })
+            .as_table_mut()
+            .and_then(|os_tables| {
+                os_tables
+                    .entry(&os.serialize_to_string())
+                    .or_insert_with(toml_edit::table)
+                    .as_table_mut()
+            });
+        let Some(os_table) = os_table else {
+            bail!(localized!(
+                pt_br: "As configurações do {os} não são uma tabela",
+                en: "The configs of {os} aren't a table",
+            ));
+        };
+
+        let scripts = os_table
+            .entry(SCRIPTS_KEY)
+            .or_insert_with(|| toml_edit::ArrayOfTables::new().into());
+        // An inline array of inline tables is turned into an array of tables.
+        if scripts.is_array() {
+            *scripts = match std::mem::take(scripts).into_array_of_tables() {
+                Ok(array_of_tables) => array_of_tables.into(),
+                Err(scripts) => scripts,
+            };
+        }
+        match scripts.as_array_of_tables_mut() {
+            Some(scripts) => Ok(scripts),
+            None => bail!(localized!(
+                pt_br: "Os scripts do {os} não são uma lista",
+                en: "The scripts of {os} aren't a list",
+            )),
+        }
+    }
+}
+
+/// Keeps the spacing and the comments around a replaced value.
+fn set_value(table: &mut toml_edit::Table, key: &str, value: &toml::Value) -> Result<()> {
+    let mut value: toml_edit::Value = value.to_string().parse()?;
+    if let Some(old_value) = table.get(key).and_then(toml_edit::Item::as_value) {
+        *value.decor_mut() = old_value.decor().clone();
+    }
+    table[key] = value.into();
+    Ok(())
+}
+
 struct Content(toml::Table);
 impl Content {
-    fn operating_system_configs_table(&self, os: OperatingSystem) -> Option<&toml::Table> {
+    fn operating_systems(&self) -> Vec<OperatingSystem> {
+        self.0
+            .table_at(OPERATING_SYSTEM_KEY)
+            .into_iter()
+            .flat_map(|os_tables| os_tables.keys())
+            .filter_map(|key| OperatingSystem::try_from(key.clone()).ok())
+            .collect()
+    }
+
+    fn operating_system_configs_table(&self, os: &OperatingSystem) -> Option<&toml::Table> {
         self.0.table_at(OPERATING_SYSTEM_KEY)?.table_at(os)
     }
 
-    fn ensure_operating_system_configs_table(&mut self, os: OperatingSystem) -> &mut toml::Table {
+    fn ensure_operating_system_configs_table(&mut self, os: &OperatingSystem) -> &mut toml::Table {
         self.0
             .ensure_table_at(OPERATING_SYSTEM_KEY)
             .ensure_table_at(os)
     }
 
-    fn profile_configs_table(&self, id: ProfileId) -> Option<&toml::Table> {
+    fn profile_ids(&self) -> Vec<ProfileId> {
+        self.0
+            .table_at(PROFILE_KEY)
+            .into_iter()
+            .flat_map(|profile_tables| profile_tables.keys())
+            .filter_map(|key| ProfileId::try_from(key.clone()).ok())
+            .collect()
+    }
+
+    fn profile_configs_table(&self, id: &ProfileId) -> Option<&toml::Table> {
         self.0.table_at(PROFILE_KEY)?.table_at(id)
     }
 
     #[cfg(any(windows, test))]
-    fn ensure_profile_configs_table(&mut self, id: ProfileId) -> &mut toml::Table {
+    fn ensure_profile_configs_table(&mut self, id: &ProfileId) -> &mut toml::Table {
         self.0.ensure_table_at(PROFILE_KEY).ensure_table_at(id)
     }
 
     fn ensure_default(&mut self) {
         self.set_scripts_if_none(
-            OperatingSystem::Windows,
+            &OperatingSystem::WINDOWS,
             [PredefinedScript {
-                label_template: "{reboot_action} no {next_boot_operating_system}".to_string(),
+                label_template: None,
                 script: Script {
-                    next_boot_operating_system: Some(SetOrUnset::Set(OperatingSystem::Linux)),
+                    next_boot_operating_system: Some(SetOrUnset::Set(OperatingSystem::LINUX)),
                     reboot_action: Some(RebootAction::Reboot),
                     ..Script::new()
                 },
+                confirm: false,
+                hooks: ScriptHooks::default(),
             }],
         );
 
-        self.set_scripts_if_none(OperatingSystem::Linux,
-            ProfileId::values().map(|profile_id| {
-                PredefinedScript {
-                    script: Script {
-                        next_boot_operating_system: Some(SetOrUnset::Set(OperatingSystem::Windows)),
-                        next_windows_boot_profile: Some(SetOrUnset::Set(profile_id)),
-                        switch_to_profile: None,
-                        reboot_action: Some(RebootAction::Reboot),
-                    },
-                    label_template: "{reboot_action} no {next_boot_operating_system} usando o perfil {next_windows_boot_profile}".to_string(),
-                }
-            })
+        let mut profile_ids = self.profile_ids();
+        if profile_ids.is_empty() {
+            profile_ids = vec![ProfileId::A, ProfileId::B];
+        }
+
+        self.set_scripts_if_none(
+            &OperatingSystem::LINUX,
+            profile_ids.into_iter().map(|profile_id| PredefinedScript {
+                script: Script {
+                    next_boot_operating_system: Some(SetOrUnset::Set(OperatingSystem::WINDOWS)),
+                    boot_once: false,
+                    next_windows_boot_profile: Some(SetOrUnset::Set(profile_id)),
+                    switch_to_profile: None,
+                    reboot_action: Some(RebootAction::Reboot),
+                    schedule: None,
+                },
+                label_template: None,
+                confirm: false,
+                hooks: ScriptHooks::default(),
+            }),
         );
     }
 
     fn set_scripts_if_none(
         &mut self,
-        os: OperatingSystem,
+        os: &OperatingSystem,
         scripts: impl IntoIterator<Item = PredefinedScript>,
     ) {
         let has_scripts = self
@@ -321,7 +799,10 @@ impl From<toml::Table> for Content {
 }
 impl Default for Content {
     fn default() -> Self {
-        let mut content = Content(toml::Table::new());
+        let mut table = toml::Table::new();
+        // An empty content is already in the current layout.
+        migrations::migrate(&mut table).unwrap();
+        let mut content = Content(table);
         content.ensure_default();
         content
     }
@@ -347,68 +828,116 @@ impl TableExt for toml::Table {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::options_types::Schedule;
     use crate::script::SwitchToProfile;
 
     #[test]
+    #[expect(clippy::too_many_lines)]
     fn content_written_by_the_writer_can_be_read_by_the_reader() -> Result<()> {
         let expected = Configs {
-            operating_system: OperatingSystemsConfigs {
-                windows: OperatingSystemConfigs {
-                    grub_entry: "windows-grub-entry".to_string(),
-                    scripts: vec![PredefinedScript {
-                        script: Script {
-                            next_boot_operating_system: None,
-                            next_windows_boot_profile: None,
-                            switch_to_profile: None,
-                            reboot_action: None,
-                        },
-                        label_template: "windows-script-label".to_string(),
-                    }],
-                },
-                linux: OperatingSystemConfigs {
-                    grub_entry: "linux-grub-entry".to_string(),
-                    scripts: vec![PredefinedScript {
-                        script: Script {
-                            next_boot_operating_system: Some(SetOrUnset::Set(
-                                OperatingSystem::Linux,
-                            )),
-                            next_windows_boot_profile: Some(SetOrUnset::Unset),
-                            switch_to_profile: Some(SwitchToProfile::Other),
-                            reboot_action: Some(RebootAction::Reboot),
-                        },
-                        label_template: "linux-script-label".to_string(),
-                    }],
-                },
-            },
-            profile: ProfilesConfigs {
-                a: ProfileConfigs {
-                    label: "profile-a-label".to_string(),
-                    display_configs: "profile-a-display-configs".to_string(),
-                },
-                b: ProfileConfigs {
-                    label: "profile-b-label".to_string(),
-                    display_configs: "profile-b-display-configs".to_string(),
-                },
-            },
+            operating_system: OperatingSystemsConfigs(IndexMap::from([
+                (
+                    OperatingSystem::WINDOWS,
+                    OperatingSystemConfigs {
+                        label: None,
+                        grub_entry: "windows-grub-entry".to_string(),
+                        scripts: vec![PredefinedScript {
+                            script: Script {
+                                next_boot_operating_system: None,
+                                boot_once: false,
+                                next_windows_boot_profile: None,
+                                switch_to_profile: None,
+                                reboot_action: None,
+                                schedule: None,
+                            },
+                            label_template: Some("windows-script-label".to_string()),
+                            confirm: false,
+                            hooks: ScriptHooks::default(),
+                        }],
+                    },
+                ),
+                (
+                    OperatingSystem::LINUX,
+                    OperatingSystemConfigs {
+                        label: None,
+                        grub_entry: "linux-grub-entry".to_string(),
+                        scripts: vec![PredefinedScript {
+                            script: Script {
+                                next_boot_operating_system: Some(SetOrUnset::Set(
+                                    OperatingSystem::LINUX,
+                                )),
+                                boot_once: true,
+                                next_windows_boot_profile: Some(SetOrUnset::Unset),
+                                switch_to_profile: Some(SwitchToProfile::Other),
+                                reboot_action: Some(RebootAction::Reboot),
+                                schedule: Some(Schedule::In(10)),
+                            },
+                            label_template: Some("linux-script-label".to_string()),
+                            confirm: true,
+                            hooks: ScriptHooks {
+                                pre_hooks: vec!["pre-hook".to_string()],
+                                post_hooks: vec!["post-hook".to_string()],
+                                hook_timeout: Some(30),
+                            },
+                        }],
+                    },
+                ),
+                (
+                    OperatingSystem::try_from("fedora".to_string()).unwrap(),
+                    OperatingSystemConfigs {
+                        label: Some("Fedora".to_string()),
+                        grub_entry: "fedora-grub-entry".to_string(),
+                        scripts: Vec::new(),
+                    },
+                ),
+            ])),
+            profile: ProfilesConfigs(IndexMap::from([
+                (
+                    ProfileId::A,
+                    ProfileConfigs {
+                        label: "profile-a-label".to_string(),
+                        display_configs: "profile-a-display-configs".to_string(),
+                    },
+                ),
+                (
+                    ProfileId::B,
+                    ProfileConfigs {
+                        label: "profile-b-label".to_string(),
+                        display_configs: "profile-b-display-configs".to_string(),
+                    },
+                ),
+                (
+                    ProfileId::try_from("tv-and-monitor".to_string()).unwrap(),
+                    ProfileConfigs {
+                        label: "profile-tv-and-monitor-label".to_string(),
+                        display_configs: "profile-tv-and-monitor-display-configs".to_string(),
+                    },
+                ),
+            ])),
         };
 
         // Sets the content via the writer.
         let writer = {
             let mut content = Content(toml::Table::new());
-            for os in OperatingSystem::values() {
-                content.set_scripts_if_none(os, expected.operating_system[os].scripts.clone());
+            for (os, os_configs) in expected.operating_system.iter() {
+                if let Some(label) = &os_configs.label {
+                    content
+                        .ensure_operating_system_configs_table(os)
+                        .insert(LABEL_KEY.to_string(), label.as_str().into());
+                }
+                content.set_scripts_if_none(os, os_configs.scripts.clone());
             }
 
             let mut writer = ConfigsWriter { content };
 
-            for os in OperatingSystem::values() {
-                writer.set_grub_entry(os, &expected.operating_system[os].grub_entry);
+            for (os, os_configs) in expected.operating_system.iter() {
+                writer.set_grub_entry(os, &os_configs.grub_entry);
             }
-            for profile_id in ProfileId::values() {
+            for (profile_id, profile_configs) in expected.profile.iter() {
                 writer.set_profile_configs_strs(
                     profile_id,
-                    &expected.profile[profile_id].label,
-                    &expected.profile[profile_id].display_configs,
+                    &profile_configs.label,
+                    &profile_configs.display_configs,
                 )?;
             }
 
@@ -427,16 +956,359 @@ mod tests {
         let mut writer = ConfigsWriter {
             content: Content(toml::Table::new()),
         };
-        assert!(!writer.has_grub_entry(OperatingSystem::Windows));
-        assert!(!writer.has_grub_entry(OperatingSystem::Linux));
+        assert!(!writer.has_grub_entry(&OperatingSystem::WINDOWS));
+        assert!(!writer.has_grub_entry(&OperatingSystem::LINUX));
+
+        writer.set_grub_entry(&OperatingSystem::WINDOWS, "windows-grub-entry");
+        assert!(writer.has_grub_entry(&OperatingSystem::WINDOWS));
+        assert!(!writer.has_grub_entry(&OperatingSystem::LINUX));
+
+        writer.set_grub_entry(&OperatingSystem::LINUX, "linux-grub-entry");
+        assert!(writer.has_grub_entry(&OperatingSystem::WINDOWS));
+        assert!(writer.has_grub_entry(&OperatingSystem::LINUX));
+        assert_eq!(
+            writer.operating_systems(),
+            [OperatingSystem::WINDOWS, OperatingSystem::LINUX]
+        );
+    }
 
-        writer.set_grub_entry(OperatingSystem::Windows, "windows-grub-entry");
-        assert!(writer.has_grub_entry(OperatingSystem::Windows));
-        assert!(!writer.has_grub_entry(OperatingSystem::Linux));
+    #[test]
+    fn scripts_writer_edit() -> Result<()> {
+        let mut writer = ScriptsWriter {
+            document: r#"
+                [operating_system.linux]
+                grub_entry = "linux-grub-entry"
+
+                [[operating_system.linux.scripts]]
+                label_template = "first"
+                next_boot_operating_system = "windows"
+                boot_once = true
+                reboot_action = "reboot"
+                schedule = "+10m"
+                pre_hooks = ["sync"]
+
+                [[operating_system.linux.scripts]]
+                label_template = "second"
+                reboot_action = "shutdown"
+                "#
+            .parse()?,
+        };
+        let linux = OperatingSystem::LINUX;
+        let number = |n| NonZeroUsize::new(n).unwrap();
+        let labels = |writer: &ScriptsWriter| -> Vec<String> {
+            let content: toml::Table = toml::from_str(&writer.document.to_string()).unwrap();
+            content[OPERATING_SYSTEM_KEY][linux.serialize_to_string()][SCRIPTS_KEY]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|script| script[LABEL_TEMPLATE_KEY].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        writer.edit(
+            &linux,
+            ScriptEdit::Add(PredefinedScript {
+                script: Script {
+                    reboot_action: Some(RebootAction::Suspend),
+                    ..Script::new()
+                },
+                label_template: Some("third".to_string()),
+                confirm: false,
+                hooks: ScriptHooks::default(),
+            }),
+        )?;
+        assert_eq!(labels(&writer), ["first", "second", "third"]);
+
+        writer.edit(
+            &linux,
+            ScriptEdit::Move {
+                from: number(3),
+                to: number(1),
+            },
+        )?;
+        assert_eq!(labels(&writer), ["third", "first", "second"]);
 
-        writer.set_grub_entry(OperatingSystem::Linux, "linux-grub-entry");
-        assert!(writer.has_grub_entry(OperatingSystem::Windows));
-        assert!(writer.has_grub_entry(OperatingSystem::Linux));
+        writer.edit(&linux, ScriptEdit::Remove(number(3)))?;
+        assert_eq!(labels(&writer), ["third", "first"]);
+        assert!(writer.edit(&linux, ScriptEdit::Remove(number(3))).is_err());
+
+        writer.edit(
+            &linux,
+            ScriptEdit::Edit {
+                number: number(2),
+                label_template: Some("edited".to_string()),
+                script: Script {
+                    reboot_action: Some(RebootAction::Shutdown),
+                    ..Script::new()
+                },
+            },
+        )?;
+        let configs = Configs::from_serialized(&format!(
+            "{}\n[operating_system.windows]\ngrub_entry = \"windows-grub-entry\"\n",
+            writer.document
+        ))?;
+        let edited = &configs.operating_system[&linux].scripts[1];
+        assert_eq!(edited.label_template.as_deref(), Some("edited"));
+        assert_eq!(edited.script.reboot_action, Some(RebootAction::Shutdown));
+        // The schedule belonged to the replaced action, but the other options and keys are kept.
+        assert_eq!(edited.script.schedule, None);
+        assert!(edited.script.boot_once);
+        assert_eq!(edited.hooks.pre_hooks, ["sync"]);
+        Ok(())
+    }
+
+    #[test]
+    fn scripts_writer_keeps_the_rest_of_the_file() -> Result<()> {
+        let original = r#"# My configs.
+version = 2
+
+[operating_system.linux]
+grub_entry   =   "linux-grub-entry" # The one of Debian.
+
+# Into Windows.
+[[operating_system.linux.scripts]]
+label_template = "first"
+next_boot_operating_system = "windows"
+reboot_action = "reboot"
+
+[profile.tv]
+label = "TV"
+display_configs = "tv-display-configs"
+"#;
+        let mut writer = ScriptsWriter {
+            document: original.parse()?,
+        };
+
+        writer.edit(
+            &OperatingSystem::LINUX,
+            ScriptEdit::Add(PredefinedScript {
+                script: Script {
+                    reboot_action: Some(RebootAction::Shutdown),
+                    ..Script::new()
+                },
+                label_template: Some("second".to_string()),
+                confirm: false,
+                hooks: ScriptHooks::default(),
+            }),
+        )?;
+
+        let (before_profiles, profiles) = original.split_at(original.find("[profile.tv]").unwrap());
+        assert_eq!(
+            writer.document.to_string(),
+            format!(
+                "{before_profiles}[[operating_system.linux.scripts]]\nreboot_action = \"shutdown\"\nlabel_template = \"second\"\n\n{profiles}"
+            )
+        );
+        // No default scripts are added for the other operating system.
+        assert!(
+            writer.document[OPERATING_SYSTEM_KEY]
+                .get(OperatingSystem::WINDOWS.serialize_to_string())
+                .is_none()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn predefined_scripts_with_reboot_actions_need_confirmation() -> Result<()> {
+        let serialized = r#"
+            [[scripts]]
+            label_template = "confirmed reboot"
+            reboot_action = "reboot"
+            confirm = true
+
+            [[scripts]]
+            label_template = "unconfirmed reboot"
+            reboot_action = "reboot"
+
+            [[scripts]]
+            label_template = "no reboot"
+            next_boot_operating_system = "windows"
+            confirm = true
+        "#;
+
+        let scripts: toml::Table = toml::from_str(serialized)?;
+        let scripts: Vec<PredefinedScript> = scripts["scripts"].clone().try_into()?;
+
+        assert_eq!(
+            scripts
+                .iter()
+                .map(PredefinedScript::needs_confirmation)
+                .collect::<Vec<_>>(),
+            [true, false, false]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn reader_keeps_operating_systems_order() -> Result<()> {
+        let serialized = r#"
+            [operating_system.windows]
+            grub_entry = "windows-grub-entry"
+
+            [operating_system.rescue]
+            label = "Rescue"
+            grub_entry = "rescue-grub-entry"
+
+            [operating_system.linux]
+            grub_entry = "linux-grub-entry"
+
+            [profile.a]
+            label = "profile-a-label"
+            display_configs = "profile-a-display-configs"
+
+            [profile.b]
+            label = "profile-b-label"
+            display_configs = "profile-b-display-configs"
+        "#;
+
+        let configs = Configs::from_serialized(serialized)?;
+
+        let rescue = OperatingSystem::try_from("rescue".to_string()).unwrap();
+        assert_eq!(
+            configs.operating_system.keys().collect::<Vec<_>>(),
+            [&OperatingSystem::WINDOWS, &rescue, &OperatingSystem::LINUX]
+        );
+        assert_eq!(
+            configs.operating_system_by_grub_entry("rescue-grub-entry"),
+            Some(rescue.clone())
+        );
+        assert_eq!(
+            configs.operating_system_by_grub_entry("hand-picked-grub-entry"),
+            None
+        );
+        assert_eq!(
+            configs.labeled_operating_system(&rescue)?.to_string(),
+            "Rescue"
+        );
+        assert_eq!(
+            configs
+                .labeled_operating_system(&OperatingSystem::LINUX)?
+                .to_string(),
+            "Linux"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn profile_after_cycles_through_profiles() -> Result<()> {
+        let serialized = r#"
+            [operating_system.windows]
+            grub_entry = "windows-grub-entry"
+
+            [operating_system.linux]
+            grub_entry = "linux-grub-entry"
+
+            [profile.tv]
+            label = "TV"
+            display_configs = "tv-display-configs"
+
+            [profile.monitor]
+            label = "Monitor"
+            display_configs = "monitor-display-configs"
+
+            [profile.both]
+            label = "TV + Monitor"
+            display_configs = "both-display-configs"
+        "#;
+
+        let configs = Configs::from_serialized(serialized)?;
+
+        let id = |id: &str| ProfileId::try_from(id.to_string()).unwrap();
+        assert_eq!(configs.profile_after(&id("tv")), Some(&id("monitor")));
+        assert_eq!(configs.profile_after(&id("monitor")), Some(&id("both")));
+        assert_eq!(configs.profile_after(&id("both")), Some(&id("tv")));
+        assert_eq!(configs.profile_after(&id("unknown")), None);
+        assert_eq!(
+            configs.labeled_profile(&id("both"))?.to_string(),
+            "\"TV + Monitor\" (both)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn reader_requires_builtin_operating_systems() {
+        let serialized = r#"
+            [operating_system.windows]
+            grub_entry = "windows-grub-entry"
+
+            [profile.a]
+            label = "profile-a-label"
+            display_configs = "profile-a-display-configs"
+
+            [profile.b]
+            label = "profile-b-label"
+            display_configs = "profile-b-display-configs"
+        "#;
+
+        assert!(Configs::from_serialized(serialized).is_err());
+    }
+
+    /// A configs file as written by the version 1, configured on both Windows and Linux.
+    const VERSION_1_CONFIGS: &str = r#"
+[operating_system.linux]
+grub_entry = "Ubuntu"
+
+[[operating_system.linux.scripts]]
+label_template = "{reboot_action} no {next_boot_operating_system} usando o perfil {next_windows_boot_profile}"
+next_boot_operating_system = "windows"
+next_windows_boot_profile = "a"
+reboot_action = "reboot"
+
+[[operating_system.linux.scripts]]
+label_template = "{reboot_action} no {next_boot_operating_system} usando o perfil {next_windows_boot_profile}"
+next_boot_operating_system = "windows"
+next_windows_boot_profile = "b"
+reboot_action = "reboot"
+
+[operating_system.windows]
+grub_entry = "Windows Boot Manager (on /dev/nvme0n1p1)"
+
+[[operating_system.windows.scripts]]
+label_template = "{reboot_action} no {next_boot_operating_system}"
+next_boot_operating_system = "linux"
+reboot_action = "reboot"
+
+[profile.a]
+display_configs = '{"a":1}'
+label = "Monitor"
+
+[profile.b]
+display_configs = '{"b":2}'
+label = "TV"
+"#;
+
+    #[test]
+    fn reader_migrates_version_1() -> Result<()> {
+        let configs = Configs::from_serialized(VERSION_1_CONFIGS)?;
+
+        assert_eq!(
+            configs.operating_system[&OperatingSystem::LINUX].grub_entry,
+            "Ubuntu"
+        );
+        assert_eq!(
+            configs.operating_system[&OperatingSystem::LINUX].scripts[1]
+                .script
+                .next_windows_boot_profile,
+            Some(SetOrUnset::Set(ProfileId::B))
+        );
+        assert_eq!(
+            configs.profile.keys().collect::<Vec<_>>(),
+            [&ProfileId::A, &ProfileId::B]
+        );
+        assert_eq!(configs.profile[&ProfileId::B].label, "TV");
+        Ok(())
+    }
+
+    #[test]
+    fn version_1_round_trips_through_the_writer() -> Result<()> {
+        let configs = Configs::from_serialized(VERSION_1_CONFIGS)?;
+
+        let serialized = ConfigsWriter::from_serialized(VERSION_1_CONFIGS)?.serialized()?;
+
+        let content: toml::Table = toml::from_str(&serialized)?;
+        assert_eq!(migrations::version(&content)?, CURRENT_VERSION);
+        assert_eq!(Configs::from_serialized(&serialized)?, configs);
+        Ok(())
     }
 
     #[test]
@@ -444,25 +1316,182 @@ mod tests {
         let mut writer = ConfigsWriter {
             content: Content(toml::Table::new()),
         };
-        assert!(!writer.has_profile_configs(ProfileId::A));
-        assert!(!writer.has_profile_configs(ProfileId::B));
+        assert!(!writer.has_profile_configs(&ProfileId::A));
+        assert!(!writer.has_profile_configs(&ProfileId::B));
 
         writer.set_profile_configs_strs(
-            ProfileId::A,
+            &ProfileId::A,
             "profile-a-label",
             "profile-a-display-configs",
         )?;
-        assert!(writer.has_profile_configs(ProfileId::A));
-        assert!(!writer.has_profile_configs(ProfileId::B));
+        assert!(writer.has_profile_configs(&ProfileId::A));
+        assert!(!writer.has_profile_configs(&ProfileId::B));
 
         writer.set_profile_configs_strs(
-            ProfileId::B,
+            &ProfileId::B,
             "profile-b-label",
             "profile-b-display-configs",
         )?;
-        assert!(writer.has_profile_configs(ProfileId::A));
-        assert!(writer.has_profile_configs(ProfileId::B));
+        assert!(writer.has_profile_configs(&ProfileId::A));
+        assert!(writer.has_profile_configs(&ProfileId::B));
+        assert_eq!(writer.profile_ids(), [ProfileId::A, ProfileId::B]);
+
+        writer.remove_profiles();
+        assert!(!writer.has_profile_configs(&ProfileId::A));
+        assert!(!writer.has_profile_configs(&ProfileId::B));
+        assert!(writer.profile_ids().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn writer_import_profiles() -> Result<()> {
+        let mut writer = ConfigsWriter {
+            content: Content(toml::Table::new()),
+        };
+        writer.set_profile_configs_strs(&ProfileId::A, "old-label", "old-display-configs")?;
+
+        let ids = writer.import_profiles(
+            r#"
+            [profile.tv]
+            label = "TV"
+            display_configs = "tv-display-configs"
+
+            [profile.monitor]
+            label = "Monitor"
+            display_configs = "monitor-display-configs"
+            "#,
+            |_| Ok(()),
+        )?;
+
+        let tv = ProfileId::try_from("tv".to_string()).unwrap();
+        let monitor = ProfileId::try_from("monitor".to_string()).unwrap();
+        assert_eq!(ids, [tv.clone(), monitor.clone()]);
+        assert_eq!(writer.profile_ids(), [tv.clone(), monitor]);
+        assert!(writer.has_profile_configs(&tv));
+        assert!(!writer.has_profile_configs(&ProfileId::A));
+        Ok(())
+    }
 
+    #[test]
+    fn writer_import_invalid_profiles() -> Result<()> {
+        let mut writer = ConfigsWriter {
+            content: Content(toml::Table::new()),
+        };
+        writer.set_profile_configs_strs(&ProfileId::A, "label", "display-configs")?;
+
+        let duplicated_label = r#"
+            [profile.tv]
+            label = "Same"
+            display_configs = "tv-display-configs"
+
+            [profile.monitor]
+            label = "Same"
+            display_configs = "monitor-display-configs"
+        "#;
+        let single = r#"
+            [profile.tv]
+            label = "TV"
+            display_configs = "tv-display-configs"
+        "#;
+
+        assert!(writer.import_profiles("", |_| Ok(())).is_err());
+        assert!(
+            writer
+                .import_profiles(duplicated_label, |_| Ok(()))
+                .is_err()
+        );
+        assert!(
+            writer
+                .import_profiles(single, |_| bail!("invalid display configs"))
+                .is_err()
+        );
+
+        // Nothing is changed when the import fails.
+        assert_eq!(writer.profile_ids(), [ProfileId::A]);
+        Ok(())
+    }
+
+    #[test]
+    fn default_linux_scripts_use_configured_profiles() -> Result<()> {
+        let tv = ProfileId::try_from("tv".to_string()).unwrap();
+        let mut content = Content(toml::Table::new());
+        content
+            .ensure_profile_configs_table(&tv)
+            .insert(LABEL_KEY.to_string(), "TV".into());
+
+        content.ensure_default();
+
+        let os_table = content
+            .operating_system_configs_table(&OperatingSystem::LINUX)
+            .expect("should not be None");
+        let scripts: Vec<PredefinedScript> = os_table[SCRIPTS_KEY].clone().try_into()?;
+        assert_eq!(scripts.len(), 1);
+        assert_eq!(
+            scripts[0].script.next_windows_boot_profile,
+            Some(SetOrUnset::Set(tv))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn switch_to_profile_label() -> Result<()> {
+        let configs = Configs::from_serialized(VERSION_1_CONFIGS)?;
+        let predef_script = |switch_to_profile| PredefinedScript {
+            script: Script {
+                switch_to_profile: Some(switch_to_profile),
+                ..Script::new()
+            },
+            label_template: Some("trocar para o perfil {switch_to_profile}".to_string()),
+            confirm: false,
+            hooks: ScriptHooks::default(),
+        };
+
+        assert_eq!(
+            predef_script(SwitchToProfile::Profile(ProfileId::B)).resolve_label(&configs),
+            "Trocar para o perfil \"TV\" (b)"
+        );
+        assert_eq!(
+            predef_script(SwitchToProfile::Saved).resolve_label(&configs),
+            "Trocar para o perfil salvo"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn default_scripts_labels_follow_locale() -> Result<()> {
+        text::set_locale(text::Locale::En);
+        let mut content = Content(toml::Table::new());
+        content.ensure_default();
+        // The file is shared, so the labels are only localized when shown.
+        assert!(!toml::to_string(&content.0)?.contains(LABEL_TEMPLATE_KEY));
+        content
+            .ensure_operating_system_configs_table(&OperatingSystem::WINDOWS)
+            .insert(GRUB_ENTRY_KEY.to_string(), "windows-grub-entry".into());
+        content
+            .ensure_operating_system_configs_table(&OperatingSystem::LINUX)
+            .insert(GRUB_ENTRY_KEY.to_string(), "linux-grub-entry".into());
+        content
+            .ensure_profile_configs_table(&ProfileId::A)
+            .extend(toml::Table::try_from(ProfileConfigs {
+                label: "TV".to_string(),
+                display_configs: "{}".to_string(),
+            })?);
+
+        let configs = Configs::from_serialized(&toml::to_string(&content.0)?)?;
+
+        let labels: Vec<_> = configs.operating_system[&OperatingSystem::LINUX]
+            .scripts
+            .iter()
+            .map(|predef_script| predef_script.resolve_label(&configs))
+            .collect();
+        assert_eq!(
+            labels,
+            [
+                "Reboot into Windows using the profile \"TV\" (a)",
+                "Reboot into Windows using the profile b"
+            ]
+        );
         Ok(())
     }
 
@@ -472,7 +1501,9 @@ mod tests {
         fn predef_script_with_label(label: &str) -> PredefinedScript {
             PredefinedScript {
                 script: Script::new(),
-                label_template: label.to_string(),
+                label_template: Some(label.to_string()),
+                confirm: false,
+                hooks: ScriptHooks::default(),
             }
         }
 
@@ -490,14 +1521,14 @@ mod tests {
         fn no_os_table() -> Result<()> {
             let mut content = Content(toml::Table::new());
             assert_eq!(
-                content.operating_system_configs_table(OperatingSystem::Linux),
+                content.operating_system_configs_table(&OperatingSystem::LINUX),
                 None
             );
             let ps = predef_script_with_label("new");
 
-            content.set_scripts_if_none(OperatingSystem::Linux, [ps.clone()]);
+            content.set_scripts_if_none(&OperatingSystem::LINUX, [ps.clone()]);
 
-            let scripts = get_scripts!(content, OperatingSystem::Linux);
+            let scripts = get_scripts!(content, &OperatingSystem::LINUX);
             assert_eq!(scripts.len(), 1);
             assert_eq!(scripts[0].clone().try_into::<PredefinedScript>()?, ps);
             Ok(())
@@ -506,16 +1537,16 @@ mod tests {
         #[test]
         fn os_table_exists() -> Result<()> {
             let mut content = Content(toml::Table::new());
-            content.ensure_operating_system_configs_table(OperatingSystem::Linux);
+            content.ensure_operating_system_configs_table(&OperatingSystem::LINUX);
             assert_ne!(
-                content.operating_system_configs_table(OperatingSystem::Linux),
+                content.operating_system_configs_table(&OperatingSystem::LINUX),
                 None
             );
             let ps = predef_script_with_label("new");
 
-            content.set_scripts_if_none(OperatingSystem::Linux, [ps.clone()]);
+            content.set_scripts_if_none(&OperatingSystem::LINUX, [ps.clone()]);
 
-            let scripts = get_scripts!(content, OperatingSystem::Linux);
+            let scripts = get_scripts!(content, &OperatingSystem::LINUX);
             assert_eq!(scripts.len(), 1);
             assert_eq!(scripts[0].clone().try_into::<PredefinedScript>()?, ps);
             Ok(())
@@ -525,15 +1556,15 @@ mod tests {
         fn scripts_exists() -> Result<()> {
             let mut content = Content(toml::Table::new());
             let ps = predef_script_with_label("new");
-            content.set_scripts_if_none(OperatingSystem::Linux, [ps.clone()]);
-            let scripts = get_scripts!(content, OperatingSystem::Linux);
+            content.set_scripts_if_none(&OperatingSystem::LINUX, [ps.clone()]);
+            let scripts = get_scripts!(content, &OperatingSystem::LINUX);
             assert_eq!(scripts.len(), 1);
             assert_eq!(scripts[0].clone().try_into::<PredefinedScript>()?, ps);
             let ps_other = predef_script_with_label("other");
 
-            content.set_scripts_if_none(OperatingSystem::Linux, [ps_other.clone()]);
+            content.set_scripts_if_none(&OperatingSystem::LINUX, [ps_other.clone()]);
 
-            let scripts = get_scripts!(content, OperatingSystem::Linux);
+            let scripts = get_scripts!(content, &OperatingSystem::LINUX);
             assert_eq!(scripts.len(), 1);
             assert_ne!(scripts[0].clone().try_into::<PredefinedScript>()?, ps_other);
             assert_eq!(scripts[0].clone().try_into::<PredefinedScript>()?, ps);
@@ -0,0 +1,113 @@
+use anyhow::{Result, bail};
+
+use super::{PROFILE_KEY, TableExt as _};
+use crate::text::localized;
+
+/// The key of the layout version of the configs file. Files without it have the version 1.
+pub(super) const VERSION_KEY: &str = "version";
+pub(crate) const CURRENT_VERSION: i64 = 2;
+
+/// The migration at index `i` upgrades the content from the version `i + 1` to `i + 2`, so there
+/// is one less than [`CURRENT_VERSION`].
+const MIGRATIONS: [fn(&mut toml::Table); 1] = [open_profiles];
+
+/// The layout version of the content.
+pub(crate) fn version(content: &toml::Table) -> Result<i64> {
+    match content.get(VERSION_KEY) {
+        None => Ok(1),
+        Some(&toml::Value::Integer(version)) if (1..=CURRENT_VERSION).contains(&version) => {
+            Ok(version)
+        }
+        Some(&toml::Value::Integer(version)) if version > CURRENT_VERSION => bail!(localized!(
+            pt_br: "O arquivo de configurações tem a versão {version}, de uma versão mais nova do my-reboot (suportada: {CURRENT_VERSION})",
+            en: "The configs file has the version {version}, from a newer version of my-reboot (supported: {CURRENT_VERSION})",
+        )),
+        Some(version) => bail!(localized!(
+            pt_br: "Versão inválida do arquivo de configurações: {version}",
+            en: "Invalid version of the configs file: {version}",
+        )),
+    }
+}
+
+/// Upgrades the content to the current layout version, returning the version it had.
+pub(crate) fn migrate(content: &mut toml::Table) -> Result<i64> {
+    let from_version = version(content)?;
+    for migration in &MIGRATIONS[usize::try_from(from_version - 1)?..] {
+        migration(content);
+    }
+    content.insert(VERSION_KEY.to_string(), CURRENT_VERSION.into());
+    Ok(from_version)
+}
+
+/// The version 1 had the fixed profiles `a` and `b`, both required, so a file configured only on
+/// Linux, which has no `[profile]` table, couldn't be read. Now `[profile]` maps any ids, in file
+/// order, and may be empty, but it's still required, so it's added when missing.
+fn open_profiles(content: &mut toml::Table) {
+    content.ensure_table_at(PROFILE_KEY);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrate_from_version_1() -> Result<()> {
+        let mut content: toml::Table = toml::from_str(
+            r#"
+            [operating_system.windows]
+            grub_entry = "windows-grub-entry"
+
+            [profile.a]
+            display_configs = "profile-a-display-configs"
+            label = "Monitor"
+            "#,
+        )?;
+        let mut expected = content.clone();
+        expected.insert(VERSION_KEY.to_string(), CURRENT_VERSION.into());
+
+        assert_eq!(migrate(&mut content)?, 1);
+
+        assert_eq!(content, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn migrate_from_version_1_without_profiles() -> Result<()> {
+        let mut content: toml::Table = toml::from_str(
+            r#"
+            [operating_system.linux]
+            grub_entry = "linux-grub-entry"
+            "#,
+        )?;
+
+        migrate(&mut content)?;
+
+        assert_eq!(version(&content)?, CURRENT_VERSION);
+        assert!(content.table_at(PROFILE_KEY).unwrap().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn migrate_current_version() -> Result<()> {
+        let serialized = format!("version = {CURRENT_VERSION}\n");
+        let mut content: toml::Table = toml::from_str(&serialized)?;
+
+        assert_eq!(migrate(&mut content)?, CURRENT_VERSION);
+
+        // No migration runs, as the content is already in the current layout.
+        assert!(content.table_at(PROFILE_KEY).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_versions() {
+        for serialized in [
+            format!("version = {}", CURRENT_VERSION + 1),
+            "version = 0".to_string(),
+            "version = \"2\"".to_string(),
+        ] {
+            let content: toml::Table = toml::from_str(&serialized).unwrap();
+            assert!(version(&content).is_err(), "{serialized}");
+        }
+    }
+}
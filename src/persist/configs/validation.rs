@@ -0,0 +1,394 @@
+use std::fmt::Display;
+use std::ops::Range;
+use std::path::Path;
+
+use indexmap::IndexMap;
+use toml::de::{DeTable, DeValue};
+
+use super::{
+    CONFIGS_FILENAME, CURRENT_VERSION, Configs, GRUB_ENTRY_KEY, LABEL_KEY, LABEL_PLACEHOLDERS,
+    LABEL_TEMPLATE_KEY, OPERATING_SYSTEM_KEY, PROFILE_KEY, SCRIPTS_KEY, migrations,
+};
+use crate::options_types::OperatingSystem;
+use crate::text::localized;
+
+const SWITCH_TO_PROFILE_KEY: &str = "switch_to_profile";
+
+/// A problem found in the configs file.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Diagnostic {
+    /// The file with the problem, which is the configs file unless it's the Grub configuration
+    /// file.
+    pub(crate) file: String,
+    /// The 1-based line where the problem is, if it can be told.
+    pub(crate) line: Option<usize>,
+    /// The TOML path of the value with the problem, like `operating_system.linux.grub_entry`.
+    pub(crate) path: String,
+    pub(crate) message: String,
+}
+impl Diagnostic {
+    /// The Grub configuration file can't be read, so the Grub entries aren't checked.
+    pub(crate) fn unreadable_grub_cfg(grub_cfg: &Path, error: &anyhow::Error) -> Self {
+        let cause = error.root_cause();
+        Diagnostic {
+            file: grub_cfg.display().to_string(),
+            line: None,
+            path: String::new(),
+            message: localized!(
+                pt_br: "{cause}. As entradas do Grub não foram verificadas",
+                en: "{cause}. The Grub entries weren't checked",
+            ),
+        }
+    }
+}
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.file)?;
+        if let Some(line) = self.line {
+            write!(f, ":{line}")?;
+        }
+        if !self.path.is_empty() {
+            write!(f, ": {}", self.path)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Checks the serialized configs, beyond what's needed for them to be read.
+///
+/// The Grub entries are only checked if `grub_entries` is given.
+pub(crate) fn validate(serialized: &str, grub_entries: Option<&[String]>) -> Vec<Diagnostic> {
+    let document = match DeTable::parse(serialized) {
+        Ok(document) => document,
+        Err(e) => {
+            return vec![Diagnostic {
+                file: CONFIGS_FILENAME.to_string(),
+                line: e.span().map(|span| line_of(serialized, &span)),
+                path: String::new(),
+                message: e.message().to_string(),
+            }];
+        }
+    };
+    let validator = Validator {
+        serialized,
+        document: DeValue::Table(document.into_inner()),
+        diagnostics: Vec::new(),
+    };
+    validator.validate(grub_entries)
+}
+
+struct Validator<'a> {
+    serialized: &'a str,
+    document: DeValue<'a>,
+    diagnostics: Vec<Diagnostic>,
+}
+impl Validator<'_> {
+    fn validate(mut self, grub_entries: Option<&[String]>) -> Vec<Diagnostic> {
+        let Some(configs) = self.read_configs() else {
+            return self.diagnostics;
+        };
+
+        for (os, os_configs) in configs.operating_system.iter() {
+            let os_path = [OPERATING_SYSTEM_KEY.into(), os.id().into()];
+
+            if let Some(grub_entries) = grub_entries
+                && !grub_entries.contains(&os_configs.grub_entry)
+            {
+                let grub_entry = &os_configs.grub_entry;
+                self.report(
+                    &[&os_path[..], &[GRUB_ENTRY_KEY.into()]].concat(),
+                    localized!(
+                        pt_br: "A entrada {grub_entry:?} não existe no arquivo de configuração do Grub",
+                        en: "The entry {grub_entry:?} doesn't exist in the Grub configuration file",
+                    ),
+                );
+            }
+
+            for (index, predefined_script) in os_configs.scripts.iter().enumerate() {
+                let script_path = [&os_path[..], &[SCRIPTS_KEY.into(), index.into()]].concat();
+
+                let label_template = predefined_script
+                    .label_template
+                    .as_deref()
+                    .unwrap_or_default();
+                for placeholder in placeholders(label_template) {
+                    if !LABEL_PLACEHOLDERS.contains(&placeholder) {
+                        let known = LABEL_PLACEHOLDERS.map(|p| format!("{{{p}}}")).join(", ");
+                        self.report(
+                            &[&script_path[..], &[LABEL_TEMPLATE_KEY.into()]].concat(),
+                            localized!(
+                                pt_br: "Marcador desconhecido {{{placeholder}}}. Os conhecidos são: {known}",
+                                en: "Unknown placeholder {{{placeholder}}}. The known ones are: {known}",
+                            ),
+                        );
+                    }
+                }
+
+                if *os != OperatingSystem::WINDOWS
+                    && predefined_script.script.switch_to_profile.is_some()
+                {
+                    let windows = OperatingSystem::WINDOWS;
+                    self.report(
+                        &[&script_path[..], &[SWITCH_TO_PROFILE_KEY.into()]].concat(),
+                        localized!(
+                            pt_br: "{SWITCH_TO_PROFILE_KEY} só pode ser usado nos scripts do {windows}",
+                            en: "{SWITCH_TO_PROFILE_KEY} can only be used in the scripts of {windows}",
+                        ),
+                    );
+                }
+            }
+        }
+
+        let mut labels = IndexMap::new();
+        for (id, profile) in configs.profile.iter() {
+            if let Some(other_id) = labels.insert(&profile.label, id) {
+                let label = &profile.label;
+                self.report(
+                    &[PROFILE_KEY.into(), id.to_string().into(), LABEL_KEY.into()],
+                    localized!(
+                        pt_br: "O nome {label:?} já é usado pelo perfil {other_id}",
+                        en: "The label {label:?} is already used by the profile {other_id}",
+                    ),
+                );
+            }
+        }
+
+        self.diagnostics
+    }
+
+    /// Reads the configs, reporting why they can't be read if that's the case.
+    fn read_configs(&mut self) -> Option<Configs> {
+        let mut content: toml::Table = toml::from_str(self.serialized).ok()?;
+        let version = match migrations::migrate(&mut content) {
+            Ok(version) => version,
+            Err(e) => {
+                self.report(&[migrations::VERSION_KEY.into()], e.to_string());
+                return None;
+            }
+        };
+
+        match Configs::from_serialized(self.serialized) {
+            Ok(configs) => Some(configs),
+            Err(e) => {
+                // The deserialization error only has the location of the problem if the content
+                // didn't need to be migrated.
+                let span = if version == CURRENT_VERSION {
+                    toml::from_str::<Configs>(self.serialized)
+                        .err()
+                        .and_then(|e| e.span())
+                } else {
+                    None
+                };
+                let message = match e.downcast_ref::<toml::de::Error>() {
+                    Some(e) => e.message().to_string(),
+                    None => e.to_string(),
+                };
+                self.diagnostics.push(Diagnostic {
+                    file: CONFIGS_FILENAME.to_string(),
+                    line: span.map(|span| line_of(self.serialized, &span)),
+                    path: String::new(),
+                    message,
+                });
+                None
+            }
+        }
+    }
+
+    fn report(&mut self, path: &[PathSegment], message: String) {
+        let line = self
+            .span_of(path)
+            .map(|span| line_of(self.serialized, &span));
+        self.diagnostics.push(Diagnostic {
+            file: CONFIGS_FILENAME.to_string(),
+            line,
+            path: path_to_string(path),
+            message,
+        });
+    }
+
+    fn span_of(&self, path: &[PathSegment]) -> Option<Range<usize>> {
+        let mut value = &self.document;
+        let mut span = None;
+        for segment in path {
+            let spanned = match segment {
+                PathSegment::Key(key) => value.get(key.as_str())?,
+                PathSegment::Index(index) => value.get(*index)?,
+            };
+            span = Some(spanned.span());
+            value = spanned.get_ref();
+        }
+        span
+    }
+}
+
+#[derive(Clone)]
+enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+impl From<&str> for PathSegment {
+    fn from(key: &str) -> Self {
+        PathSegment::Key(key.to_string())
+    }
+}
+impl From<String> for PathSegment {
+    fn from(key: String) -> Self {
+        PathSegment::Key(key)
+    }
+}
+impl From<usize> for PathSegment {
+    fn from(index: usize) -> Self {
+        PathSegment::Index(index)
+    }
+}
+
+/// Like `operating_system.linux.scripts[0].label_template`.
+fn path_to_string(path: &[PathSegment]) -> String {
+    let mut string = String::new();
+    for segment in path {
+        match segment {
+            PathSegment::Key(key) => {
+                if !string.is_empty() {
+                    string.push('.');
+                }
+                string.push_str(key);
+            }
+            PathSegment::Index(index) => {
+                string.push('[');
+                string.push_str(&index.to_string());
+                string.push(']');
+            }
+        }
+    }
+    string
+}
+
+/// The names between braces in a label template.
+fn placeholders(template: &str) -> Vec<&str> {
+    let mut placeholders = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rest = &rest[start + 1..];
+        match rest.find('}') {
+            Some(end) => {
+                placeholders.push(&rest[..end]);
+                rest = &rest[end + 1..];
+            }
+            None => break,
+        }
+    }
+    placeholders
+}
+
+fn line_of(serialized: &str, span: &Range<usize>) -> usize {
+    serialized[..span.start].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID: &str = r#"
+        version = 2
+
+        [operating_system.windows]
+        grub_entry = "windows-grub-entry"
+
+        [[operating_system.windows.scripts]]
+        label_template = "{reboot_action} into {next_boot_operating_system}"
+        next_boot_operating_system = "linux"
+        switch_to_profile = "other"
+        reboot_action = "reboot"
+
+        [operating_system.linux]
+        grub_entry = "linux-grub-entry"
+
+        [[operating_system.linux.scripts]]
+        label_template = "{reboot_action} into {next_boot_operating_system} ({schedule})"
+        next_boot_operating_system = "windows"
+        reboot_action = "reboot"
+        schedule = "+10m"
+
+        [profile.tv]
+        label = "TV"
+        display_configs = "tv-display-configs"
+
+        [profile.monitor]
+        label = "Monitor"
+        display_configs = "monitor-display-configs"
+    "#;
+
+    fn grub_entries() -> Vec<String> {
+        vec![
+            "windows-grub-entry".to_string(),
+            "linux-grub-entry".to_string(),
+        ]
+    }
+
+    #[test]
+    fn valid_configs() {
+        assert_eq!(validate(VALID, Some(&grub_entries())), []);
+    }
+
+    #[test]
+    fn problems_are_located() {
+        let serialized = VALID
+            .replace("\"linux-grub-entry\"", "\"missing-grub-entry\"")
+            .replace("({schedule})", "({scheduel})")
+            .replace(
+                "next_boot_operating_system = \"windows\"",
+                "next_boot_operating_system = \"windows\"\n        switch_to_profile = \"saved\"",
+            )
+            .replace("label = \"Monitor\"", "label = \"TV\"");
+
+        let diagnostics = validate(&serialized, Some(&grub_entries()));
+
+        let located: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.line, diagnostic.path.as_str()))
+            .collect();
+        assert_eq!(
+            located,
+            [
+                (Some(14), "operating_system.linux.grub_entry"),
+                (Some(17), "operating_system.linux.scripts[0].label_template"),
+                (
+                    Some(19),
+                    "operating_system.linux.scripts[0].switch_to_profile"
+                ),
+                (Some(28), "profile.monitor.label"),
+            ]
+        );
+        assert!(diagnostics[1].message.contains("{scheduel}"));
+        assert!(diagnostics[3].message.contains("tv"));
+    }
+
+    #[test]
+    fn grub_entries_are_only_checked_if_given() {
+        let serialized = VALID.replace("\"linux-grub-entry\"", "\"missing-grub-entry\"");
+
+        assert_eq!(validate(&serialized, None), []);
+    }
+
+    #[test]
+    fn unreadable_configs() {
+        let diagnostics = validate("[operating_system.linux\n", None);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, Some(1));
+
+        let serialized = VALID.replace("reboot_action = \"reboot\"", "reboot_action = \"reboots\"");
+        let diagnostics = validate(&serialized, None);
+        assert_eq!(diagnostics.len(), 1);
+        // The fields of the scripts are flattened, so only their table can be located.
+        assert_eq!(diagnostics[0].line, Some(7));
+    }
+
+    #[test]
+    fn placeholders_of_template() {
+        assert_eq!(
+            placeholders("{reboot_action} into {next_boot_operating_system} {unclosed"),
+            ["reboot_action", "next_boot_operating_system"]
+        );
+        assert!(placeholders("no placeholders").is_empty());
+    }
+}
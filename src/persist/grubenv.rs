@@ -1,74 +1,250 @@
-use std::collections::BTreeMap;
-use std::fmt::Write as _;
-use std::path::PathBuf;
+use std::error::Error;
+use std::fmt::{Display, Write as _};
+use std::path::{Path, PathBuf};
 use std::{fs, io, iter};
 
+use indexmap::IndexMap;
+
 use crate::host_os::state_path;
+use crate::persist::atomic_write;
+use crate::text::localized;
 
-const GRUBENV_CONTENT_LENGTH: usize = 1024;
-const GRUBENV_HEADER_LINE: &str = "# GRUB Environment Block\n";
+pub(crate) const GRUBENV_FILENAME: &str = "grubenv";
+pub(crate) const GRUBENV_CONTENT_LENGTH: usize = 1024;
+pub(crate) const GRUBENV_HEADER_LINE: &str = "# GRUB Environment Block\n";
 
+/// The GRUB environment block, as read and written by GRUB's `load_env`/`save_env` and
+/// `grub-editenv`.
+///
+/// Entries that are not changed are written back exactly as they were read, in the same order.
 pub struct Grubenv {
-    content: BTreeMap<String, String>,
+    content: IndexMap<String, Value>,
+    size: usize,
+}
+
+struct Value {
+    value: String,
+    /// The value as it is in the file, with GRUB's escaping.
+    raw: String,
+}
+impl Value {
+    fn from_raw(raw: &str) -> Self {
+        let mut value = String::with_capacity(raw.len());
+        let mut chars = raw.chars();
+        while let Some(c) = chars.next() {
+            if c == '\\' {
+                // GRUB escapes the next character, whatever it is.
+                value.extend(chars.next());
+            } else {
+                value.push(c);
+            }
+        }
+
+        Value {
+            value,
+            raw: raw.to_string(),
+        }
+    }
+
+    fn from_value(value: &str) -> Self {
+        let mut raw = String::with_capacity(value.len());
+        for c in value.chars() {
+            if c == '\\' || c == '\n' {
+                raw.push('\\');
+            }
+            raw.push(c);
+        }
+
+        Value {
+            value: value.to_string(),
+            raw,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum GrubenvError {
+    Io(io::Error),
+    InvalidUtf8,
+    InvalidLine { line_number: usize, line: String },
+    InvalidKey(String),
+    TooLarge { length: usize, size: usize },
+}
+impl Error for GrubenvError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GrubenvError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+impl Display for GrubenvError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let message = match self {
+            GrubenvError::Io(e) => localized!(
+                pt_br: "Erro ao acessar o grubenv: {e}",
+                en: "Error accessing the grubenv: {e}",
+            ),
+            GrubenvError::InvalidUtf8 => localized!(
+                pt_br: "O grubenv contém texto inválido",
+                en: "The grubenv contains invalid text",
+            ),
+            GrubenvError::InvalidLine { line_number, line } => localized!(
+                pt_br: "Linha {line_number} inválida no grubenv: {line:?}",
+                en: "Invalid line {line_number} in the grubenv: {line:?}",
+            ),
+            GrubenvError::InvalidKey(key) => localized!(
+                pt_br: "Nome inválido de variável do grubenv: {key:?}",
+                en: "Invalid grubenv variable name: {key:?}",
+            ),
+            GrubenvError::TooLarge { length, size } => localized!(
+                pt_br: "O conteúdo do grubenv ({length} bytes) excede o tamanho do arquivo ({size} bytes)",
+                en: "The grubenv content ({length} bytes) exceeds the file size ({size} bytes)",
+            ),
+        };
+        f.write_str(&message)
+    }
+}
+impl From<io::Error> for GrubenvError {
+    fn from(e: io::Error) -> Self {
+        GrubenvError::Io(e)
+    }
 }
 
 impl Grubenv {
-    pub fn load() -> io::Result<Grubenv> {
-        let file_content = fs::read_to_string(Self::path())?;
-        Ok(Self::from_file_content(&file_content))
-    }
-
-    fn from_file_content(file_content: &str) -> Grubenv {
-        let content = file_content
-            .lines()
-            .filter(|line| !line.starts_with('#'))
-            .map(|line| {
-                let (key, value) = line.split_once('=').unwrap();
-                (key.to_string(), value.to_string())
-            })
-            .collect();
+    pub fn load() -> Result<Grubenv, GrubenvError> {
+        Self::load_from(&Self::path())
+    }
 
-        Grubenv { content }
+    /// Loads a `grubenv` file other than the one in the state directory.
+    pub(crate) fn load_from(path: &Path) -> Result<Grubenv, GrubenvError> {
+        let file_content = fs::read(path)?;
+        let file_content =
+            String::from_utf8(file_content).map_err(|_| GrubenvError::InvalidUtf8)?;
+        Self::from_file_content(&file_content)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn empty() -> Grubenv {
+        Grubenv {
+            content: IndexMap::new(),
+            size: GRUBENV_CONTENT_LENGTH,
+        }
+    }
+
+    fn from_file_content(file_content: &str) -> Result<Grubenv, GrubenvError> {
+        let mut content = IndexMap::new();
+
+        for (index, line) in Self::lines(file_content).enumerate() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid_line = || GrubenvError::InvalidLine {
+                line_number: index + 1,
+                line: line.to_string(),
+            };
+            let (key, raw_value) = line.split_once('=').ok_or_else(invalid_line)?;
+            if !Self::is_valid_key(key) {
+                return Err(invalid_line());
+            }
+
+            content.insert(key.to_string(), Value::from_raw(raw_value));
+        }
+
+        Ok(Grubenv {
+            content,
+            size: file_content.len().max(GRUBENV_CONTENT_LENGTH),
+        })
+    }
+
+    /// Splits the content on the newlines that are not escaped.
+    fn lines(file_content: &str) -> impl Iterator<Item = &str> {
+        let mut remaining = file_content;
+        iter::from_fn(move || {
+            if remaining.is_empty() {
+                return None;
+            }
+
+            let mut escaped = false;
+            let end = remaining
+                .char_indices()
+                .find(|&(_, c)| {
+                    let is_end = c == '\n' && !escaped;
+                    escaped = c == '\\' && !escaped;
+                    is_end
+                })
+                .map(|(i, _)| i);
+
+            let line;
+            (line, remaining) = match end {
+                Some(end) => (&remaining[..end], &remaining[end + 1..]),
+                None => (remaining, ""),
+            };
+            Some(line)
+        })
+    }
+
+    fn is_valid_key(key: &str) -> bool {
+        !key.is_empty() && !key.contains(['=', '\n', '\\']) && !key.starts_with('#')
+    }
+
+    /// The entries, in the order they are in the file.
+    pub(crate) fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.content
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.value.as_str()))
     }
 
     pub fn get(&self, key: &str) -> Option<&String> {
-        self.content.get(key)
+        self.content.get(key).map(|value| &value.value)
     }
 
-    pub fn set(&mut self, key: &str, value: &str) {
-        self.content.insert(key.to_string(), value.to_string());
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), GrubenvError> {
+        if !Self::is_valid_key(key) {
+            return Err(GrubenvError::InvalidKey(key.to_string()));
+        }
+
+        self.content
+            .insert(key.to_string(), Value::from_value(value));
+        Ok(())
     }
 
     pub fn unset(&mut self, key: &str) {
-        self.content.remove(key);
+        self.content.shift_remove(key);
     }
 
-    pub fn save(&self) -> io::Result<()> {
-        let file_content = self.to_file_content();
-        fs::write(Self::path(), file_content)
+    pub fn save(&self) -> Result<(), GrubenvError> {
+        // The content is fully generated before touching the file.
+        let file_content = self.to_file_content()?;
+        atomic_write::write(&Self::path(), file_content)?;
+        Ok(())
     }
 
-    fn to_file_content(&self) -> String {
-        let mut content = String::with_capacity(GRUBENV_CONTENT_LENGTH);
+    fn to_file_content(&self) -> Result<String, GrubenvError> {
+        let mut content = String::with_capacity(self.size);
         content.push_str(GRUBENV_HEADER_LINE);
 
         for (key, value) in &self.content {
-            let _ = writeln!(content, "{key}={value}");
+            let _ = writeln!(content, "{key}={}", value.raw);
         }
 
-        let Some(padding_len) = GRUBENV_CONTENT_LENGTH.checked_sub(content.len()) else {
-            panic!("Grubenv content is too large!");
+        let Some(padding_len) = self.size.checked_sub(content.len()) else {
+            return Err(GrubenvError::TooLarge {
+                length: content.len(),
+                size: self.size,
+            });
         };
 
         let padding = iter::repeat_n('#', padding_len);
         content.extend(padding);
-        assert_eq!(content.len(), GRUBENV_CONTENT_LENGTH);
+        assert_eq!(content.len(), self.size);
 
-        content
+        Ok(content)
     }
 
     fn path() -> PathBuf {
-        state_path("grubenv")
+        state_path(GRUBENV_FILENAME)
     }
 }
 
@@ -80,11 +256,53 @@ mod tests {
     fn from_file_content() {
         let file_content = "abc=xyz\n#ignored line\njjj=123";
 
-        let grubenv = Grubenv::from_file_content(file_content);
+        let grubenv = Grubenv::from_file_content(file_content).unwrap();
 
         assert_eq!(grubenv.content.len(), 2);
-        assert_eq!(grubenv.content["abc"], "xyz");
-        assert_eq!(grubenv.content["jjj"], "123");
+        assert_eq!(grubenv.content["abc"].value, "xyz");
+        assert_eq!(grubenv.content["jjj"].value, "123");
+    }
+
+    #[test]
+    fn from_file_content_with_header_and_padding() {
+        let file_content = grubenv_file_content("saved_entry=windows\n");
+
+        let grubenv = Grubenv::from_file_content(&file_content).unwrap();
+
+        assert_eq!(grubenv.content.len(), 1);
+        assert_eq!(grubenv.content["saved_entry"].value, "windows");
+        assert_eq!(grubenv.size, GRUBENV_CONTENT_LENGTH);
+    }
+
+    #[test]
+    fn from_file_content_unescapes_values() {
+        let file_content = "abc=back\\\\slash\nnew=line\\\nbreak\njjj=123\n";
+
+        let grubenv = Grubenv::from_file_content(file_content).unwrap();
+
+        assert_eq!(grubenv.content.len(), 3);
+        assert_eq!(grubenv.content["abc"].value, "back\\slash");
+        assert_eq!(grubenv.content["new"].value, "line\nbreak");
+        assert_eq!(grubenv.content["jjj"].value, "123");
+    }
+
+    #[test]
+    fn from_file_content_invalid_line() {
+        let file_content = "abc=xyz\nstray line\njjj=123";
+
+        let result = Grubenv::from_file_content(file_content);
+
+        assert!(matches!(
+            result,
+            Err(GrubenvError::InvalidLine { line_number: 2, line }) if line == "stray line"
+        ));
+    }
+
+    #[test]
+    fn from_file_content_empty_key() {
+        let result = Grubenv::from_file_content("=xyz\n");
+
+        assert!(matches!(result, Err(GrubenvError::InvalidLine { .. })));
     }
 
     #[test]
@@ -99,13 +317,28 @@ mod tests {
     fn set() {
         let mut grubenv = create_grubenv();
 
-        grubenv.set("@@@", "###");
-        grubenv.set("jjj", "999");
+        grubenv.set("@@@", "###").unwrap();
+        grubenv.set("jjj", "999").unwrap();
 
         assert_eq!(grubenv.content.len(), 3);
-        assert_eq!(grubenv.content["abc"], "xyz");
-        assert_eq!(grubenv.content["jjj"], "999");
-        assert_eq!(grubenv.content["@@@"], "###");
+        assert_eq!(grubenv.content["abc"].value, "xyz");
+        assert_eq!(grubenv.content["jjj"].value, "999");
+        assert_eq!(grubenv.content["@@@"].value, "###");
+    }
+
+    #[test]
+    fn set_invalid_key() {
+        let mut grubenv = create_grubenv();
+
+        for key in ["", "a=b", "a\nb", "#abc"] {
+            let result = grubenv.set(key, "value");
+
+            assert!(
+                matches!(result, Err(GrubenvError::InvalidKey(_))),
+                "Result for key {key:?}"
+            );
+        }
+        assert_eq!(grubenv.content.len(), 2);
     }
 
     #[test]
@@ -115,19 +348,19 @@ mod tests {
         grubenv.unset("abc");
 
         assert_eq!(grubenv.content.len(), 1);
-        assert_eq!(grubenv.content["jjj"], "123");
+        assert_eq!(grubenv.content["jjj"].value, "123");
     }
 
     #[test]
     fn to_file_content() {
         const EXPECTED_LINE_1: &str = "abc=xyz\n";
         const EXPECTED_LINE_2: &str = "jjj=123\n";
-        // Entries are sorted because we are using a BTreeMap.
+        // Entries are in the order they were inserted.
         let expected_lines = format!("{EXPECTED_LINE_1}{EXPECTED_LINE_2}");
 
         let grubenv = create_grubenv();
 
-        let file_content = grubenv.to_file_content();
+        let file_content = grubenv.to_file_content().unwrap();
 
         assert_eq!(file_content.len(), GRUBENV_CONTENT_LENGTH);
         assert!(file_content.starts_with(GRUBENV_HEADER_LINE));
@@ -139,12 +372,83 @@ mod tests {
         assert!(!remaining.contains(|c| c != '#'));
     }
 
+    #[test]
+    fn to_file_content_escapes_values() {
+        let mut grubenv = create_grubenv();
+        grubenv.unset("abc");
+        grubenv.unset("jjj");
+
+        grubenv.set("abc", "back\\slash").unwrap();
+        grubenv.set("new", "line\nbreak").unwrap();
+
+        let file_content = grubenv.to_file_content().unwrap();
+
+        let remaining = &file_content[GRUBENV_HEADER_LINE.len()..];
+        assert!(remaining.starts_with("abc=back\\\\slash\nnew=line\\\nbreak\n"));
+
+        let reloaded = Grubenv::from_file_content(&file_content).unwrap();
+        assert_eq!(reloaded.get("abc"), Some(&"back\\slash".to_string()));
+        assert_eq!(reloaded.get("new"), Some(&"line\nbreak".to_string()));
+    }
+
+    #[test]
+    fn to_file_content_keeps_untouched_entries() {
+        // Written by some other tool, with an unnecessary escaping and not sorted.
+        let original = grubenv_file_content("zzz=foreign\\ value\nsaved_entry=linux\naaa=1\n");
+        let mut grubenv = Grubenv::from_file_content(&original).unwrap();
+
+        grubenv.set("saved_entry", "windows").unwrap();
+
+        let file_content = grubenv.to_file_content().unwrap();
+        let expected = grubenv_file_content("zzz=foreign\\ value\nsaved_entry=windows\naaa=1\n");
+        assert_eq!(file_content, expected);
+    }
+
+    #[test]
+    fn to_file_content_keeps_the_file_size() {
+        let mut original = grubenv_file_content("abc=xyz\n");
+        original.extend(iter::repeat_n('#', GRUBENV_CONTENT_LENGTH));
+        let grubenv = Grubenv::from_file_content(&original).unwrap();
+
+        let file_content = grubenv.to_file_content().unwrap();
+
+        assert_eq!(file_content, original);
+    }
+
+    #[test]
+    fn to_file_content_too_large() {
+        let mut grubenv = create_grubenv();
+        grubenv
+            .set("big", &"x".repeat(GRUBENV_CONTENT_LENGTH))
+            .unwrap();
+
+        let result = grubenv.to_file_content();
+
+        assert!(matches!(
+            result,
+            Err(GrubenvError::TooLarge {
+                size: GRUBENV_CONTENT_LENGTH,
+                ..
+            })
+        ));
+    }
+
     fn create_grubenv() -> Grubenv {
-        let content = BTreeMap::from_iter([
-            ("abc".to_string(), "xyz".to_string()),
-            ("jjj".to_string(), "123".to_string()),
+        let content = IndexMap::from_iter([
+            ("abc".to_string(), Value::from_value("xyz")),
+            ("jjj".to_string(), Value::from_value("123")),
         ]);
 
-        Grubenv { content }
+        Grubenv {
+            content,
+            size: GRUBENV_CONTENT_LENGTH,
+        }
+    }
+
+    fn grubenv_file_content(entries: &str) -> String {
+        let mut content = format!("{GRUBENV_HEADER_LINE}{entries}");
+        let padding = iter::repeat_n('#', GRUBENV_CONTENT_LENGTH - content.len());
+        content.extend(padding);
+        content
     }
 }
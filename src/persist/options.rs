@@ -4,10 +4,13 @@ use std::{fs, io};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
+#[cfg(windows)]
+use crate::host_os::PendingAction;
 use crate::host_os::state_path;
 use crate::options_types::ProfileId;
+use crate::persist::atomic_write;
 
-const OPTIONS_FILENAME: &str = "my-reboot-options.toml";
+pub(crate) const OPTIONS_FILENAME: &str = "my-reboot-options.toml";
 
 #[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub(crate) struct Options {
@@ -26,7 +29,7 @@ impl Options {
     }
 
     pub(crate) fn save(&self) -> Result<()> {
-        fs::write(Self::path(), toml::to_string(self)?)?;
+        atomic_write::write(&Self::path(), toml::to_string(self)?)?;
         Ok(())
     }
 
@@ -43,4 +46,7 @@ pub(crate) struct OperatingSystemsOptions {
 #[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub(crate) struct OperatingSystemOptions {
     pub(crate) profile: Option<ProfileId>,
+    #[cfg(windows)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) pending_action: Option<PendingAction>,
 }
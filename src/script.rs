@@ -1,153 +1,400 @@
+mod plan;
+
 use std::env;
 use std::fmt::Debug;
+use std::time::Duration;
 
 use ansi_term::{ANSIString, Color};
-use anyhow::Result;
+use anyhow::{Context, Result, bail};
+use chrono::Local;
 use serde::{Deserialize, Serialize};
 
-#[cfg(windows)]
-use crate::options_types::Values as _;
-use crate::options_types::{LabeledProfile, OperatingSystem, ProfileId, RebootAction};
+use self::plan::Plan;
+use crate::host_os::{
+    CommandRunner, HostCommand, NoOpCommandRunner, PendingAction, RecordingCommandRunner,
+    SystemCommandRunner,
+};
+use crate::options_types::{
+    LabeledOperatingSystem, LabeledProfile, OperatingSystem, ProfileId, RebootAction, Schedule,
+};
+use crate::persist::backup::back_up_state_files;
 use crate::state::StateProvider;
-use crate::text::Capitalized;
+use crate::text::{Capitalized, Localized, localized};
 use crate::{host_os, text};
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub struct Script {
     pub next_boot_operating_system: Option<SetOrUnset<OperatingSystem>>,
+    /// Whether [`Script::next_boot_operating_system`] is to be booted only once.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) boot_once: bool,
     pub(crate) next_windows_boot_profile: Option<SetOrUnset<ProfileId>>,
     pub(crate) switch_to_profile: Option<SwitchToProfile>,
     pub reboot_action: Option<RebootAction>,
+    /// When [`Script::reboot_action`] is to be performed, if not immediately.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) schedule: Option<Schedule>,
 }
 impl Script {
     pub const fn new() -> Self {
         Script {
             next_boot_operating_system: None,
+            boot_once: false,
             next_windows_boot_profile: None,
             switch_to_profile: None,
             reboot_action: None,
+            schedule: None,
         }
     }
 
-    pub fn execute(self) -> Result<()> {
-        let mut executor = ScriptExecutor {
-            state_provider: StateProvider::new()?,
+    /// Whether executing the script may change the state files.
+    fn changes_state(&self) -> bool {
+        self.next_boot_operating_system.is_some()
+            || self.next_windows_boot_profile.is_some()
+            || self.switch_to_profile.is_some()
+    }
+
+    pub fn execute(&self) -> Result<()> {
+        self.execute_with(ExecutionOptions::default(), &NO_HOOKS)
+    }
+
+    pub(crate) fn execute_with(
+        &self,
+        options: ExecutionOptions,
+        hooks: &ScriptHooks,
+    ) -> Result<()> {
+        if options.dry_run {
+            return self.dry_run(hooks);
+        }
+
+        if self.changes_state() {
+            back_up_state_files()?;
+        }
+
+        let command_runner: Box<dyn CommandRunner> = if env::var("NO_REBOOT_ACTION").is_ok() {
+            Box::new(NoOpCommandRunner)
+        } else {
+            Box::new(SystemCommandRunner)
         };
+        let mut executor = ScriptExecutor::new(StateProvider::new()?, command_runner.as_ref());
+        executor.hooks = hooks;
 
         executor.execute(self)
     }
+
+    /// Prints what executing the script would change, without changing anything.
+    fn dry_run(&self, hooks: &ScriptHooks) -> Result<()> {
+        let plan = self.plan(StateProvider::new()?, hooks)?;
+        print!("{plan}");
+        Ok(())
+    }
+
+    fn plan(&self, state_provider: StateProvider, hooks: &ScriptHooks) -> Result<Plan> {
+        let command_runner = RecordingCommandRunner::default();
+        let mut executor = ScriptExecutor::new(state_provider.into_dry_run(), &command_runner);
+        executor.dry_run = true;
+
+        let before = executor.state_provider.snapshot()?;
+        executor.execute(self)?;
+        let after = executor.state_provider.snapshot()?;
+
+        Ok(Plan::new(
+            &before,
+            &after,
+            hooks,
+            executor.profile_switch,
+            self.reboot_action.map(|action| (action, self.schedule)),
+            command_runner.command_lines(),
+        ))
+    }
 }
 
-struct ScriptExecutor {
+/// How a script is executed, as given in the command line.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct ExecutionOptions {
+    /// Only tells what executing the script would change.
+    pub(crate) dry_run: bool,
+    /// Asks for confirmation of the reboot action, even if the script isn't configured for it.
+    pub(crate) confirm: bool,
+    /// Skips the confirmation of the reboot action.
+    pub(crate) yes: bool,
+}
+
+/// Shell command lines run around the execution of a predefined script.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct ScriptHooks {
+    /// Run before anything is done. If one of them fails, the script is aborted.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) pre_hooks: Vec<String>,
+    /// Run after the boot options are set and the profile is switched, right before the reboot
+    /// action.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) post_hooks: Vec<String>,
+    /// How many seconds each hook may run before being killed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) hook_timeout: Option<u64>,
+}
+impl ScriptHooks {
+    const DEFAULT_TIMEOUT_SECONDS: u64 = 60;
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(self.hook_timeout.unwrap_or(Self::DEFAULT_TIMEOUT_SECONDS))
+    }
+}
+
+static NO_HOOKS: ScriptHooks = ScriptHooks {
+    pre_hooks: Vec::new(),
+    post_hooks: Vec::new(),
+    hook_timeout: None,
+};
+
+struct ScriptExecutor<'a> {
     state_provider: StateProvider,
+    command_runner: &'a dyn CommandRunner,
+    hooks: &'a ScriptHooks,
+    /// Whether the changes are only planned, so nothing is told as if it were done.
+    dry_run: bool,
+    /// The profile switched to by a dry run.
+    profile_switch: Option<String>,
 }
-impl ScriptExecutor {
-    fn execute(&mut self, script: Script) -> Result<()> {
-        if let Some(os_option) = script.next_boot_operating_system {
-            self.apply_next_boot_operating_system(os_option);
+impl<'a> ScriptExecutor<'a> {
+    fn new(state_provider: StateProvider, command_runner: &'a dyn CommandRunner) -> Self {
+        Self {
+            state_provider,
+            command_runner,
+            hooks: &NO_HOOKS,
+            dry_run: false,
+            profile_switch: None,
         }
+    }
+
+    fn execute(&mut self, script: &Script) -> Result<()> {
+        self.run_pre_hooks()?;
 
-        if let Some(profile_option) = script.next_windows_boot_profile {
-            self.apply_next_windows_boot_profile(profile_option);
+        if let Some(os_option) = &script.next_boot_operating_system {
+            self.apply_next_boot_operating_system(os_option, script.boot_once)?;
+        }
+
+        if let Some(profile_option) = &script.next_windows_boot_profile {
+            self.apply_next_windows_boot_profile(profile_option)?;
         }
 
         cfg_select! {
             windows => {
-                if let Some(switch_to) = script.switch_to_profile {
+                if let Some(switch_to) = &script.switch_to_profile {
                     self.apply_switch_to_profile(switch_to)?;
                 }
             },
             _ => {
                 if script.switch_to_profile.is_some() {
-                    anyhow::bail!("Troca de perfil é suportada somente no Windows");
+                    anyhow::bail!(localized!(
+                        pt_br: "Troca de perfil é suportada somente no Windows",
+                        en: "Profile switching is only supported on Windows",
+                    ));
                 }
             },
         }
 
+        self.run_post_hooks();
+
         if let Some(reboot_action) = script.reboot_action {
-            Self::apply_reboot_action(reboot_action)?;
+            match script.schedule {
+                Some(schedule) => self.schedule_reboot_action(reboot_action, schedule)?,
+                None => self.apply_reboot_action(reboot_action)?,
+            }
         }
 
         Ok(())
     }
 
-    fn apply_next_boot_operating_system(&mut self, os_option: SetOrUnset<OperatingSystem>) {
+    /// Runs the pre-hooks, stopping at the first one that fails.
+    fn run_pre_hooks(&self) -> Result<()> {
+        if self.dry_run {
+            return Ok(());
+        }
+
+        for hook in &self.hooks.pre_hooks {
+            let failed = || {
+                localized!(
+                    pt_br: "O pre-hook falhou, então o script foi abortado: {hook}",
+                    en: "The pre-hook failed, so the script was aborted: {hook}",
+                )
+            };
+            if !self.run_hook(hook).with_context(failed)? {
+                bail!(failed());
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the post-hooks, only warning about the ones that fail, as the script is already done.
+    fn run_post_hooks(&self) {
+        if self.dry_run {
+            return;
+        }
+
+        for hook in &self.hooks.post_hooks {
+            let error = match self.run_hook(hook) {
+                Ok(true) => continue,
+                Ok(false) => None,
+                Err(e) => Some(e),
+            };
+            let warning = localized!(
+                pt_br: "O post-hook falhou: {hook}",
+                en: "The post-hook failed: {hook}",
+            );
+            match error {
+                Some(e) => eprintln!("{}: {e:#}", Color::Yellow.paint(warning)),
+                None => eprintln!("{}", Color::Yellow.paint(warning)),
+            }
+        }
+    }
+
+    /// Runs the hook, printing its output, and returns whether it succeeded.
+    fn run_hook(&self, hook: &str) -> Result<bool> {
+        println!(
+            "{}: {hook}",
+            Localized::new("Executando hook", "Running hook")
+        );
+        let captured = self
+            .command_runner
+            .run_captured(&HostCommand::shell(hook), self.hooks.timeout())?;
+        for line in captured.output.lines() {
+            println!("  | {line}");
+        }
+        Ok(captured.success)
+    }
+
+    fn apply_next_boot_operating_system(
+        &mut self,
+        os_option: &SetOrUnset<OperatingSystem>,
+        once: bool,
+    ) -> Result<()> {
+        // Clone the label to avoid capturing the state_provider lifetime.
+        let os_option = match os_option {
+            SetOrUnset::Set(os) => {
+                let labeled_os = self.state_provider.configs().labeled_operating_system(os)?;
+                Some((os.clone(), labeled_os.to_string(), labeled_os.index()))
+            }
+            SetOrUnset::Unset => None,
+        };
+        let os_option = os_option
+            .as_ref()
+            .map(|(os, label, index)| LabeledOperatingSystem::new(os, label, *index))
+            .into();
+
+        let description = if once {
+            text::operating_system::ONE_SHOT_DESCRIPTION
+        } else {
+            text::operating_system::ON_NEXT_BOOT_DESCRIPTION
+        };
+
         self.apply_option(
             os_option,
-            StateProvider::set_next_boot_operating_system,
-            std::convert::identity,
-            text::operating_system::ON_NEXT_BOOT_DESCRIPTION,
+            |state_provider, os| {
+                if once {
+                    state_provider.set_one_shot_boot_operating_system(os)
+                } else {
+                    state_provider.set_next_boot_operating_system(os)
+                }
+            },
+            |labeled_os| labeled_os.operating_system().clone(),
+            description,
             text::operating_system::WAS_UPDATED_TO,
             text::operating_system::value_text,
-        );
+        )
     }
 
-    fn apply_next_windows_boot_profile(&mut self, profile_option: SetOrUnset<ProfileId>) {
+    fn apply_next_windows_boot_profile(
+        &mut self,
+        profile_option: &SetOrUnset<ProfileId>,
+    ) -> Result<()> {
         // Clone the label to avoid capturing the state_provider lifetime.
-        let profile_option = profile_option.into_option().map(|profile_id| {
-            let label = self.state_provider.configs().profile[profile_id]
-                .label
-                .clone();
-            (profile_id, label)
-        });
+        let profile_option = match profile_option {
+            SetOrUnset::Set(profile_id) => {
+                let labeled_profile = self.state_provider.configs().labeled_profile(profile_id)?;
+                let label = self.state_provider.configs().profile[profile_id]
+                    .label
+                    .clone();
+                Some((profile_id.clone(), label, labeled_profile.index()))
+            }
+            SetOrUnset::Unset => None,
+        };
         let profile_option = profile_option
             .as_ref()
-            .map(|(profile_id, label)| LabeledProfile::new(*profile_id, label))
+            .map(|(profile_id, label, index)| LabeledProfile::new(profile_id, label, *index))
             .into();
 
         self.apply_option(
             profile_option,
             StateProvider::set_next_windows_boot_profile,
-            LabeledProfile::profile_id,
+            |labeled_profile| labeled_profile.profile_id().clone(),
             text::profile::ON_NEXT_WINDOWS_BOOT_DESCRIPTION,
             text::profile::WAS_UPDATED_TO,
             text::profile::next_boot_value_text,
-        );
+        )
     }
 
     fn apply_option<T: Copy, U>(
         &mut self,
         option: SetOrUnset<T>,
-        set: impl FnOnce(&mut StateProvider, Option<U>),
+        set: impl FnOnce(&mut StateProvider, Option<U>) -> Result<()>,
         extract: impl FnOnce(T) -> U,
-        description: &str,
-        was_updated_to: &str,
+        description: Localized,
+        was_updated_to: Localized,
         value_text: impl FnOnce(Option<T>) -> ANSIString<'static>,
-    ) {
+    ) -> Result<()> {
         match option {
-            SetOrUnset::Set(option) => set(&mut self.state_provider, Some(extract(option))),
-            SetOrUnset::Unset => set(&mut self.state_provider, None),
+            SetOrUnset::Set(option) => set(&mut self.state_provider, Some(extract(option)))?,
+            SetOrUnset::Unset => set(&mut self.state_provider, None)?,
         }
 
+        if self.dry_run {
+            return Ok(());
+        }
         println!(
             "{} {} {}.",
             Capitalized(description),
             was_updated_to,
             value_text(option.into_option())
         );
+
+        Ok(())
     }
 
     #[cfg(windows)]
-    fn apply_switch_to_profile(&mut self, switch_to: SwitchToProfile) -> Result<()> {
+    fn apply_switch_to_profile(&mut self, switch_to: &SwitchToProfile) -> Result<()> {
         let from_profile = self.state_provider.current_profile()?;
 
         match switch_to {
             SwitchToProfile::Other => {
                 let Some(from_profile) = from_profile else {
-                    anyhow::bail!("Não foi possível identificar o perfil atual");
+                    anyhow::bail!(localized!(
+                        pt_br: "Não foi possível identificar o perfil atual",
+                        en: "The current profile couldn't be identified",
+                    ));
                 };
 
-                let to_profile = ProfileId::values()
-                    .into_iter()
-                    .find(|&p| p != from_profile)
-                    .unwrap();
-
-                self.switch_profile_to(to_profile)?;
+                // Cycles through the profiles, in the order they are configured.
+                let to_profile = self
+                    .state_provider
+                    .configs()
+                    .profile_after(&from_profile)
+                    .filter(|&to_profile| *to_profile != from_profile)
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(localized!(
+                            pt_br: "Não há outro perfil para o qual trocar",
+                            en: "There is no other profile to switch to",
+                        ))
+                    })?
+                    .clone();
+
+                self.switch_profile_to(&to_profile)?;
             }
             SwitchToProfile::Profile(to_profile) => {
-                if Some(to_profile) == from_profile {
+                if Some(to_profile) == from_profile.as_ref() {
                     let labeled_profile =
-                        LabeledProfile::get(to_profile, self.state_provider.configs());
+                        self.state_provider.configs().labeled_profile(to_profile)?;
                     println!(
                         "{} {}",
                         text::profile::current_value_text(Some(labeled_profile)),
@@ -157,37 +404,50 @@ impl ScriptExecutor {
                     self.switch_profile_to(to_profile)?;
                 }
             }
-            SwitchToProfile::Saved => match self.state_provider.next_windows_boot_profile() {
-                Some(to_profile) => {
-                    if Some(to_profile) == from_profile {
-                        let labeled_profile =
-                            LabeledProfile::get(to_profile, self.state_provider.configs());
+            SwitchToProfile::Saved => {
+                match self.state_provider.next_windows_boot_profile().cloned() {
+                    Some(to_profile) => {
+                        if Some(&to_profile) == from_profile.as_ref() {
+                            let labeled_profile =
+                                self.state_provider.configs().labeled_profile(&to_profile)?;
+                            let description = text::profile::ON_NEXT_WINDOWS_BOOT_DESCRIPTION;
+                            let value = text::profile::current_value_text(Some(labeled_profile));
+                            println!(
+                                "{}",
+                                localized!(
+                                    pt_br: "O {description} é {value}, que já é o perfil atual",
+                                    en: "The {description} is {value}, which is already the current profile",
+                                )
+                            );
+                        } else {
+                            self.switch_profile_to(&to_profile)?;
+                        }
+                    }
+                    None => {
+                        let description = text::profile::ON_NEXT_WINDOWS_BOOT_DESCRIPTION;
+                        let value = text::profile::next_boot_value_text(None);
                         println!(
-                            "O {} é {}, que já é o perfil atual",
-                            text::profile::ON_NEXT_WINDOWS_BOOT_DESCRIPTION,
-                            text::profile::current_value_text(Some(labeled_profile))
+                            "{}",
+                            localized!(
+                                pt_br: "O {description} é {value}",
+                                en: "The {description} is {value}",
+                            )
                         );
-                    } else {
-                        self.switch_profile_to(to_profile)?;
-                        self.state_provider.next_windows_boot_profile();
                     }
                 }
-                None => {
-                    println!(
-                        "O {} é {}",
-                        text::profile::ON_NEXT_WINDOWS_BOOT_DESCRIPTION,
-                        text::profile::next_boot_value_text(None)
-                    );
-                }
-            },
+            }
         }
 
         Ok(())
     }
 
     #[cfg(windows)]
-    fn switch_profile_to(&self, profile_id: ProfileId) -> Result<()> {
-        let labeled_profile = LabeledProfile::get(profile_id, self.state_provider.configs());
+    fn switch_profile_to(&mut self, profile_id: &ProfileId) -> Result<()> {
+        let labeled_profile = self.state_provider.configs().labeled_profile(profile_id)?;
+        if self.dry_run {
+            self.profile_switch = Some(labeled_profile.to_string());
+            return Ok(());
+        }
         println!(
             "{} {}",
             text::profile::switching::TO,
@@ -196,21 +456,52 @@ impl ScriptExecutor {
         self.state_provider.set_current_profile(profile_id)
     }
 
-    fn apply_reboot_action(reboot_action: RebootAction) -> Result<()> {
-        match reboot_action {
-            RebootAction::Reboot => Self::do_reboot_action(host_os::reboot, "Reiniciando"),
-            RebootAction::Shutdown => Self::do_reboot_action(host_os::shutdown, "Desligando"),
+    fn apply_reboot_action(&self, reboot_action: RebootAction) -> Result<()> {
+        let message = match reboot_action {
+            RebootAction::Reboot => Localized::new("Reiniciando", "Rebooting"),
+            RebootAction::Shutdown => Localized::new("Desligando", "Shutting down"),
+            RebootAction::FirmwareSetup => Localized::new(
+                "Reiniciando na configuração do firmware",
+                "Rebooting into the firmware setup",
+            ),
+            RebootAction::Suspend => Localized::new("Suspendendo", "Suspending"),
+            RebootAction::Hibernate => Localized::new("Hibernando", "Hibernating"),
+            RebootAction::Logout => Localized::new("Encerrando a sessão", "Logging out"),
+            RebootAction::Kexec => Localized::new("Reiniciando via kexec", "Rebooting via kexec"),
+        };
+        if !self.dry_run {
+            println!("{message}...");
         }
+        host_os::perform(reboot_action, self.command_runner)
     }
 
-    fn do_reboot_action(method: fn() -> Result<()>, message: &str) -> Result<()> {
-        println!("{message}...");
-        if env::var("NO_REBOOT_ACTION").is_ok() {
-            println!("{} 😬", Color::Yellow.paint("...mas não de verdade!"));
-            Ok(())
-        } else {
-            method()
+    fn schedule_reboot_action(
+        &mut self,
+        reboot_action: RebootAction,
+        schedule: Schedule,
+    ) -> Result<()> {
+        let now = Local::now();
+        let pending_action = PendingAction {
+            action: reboot_action,
+            at: schedule.resolve(now),
+        };
+        let pending = text::reboot_action::pending_text(&pending_action, now);
+        if !self.dry_run {
+            println!(
+                "{}",
+                localized!(
+                    pt_br: "Agendado: {pending}. Use 'my-reboot cancel' para cancelar.",
+                    en: "Scheduled: {pending}. Use 'my-reboot cancel' to cancel.",
+                )
+            );
+        }
+        host_os::schedule(reboot_action, schedule, self.command_runner)?;
+        // Windows doesn't tell which action is pending.
+        #[cfg(windows)]
+        if !self.dry_run {
+            self.state_provider.set_pending_action(pending_action)?;
         }
+        Ok(())
     }
 }
 
@@ -243,7 +534,7 @@ impl<T> From<Option<T>> for SetOrUnset<T> {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub(crate) enum SwitchToProfile {
     #[serde(rename = "other")]
     Other,
@@ -262,13 +553,235 @@ impl From<ProfileId> for SwitchToProfile {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::host_os::CapturedOutput;
     use crate::options_types::{DeserializeFromString as _, SerializeToString as _};
+    use crate::persist::configs::Configs;
+
+    fn test_configs() -> Result<Configs> {
+        Configs::from_serialized(
+            r#"
+            [operating_system.windows]
+            grub_entry = "windows-grub-entry"
+
+            [operating_system.linux]
+            grub_entry = "linux-grub-entry"
+
+            [profile.a]
+            label = "Monitor"
+            display_configs = "monitor-display-configs"
+            "#,
+        )
+    }
+
+    /// The command lines that executing the script would run.
+    #[cfg(not(windows))]
+    fn executed_commands(script: &Script) -> Result<Vec<String>> {
+        executed_commands_with_hooks(script, &NO_HOOKS)
+    }
+
+    #[cfg(not(windows))]
+    fn executed_commands_with_hooks(script: &Script, hooks: &ScriptHooks) -> Result<Vec<String>> {
+        let configs = test_configs()?;
+        let command_runner = RecordingCommandRunner::default();
+        let mut executor =
+            ScriptExecutor::new(StateProvider::with_configs(configs), &command_runner);
+        executor.hooks = hooks;
+        executor.execute(script)?;
+        Ok(command_runner.command_lines())
+    }
+
+    fn test_hooks() -> ScriptHooks {
+        ScriptHooks {
+            pre_hooks: vec!["stop-vms".to_string()],
+            post_hooks: vec!["restart-audio-sink".to_string()],
+            hook_timeout: Some(5),
+        }
+    }
+
+    /// Fails all the commands, after recording them.
+    #[derive(Default)]
+    struct FailingCommandRunner(RecordingCommandRunner);
+    impl CommandRunner for FailingCommandRunner {
+        fn run(&self, command: &HostCommand) -> Result<bool> {
+            self.0.run(command)?;
+            Ok(false)
+        }
+
+        fn run_captured(&self, command: &HostCommand, timeout: Duration) -> Result<CapturedOutput> {
+            self.0.run_captured(command, timeout)?;
+            Ok(CapturedOutput {
+                success: false,
+                output: "failure\n".to_string(),
+            })
+        }
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn execute_runs_the_reboot_action_command() -> Result<()> {
+        let cases = [
+            (RebootAction::Reboot, "systemctl reboot"),
+            (RebootAction::Shutdown, "systemctl poweroff"),
+            (
+                RebootAction::FirmwareSetup,
+                "systemctl reboot --firmware-setup",
+            ),
+        ];
+
+        for (reboot_action, expected) in cases {
+            let script = Script {
+                reboot_action: Some(reboot_action),
+                ..Script::new()
+            };
+            assert_eq!(executed_commands(&script)?, [expected]);
+        }
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn execute_schedules_the_reboot_action() -> Result<()> {
+        let script = Script {
+            reboot_action: Some(RebootAction::Shutdown),
+            schedule: Some(Schedule::In(10)),
+            ..Script::new()
+        };
+
+        assert_eq!(executed_commands(&script)?, ["shutdown --poweroff +10"]);
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn execute_records_the_scheduled_action() -> Result<()> {
+        let script = Script {
+            reboot_action: Some(RebootAction::Shutdown),
+            schedule: Some(Schedule::In(10)),
+            ..Script::new()
+        };
+        let command_runner = RecordingCommandRunner::default();
+        let mut executor = ScriptExecutor::new(
+            StateProvider::with_configs(test_configs()?).into_dry_run(),
+            &command_runner,
+        );
+
+        executor.execute(&script)?;
+
+        assert_eq!(command_runner.command_lines().len(), 1);
+        let options = executor.state_provider.snapshot()?.options;
+        assert!(
+            options.iter().any(|line| line == "action = \"shutdown\""),
+            "{options:?}"
+        );
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn plan_tells_the_changes_and_commands() -> Result<()> {
+        let script = Script {
+            next_boot_operating_system: Some(SetOrUnset::Set(OperatingSystem::WINDOWS)),
+            next_windows_boot_profile: Some(SetOrUnset::Set(ProfileId::A)),
+            reboot_action: Some(RebootAction::Reboot),
+            schedule: Some(Schedule::In(10)),
+            ..Script::new()
+        };
+
+        let plan = script.plan(StateProvider::with_configs(test_configs()?), &NO_HOOKS)?;
+
+        assert_eq!(plan.grubenv_changes, ["+ saved_entry=windows-grub-entry"]);
+        assert_eq!(plan.options_changes, ["+ profile = \"a\""]);
+        assert_eq!(plan.profile_switch, None);
+        assert_eq!(
+            plan.reboot_action,
+            Some((RebootAction::Reboot, Some(Schedule::In(10))))
+        );
+        assert_eq!(plan.commands, ["shutdown --reboot +10"]);
+        Ok(())
+    }
+
+    #[test]
+    fn plan_of_one_shot_boot() -> Result<()> {
+        let script = Script {
+            next_boot_operating_system: Some(SetOrUnset::Set(OperatingSystem::LINUX)),
+            boot_once: true,
+            ..Script::new()
+        };
+
+        let plan = script.plan(StateProvider::with_configs(test_configs()?), &NO_HOOKS)?;
+
+        assert_eq!(plan.grubenv_changes, ["+ next_entry=linux-grub-entry"]);
+        assert!(plan.options_changes.is_empty());
+        assert!(plan.commands.is_empty());
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn execute_without_reboot_action_runs_nothing() -> Result<()> {
+        assert!(executed_commands(&Script::new())?.is_empty());
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn execute_runs_the_hooks_around_the_script() -> Result<()> {
+        let script = Script {
+            reboot_action: Some(RebootAction::Reboot),
+            ..Script::new()
+        };
+
+        assert_eq!(
+            executed_commands_with_hooks(&script, &test_hooks())?,
+            [
+                "sh -c stop-vms",
+                "sh -c restart-audio-sink",
+                "systemctl reboot"
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn failing_pre_hook_aborts_the_script() -> Result<()> {
+        let script = Script {
+            next_boot_operating_system: Some(SetOrUnset::Set(OperatingSystem::WINDOWS)),
+            reboot_action: Some(RebootAction::Reboot),
+            ..Script::new()
+        };
+        let hooks = test_hooks();
+        let command_runner = FailingCommandRunner::default();
+        let mut executor = ScriptExecutor::new(
+            StateProvider::with_configs(test_configs()?).into_dry_run(),
+            &command_runner,
+        );
+        executor.hooks = &hooks;
+
+        assert!(executor.execute(&script).is_err());
+        assert_eq!(
+            command_runner.0.command_lines(),
+            [HostCommand::shell("stop-vms").to_string()]
+        );
+        assert!(executor.state_provider.snapshot()?.grubenv.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn plan_lists_the_hooks() -> Result<()> {
+        let plan =
+            Script::new().plan(StateProvider::with_configs(test_configs()?), &test_hooks())?;
+
+        assert_eq!(plan.pre_hooks, ["stop-vms"]);
+        assert_eq!(plan.post_hooks, ["restart-audio-sink"]);
+        Ok(())
+    }
 
     #[test]
     fn set_or_unset_operating_system_serialize_to_string() {
         let cases = [
-            (SetOrUnset::Set(OperatingSystem::Windows), "windows"),
-            (SetOrUnset::Set(OperatingSystem::Linux), "linux"),
+            (SetOrUnset::Set(OperatingSystem::WINDOWS), "windows"),
+            (SetOrUnset::Set(OperatingSystem::LINUX), "linux"),
+            (SetOrUnset::Set(os("fedora")), "fedora"),
             (SetOrUnset::Unset, "unset"),
         ];
 
@@ -280,10 +793,12 @@ mod tests {
     #[test]
     fn set_or_unset_operating_system_deserialize_from_string() {
         let cases = [
-            ("windows", Some(SetOrUnset::Set(OperatingSystem::Windows))),
-            ("linux", Some(SetOrUnset::Set(OperatingSystem::Linux))),
+            ("windows", Some(SetOrUnset::Set(OperatingSystem::WINDOWS))),
+            ("linux", Some(SetOrUnset::Set(OperatingSystem::LINUX))),
+            ("fedora", Some(SetOrUnset::Set(os("fedora")))),
             ("unset", Some(SetOrUnset::Unset)),
-            ("invalid", None),
+            ("Invalid OS", None),
+            ("", None),
         ];
 
         for (s, expected) in cases {
@@ -294,6 +809,14 @@ mod tests {
         }
     }
 
+    fn os(id: &str) -> OperatingSystem {
+        OperatingSystem::try_from(id.to_string()).unwrap()
+    }
+
+    fn profile_id(id: &str) -> ProfileId {
+        ProfileId::try_from(id.to_string()).unwrap()
+    }
+
     #[test]
     fn set_or_unset_profile_id_serialize_to_string() {
         let cases = [
@@ -312,8 +835,9 @@ mod tests {
         let cases = [
             ("a", Some(SetOrUnset::Set(ProfileId::A))),
             ("b", Some(SetOrUnset::Set(ProfileId::B))),
+            ("tv", Some(SetOrUnset::Set(profile_id("tv")))),
             ("unset", Some(SetOrUnset::Unset)),
-            ("invalid", None),
+            ("Invalid profile", None),
         ];
 
         for (s, expected) in cases {
@@ -345,7 +869,8 @@ mod tests {
             ("saved", Some(SwitchToProfile::Saved)),
             ("a", Some(SwitchToProfile::Profile(ProfileId::A))),
             ("b", Some(SwitchToProfile::Profile(ProfileId::B))),
-            ("invalid", None),
+            ("tv", Some(SwitchToProfile::Profile(profile_id("tv")))),
+            ("Invalid profile", None),
         ];
 
         for (s, expected) in cases {
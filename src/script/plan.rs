@@ -0,0 +1,159 @@
+use std::fmt::Display;
+
+use ansi_term::Color::{Green, Red};
+
+use crate::options_types::{RebootAction, Schedule};
+use crate::persist::grubenv::GRUBENV_FILENAME;
+use crate::persist::options::OPTIONS_FILENAME;
+use crate::script::ScriptHooks;
+use crate::state::StateSnapshot;
+use crate::text::{self, Capitalized, Localized};
+
+/// What executing a script would do, as shown by a dry run.
+pub(crate) struct Plan {
+    pub(crate) pre_hooks: Vec<String>,
+    /// The changed lines of the grubenv, like `+ saved_entry=...`.
+    pub(crate) grubenv_changes: Vec<String>,
+    /// The changed lines of the options file.
+    pub(crate) options_changes: Vec<String>,
+    /// The profile that would be switched to.
+    pub(crate) profile_switch: Option<String>,
+    pub(crate) post_hooks: Vec<String>,
+    pub(crate) reboot_action: Option<(RebootAction, Option<Schedule>)>,
+    /// The command lines that would be run.
+    pub(crate) commands: Vec<String>,
+}
+impl Plan {
+    pub(crate) fn new(
+        before: &StateSnapshot,
+        after: &StateSnapshot,
+        hooks: &ScriptHooks,
+        profile_switch: Option<String>,
+        reboot_action: Option<(RebootAction, Option<Schedule>)>,
+        commands: Vec<String>,
+    ) -> Self {
+        Self {
+            pre_hooks: hooks.pre_hooks.clone(),
+            grubenv_changes: changes(&before.grubenv, &after.grubenv),
+            options_changes: changes(&before.options, &after.options),
+            profile_switch,
+            post_hooks: hooks.post_hooks.clone(),
+            reboot_action,
+            commands,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pre_hooks.is_empty()
+            && self.post_hooks.is_empty()
+            && self.grubenv_changes.is_empty()
+            && self.options_changes.is_empty()
+            && self.profile_switch.is_none()
+            && self.reboot_action.is_none()
+    }
+}
+impl Display for Plan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return writeln!(
+                f,
+                "{}",
+                Localized::new("Nada seria alterado.", "Nothing would be changed.")
+            );
+        }
+
+        writeln!(
+            f,
+            "{}",
+            Localized::new("Plano (nada foi alterado):", "Plan (nothing was changed):")
+        )?;
+
+        write_hooks(f, "Pre-hooks", &self.pre_hooks)?;
+
+        for (filename, changes) in [
+            (GRUBENV_FILENAME, &self.grubenv_changes),
+            (OPTIONS_FILENAME, &self.options_changes),
+        ] {
+            if changes.is_empty() {
+                continue;
+            }
+            writeln!(f, "--- {filename}")?;
+            for change in changes {
+                let color = if change.starts_with('-') { Red } else { Green };
+                writeln!(f, "{}", color.paint(change))?;
+            }
+        }
+
+        if let Some(profile) = &self.profile_switch {
+            writeln!(
+                f,
+                "{}: {profile}",
+                Capitalized(text::profile::SWITCH_DESCRIPTION)
+            )?;
+        }
+
+        write_hooks(f, "Post-hooks", &self.post_hooks)?;
+
+        if let Some((action, schedule)) = self.reboot_action {
+            let description = Capitalized(text::reboot_action::ACTION_DESCRIPTION);
+            match schedule {
+                Some(schedule) => writeln!(f, "{description}: {action} ({schedule})")?,
+                None => writeln!(f, "{description}: {action}")?,
+            }
+            for command in &self.commands {
+                writeln!(f, "  $ {command}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn write_hooks(f: &mut std::fmt::Formatter<'_>, title: &str, hooks: &[String]) -> std::fmt::Result {
+    if !hooks.is_empty() {
+        writeln!(f, "{title}:")?;
+        for hook in hooks {
+            writeln!(f, "  $ {hook}")?;
+        }
+    }
+    Ok(())
+}
+
+/// The lines removed from `before`, prefixed by `-`, followed by the ones added to `after`,
+/// prefixed by `+`.
+fn changes(before: &[String], after: &[String]) -> Vec<String> {
+    let removed = before
+        .iter()
+        .filter(|line| !after.contains(line))
+        .map(|line| format!("- {line}"));
+    let added = after
+        .iter()
+        .filter(|line| !before.contains(line))
+        .map(|line| format!("+ {line}"));
+    removed.chain(added).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn changes_of_lines() {
+        let before = lines(&["saved_entry=linux", "next_entry=windows", "other=1"]);
+        let after = lines(&["saved_entry=windows", "other=1"]);
+
+        assert_eq!(
+            changes(&before, &after),
+            [
+                "- saved_entry=linux",
+                "- next_entry=windows",
+                "+ saved_entry=windows",
+            ]
+        );
+        assert!(changes(&before, &before).is_empty());
+    }
+}
@@ -1,25 +1,60 @@
-use anyhow::Result;
+use anyhow::{Result, bail};
 
-#[cfg(windows)]
-use crate::host_os;
+use crate::host_os::{self, PendingAction};
 use crate::options_types::{OperatingSystem, ProfileId};
 use crate::persist::configs::Configs;
 use crate::persist::grubenv::Grubenv;
 use crate::persist::options::Options;
+use crate::text::localized;
 
 const GRUB_ENTRY: &str = "saved_entry";
+/// The GRUB entry to be booted only once, taking precedence over [`GRUB_ENTRY`].
+const ONE_SHOT_GRUB_ENTRY: &str = "next_entry";
 
 pub struct State {
-    pub next_boot_operating_system: Option<OperatingSystem>,
+    pub next_boot_operating_system: Option<NextBootEntry>,
+    /// The raw GRUB entry, as saved in the grubenv.
+    pub(crate) saved_entry: Option<String>,
+    pub(crate) one_shot_boot_operating_system: Option<NextBootEntry>,
+    /// The raw one-shot GRUB entry, as saved in the grubenv.
+    pub(crate) next_entry: Option<String>,
     pub(crate) next_windows_boot_profile: Option<ProfileId>,
     #[cfg(windows)]
     pub(crate) current_profile: Option<ProfileId>,
+    pub(crate) pending_action: Option<PendingAction>,
+}
+
+/// The contents of the state files, as compared by a dry run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct StateSnapshot {
+    /// The grubenv entries, as `key=value`.
+    pub(crate) grubenv: Vec<String>,
+    /// The lines of the options file.
+    pub(crate) options: Vec<String>,
+}
+
+/// The GRUB entry saved to be booted next.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NextBootEntry {
+    OperatingSystem(OperatingSystem),
+    /// An entry that doesn't belong to any configured operating system, e.g. chosen by hand.
+    Unknown(String),
+}
+impl NextBootEntry {
+    pub(crate) fn into_operating_system(self) -> Option<OperatingSystem> {
+        match self {
+            NextBootEntry::OperatingSystem(os) => Some(os),
+            NextBootEntry::Unknown(_) => None,
+        }
+    }
 }
 
 pub struct StateProvider {
     grubenv: Grubenv,
     options: Options,
     configs: Configs,
+    /// Whether the changes are kept only in memory.
+    dry_run: bool,
 }
 impl StateProvider {
     pub fn new() -> Result<StateProvider> {
@@ -30,46 +65,130 @@ impl StateProvider {
             grubenv,
             options,
             configs,
+            dry_run: false,
+        })
+    }
+
+    /// A provider with no GRUB entries, the default options and the given configs.
+    #[cfg(test)]
+    pub(crate) fn with_configs(configs: Configs) -> StateProvider {
+        StateProvider {
+            grubenv: Grubenv::empty(),
+            options: Options::default(),
+            configs,
+            dry_run: false,
+        }
+    }
+
+    /// Makes the provider keep the changes only in memory, without saving the state files.
+    pub(crate) fn into_dry_run(self) -> StateProvider {
+        StateProvider {
+            dry_run: true,
+            ..self
+        }
+    }
+
+    /// The state files' contents as lines, to tell what a dry run would change.
+    pub(crate) fn snapshot(&self) -> Result<StateSnapshot> {
+        Ok(StateSnapshot {
+            grubenv: self
+                .grubenv
+                .entries()
+                .map(|(key, value)| format!("{key}={value}"))
+                .collect(),
+            options: toml::to_string(&self.options)?
+                .lines()
+                .map(str::to_string)
+                .collect(),
         })
     }
 
-    #[cfg_attr(not(windows), expect(clippy::unnecessary_wraps))]
     pub fn state(&self) -> Result<State> {
         Ok(State {
-            next_boot_operating_system: self.next_boot_operating_system(),
-            next_windows_boot_profile: self.next_windows_boot_profile(),
+            next_boot_operating_system: self.next_boot_entry(GRUB_ENTRY),
+            saved_entry: self.grubenv.get(GRUB_ENTRY).cloned(),
+            one_shot_boot_operating_system: self.next_boot_entry(ONE_SHOT_GRUB_ENTRY),
+            next_entry: self.grubenv.get(ONE_SHOT_GRUB_ENTRY).cloned(),
+            next_windows_boot_profile: self.next_windows_boot_profile().cloned(),
             #[cfg(windows)]
             current_profile: self.current_profile()?,
+            pending_action: host_os::pending_action()?,
+        })
+    }
+
+    fn next_boot_entry(&self, name: &str) -> Option<NextBootEntry> {
+        self.grubenv.get(name).map(|grub_entry| {
+            match self.configs.operating_system_by_grub_entry(grub_entry) {
+                Some(os) => NextBootEntry::OperatingSystem(os),
+                None => NextBootEntry::Unknown(grub_entry.clone()),
+            }
         })
     }
 
-    fn next_boot_operating_system(&self) -> Option<OperatingSystem> {
-        self.grubenv
-            .get(GRUB_ENTRY)
-            .map(|grub_entry| self.configs.operating_system_by_grub_entry(grub_entry))
+    /// Sets the operating system to be booted from now on, discarding any one-shot boot.
+    pub fn set_next_boot_operating_system(&mut self, os: Option<OperatingSystem>) -> Result<()> {
+        self.grubenv.unset(ONE_SHOT_GRUB_ENTRY);
+        self.set_grub_entry(GRUB_ENTRY, os)
+    }
+
+    /// Sets the operating system to be booted only on the next boot, after which GRUB falls back
+    /// to the one set by [`Self::set_next_boot_operating_system`].
+    pub(crate) fn set_one_shot_boot_operating_system(
+        &mut self,
+        os: Option<OperatingSystem>,
+    ) -> Result<()> {
+        self.set_grub_entry(ONE_SHOT_GRUB_ENTRY, os)
     }
 
-    pub fn set_next_boot_operating_system(&mut self, os: Option<OperatingSystem>) {
+    fn set_grub_entry(&mut self, name: &str, os: Option<OperatingSystem>) -> Result<()> {
         match os {
             Some(os) => {
-                let grub_entry = &self.configs.operating_system[os].grub_entry;
-                self.grubenv.set(GRUB_ENTRY, grub_entry);
+                let Some(os_configs) = self.configs.operating_system.get(&os) else {
+                    let id = os.id();
+                    bail!(localized!(
+                        pt_br: "Sistema operacional desconhecido: {id}",
+                        en: "Unknown operating system: {id}",
+                    ));
+                };
+                self.grubenv.set(name, &os_configs.grub_entry)?;
             }
             None => {
-                self.grubenv.unset(GRUB_ENTRY);
+                self.grubenv.unset(name);
             }
         }
 
-        self.grubenv.save().unwrap();
+        if !self.dry_run {
+            self.grubenv.save()?;
+        }
+        Ok(())
     }
 
-    pub(crate) fn next_windows_boot_profile(&self) -> Option<ProfileId> {
-        self.options.operating_system.windows.profile
+    pub(crate) fn next_windows_boot_profile(&self) -> Option<&ProfileId> {
+        self.options.operating_system.windows.profile.as_ref()
     }
 
-    pub(crate) fn set_next_windows_boot_profile(&mut self, profile_id: Option<ProfileId>) {
+    pub(crate) fn set_next_windows_boot_profile(
+        &mut self,
+        profile_id: Option<ProfileId>,
+    ) -> Result<()> {
         self.options.operating_system.windows.profile = profile_id;
-        self.options.save().unwrap();
+        self.save_options()
+    }
+
+    /// Records the action scheduled with [`host_os::schedule`], as Windows doesn't tell which
+    /// action is pending.
+    #[cfg(windows)]
+    pub(crate) fn set_pending_action(&mut self, pending_action: PendingAction) -> Result<()> {
+        self.options.operating_system.windows.pending_action = Some(pending_action);
+        self.save_options()
+    }
+
+    fn save_options(&self) -> Result<()> {
+        if self.dry_run {
+            Ok(())
+        } else {
+            self.options.save()
+        }
     }
 
     #[cfg(windows)]
@@ -78,7 +197,7 @@ impl StateProvider {
     }
 
     #[cfg(windows)]
-    pub(crate) fn set_current_profile(&self, profile_id: ProfileId) -> Result<()> {
+    pub(crate) fn set_current_profile(&self, profile_id: &ProfileId) -> Result<()> {
         self.current_profile_handler().switch_to(profile_id)
     }
 
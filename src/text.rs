@@ -1,53 +1,193 @@
 use std::fmt::{Display, Write};
 
 use ansi_term::ANSIString;
-use ansi_term::Color::{Blue, Green, Red};
+use ansi_term::Color::{self, Blue, Cyan, Green, Purple, Red, Yellow};
+use serde::{Deserialize, Serialize};
+
+/// The languages of the user-facing text.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub(crate) enum Locale {
+    #[serde(rename = "pt-BR", alias = "pt_BR", alias = "pt")]
+    PtBr,
+    #[serde(rename = "en", alias = "en_US", alias = "en-US")]
+    En,
+}
+impl Locale {
+    /// Chosen by the `locale` key in the configs file, then by the environment variables, in the
+    /// same precedence as POSIX. Defaults to Brazilian Portuguese.
+    ///
+    /// The configs file is only read if the state directory is already resolved, since resolving it
+    /// may need localized texts.
+    #[cfg(not(test))]
+    fn detect() -> Self {
+        crate::host_os::resolved_state_dir()
+            .and_then(|state_dir| {
+                crate::persist::configs::Configs::configured_locale(&state_dir.path)
+            })
+            .or_else(Self::from_env)
+            .unwrap_or(Locale::PtBr)
+    }
+
+    #[cfg(not(test))]
+    fn from_env() -> Option<Self> {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Self::from_posix(&value))
+    }
+
+    /// Parses values like `pt_BR.UTF-8`, `en_US` or `en`.
+    fn from_posix(value: &str) -> Option<Self> {
+        let language = value.split(['_', '-', '.', '@']).next()?;
+        match language.to_ascii_lowercase().as_str() {
+            "pt" => Some(Locale::PtBr),
+            "en" => Some(Locale::En),
+            _ => None,
+        }
+    }
+}
 
-use crate::options_types::Values;
+cfg_select! {
+    test => {
+        thread_local! {
+            static LOCALE: std::cell::Cell<Locale> = const { std::cell::Cell::new(Locale::PtBr) };
+        }
+
+        pub(crate) fn locale() -> Locale {
+            LOCALE.get()
+        }
+
+        /// Tests run in Brazilian Portuguese unless they change the locale of their thread.
+        pub(crate) fn set_locale(locale: Locale) {
+            LOCALE.set(locale);
+        }
+    },
+    _ => {
+        static LOCALE: std::sync::OnceLock<Locale> = std::sync::OnceLock::new();
+
+        pub(crate) fn locale() -> Locale {
+            *LOCALE.get_or_init(Locale::detect)
+        }
+    },
+}
 
-pub mod operating_system {
+/// A text in all the supported locales, displayed in the current one.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct Localized {
+    pt_br: &'static str,
+    en: &'static str,
+}
+impl Localized {
+    pub(crate) const fn new(pt_br: &'static str, en: &'static str) -> Self {
+        Self { pt_br, en }
+    }
+
+    pub(crate) fn get(self) -> &'static str {
+        match locale() {
+            Locale::PtBr => self.pt_br,
+            Locale::En => self.en,
+        }
+    }
+}
+impl Display for Localized {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.get())
+    }
+}
+
+/// Formats a text in the current locale. The format strings of all locales can capture the same
+/// variables.
+macro_rules! localized {
+    (pt_br: $pt_br:literal, en: $en:literal $(, $arg:expr)* $(,)?) => {
+        match $crate::text::locale() {
+            $crate::text::Locale::PtBr => format!($pt_br $(, $arg)*),
+            $crate::text::Locale::En => format!($en $(, $arg)*),
+        }
+    };
+}
+pub(crate) use localized;
+
+pub(crate) mod operating_system {
     use ansi_term::ANSIString;
+    use ansi_term::Color::Red;
+
+    use super::Localized;
+    use crate::options_types::LabeledOperatingSystem;
 
-    use crate::options_types::OperatingSystem;
+    pub(crate) const ON_NEXT_BOOT_DESCRIPTION: Localized = Localized::new(
+        "sistema operacional a ser iniciado na próxima inicialização do computador",
+        "operating system to be started on the next boot of the computer",
+    );
 
-    pub const ON_NEXT_BOOT_DESCRIPTION: &str =
-        "sistema operacional a ser iniciado na próxima inicialização do computador";
+    pub(crate) const ONE_SHOT_DESCRIPTION: Localized = Localized::new(
+        "sistema operacional a ser iniciado somente na próxima inicialização do computador",
+        "operating system to be started only on the next boot of the computer",
+    );
 
-    pub const WAS_UPDATED_TO: &str = "foi atualizado para";
+    pub(crate) const AFTER_ONE_SHOT_DESCRIPTION: Localized = Localized::new(
+        "sistema operacional a ser iniciado nas inicializações seguintes",
+        "operating system to be started on the following boots",
+    );
 
-    pub const UNDEFINED: &str = "indefinido";
+    pub(crate) const ONCE: Localized = Localized::new("somente uma vez", "only once");
 
-    pub fn value_text(os: Option<OperatingSystem>) -> ANSIString<'static> {
-        super::two_values_option_value_text(os, UNDEFINED)
+    pub(crate) const WAS_UPDATED_TO: Localized =
+        Localized::new("foi atualizado para", "was updated to");
+
+    pub(crate) const UNDEFINED: Localized = Localized::new("indefinido", "undefined");
+
+    pub(crate) fn value_text(labeled_os: Option<LabeledOperatingSystem>) -> ANSIString<'static> {
+        let labeled_os = labeled_os.map(|los| (los.index(), los.to_string()));
+        super::indexed_value_text(labeled_os, UNDEFINED)
+    }
+
+    /// For a GRUB entry that doesn't belong to any configured operating system.
+    pub(crate) fn unknown_entry_value_text(grub_entry: &str) -> ANSIString<'static> {
+        Red.bold().paint(localized!(
+            pt_br: "entrada desconhecida: {grub_entry}",
+            en: "unknown entry: {grub_entry}",
+        ))
     }
 }
 
 pub(crate) mod profile {
     use ansi_term::ANSIString;
 
+    use super::Localized;
     use crate::options_types::LabeledProfile;
 
-    pub(crate) const ON_NEXT_WINDOWS_BOOT_DESCRIPTION: &str =
-        "perfil a ser usado na próxima inicialização do Windows";
+    pub(crate) const ON_NEXT_WINDOWS_BOOT_DESCRIPTION: Localized = Localized::new(
+        "perfil a ser usado na próxima inicialização do Windows",
+        "profile to be used on the next boot of Windows",
+    );
 
-    #[cfg(any(windows, test))]
-    pub(crate) const SWITCH_DESCRIPTION: &str = "troca de perfil";
+    pub(crate) const SWITCH_DESCRIPTION: Localized =
+        Localized::new("troca de perfil", "profile switch");
 
-    pub(crate) const WAS_UPDATED_TO: &str = "foi atualizado para";
+    pub(crate) const WAS_UPDATED_TO: Localized =
+        Localized::new("foi atualizado para", "was updated to");
 
     #[cfg(windows)]
-    pub(crate) const CURRENT: &str = "perfil atual";
+    pub(crate) const CURRENT: Localized = Localized::new("perfil atual", "current profile");
 
-    pub(crate) const UNDEFINED: &str = "indefinido";
+    pub(crate) const UNDEFINED: Localized = Localized::new("indefinido", "undefined");
 
     #[cfg(windows)]
-    const UNRECOGNIZED: &str = "não reconhecido";
+    const UNRECOGNIZED: Localized = Localized::new("não reconhecido", "unrecognized");
 
     #[cfg(windows)]
     pub(crate) mod switching {
-        pub(crate) const TO: &str = "Trocando de perfil para";
-        pub(crate) const TAKING_TOO_LONG: &str = "O perfil não trocou no tempo limite";
-        pub(crate) const IS_ALREADY_CURRENT: &str = "já é o perfil atual";
+        use crate::text::Localized;
+
+        pub(crate) const TO: Localized =
+            Localized::new("Trocando de perfil para", "Switching profile to");
+        pub(crate) const TAKING_TOO_LONG: Localized = Localized::new(
+            "O perfil não trocou no tempo limite",
+            "The profile didn't switch within the time limit",
+        );
+        pub(crate) const IS_ALREADY_CURRENT: Localized =
+            Localized::new("já é o perfil atual", "is already the current profile");
     }
 
     pub(crate) fn next_boot_value_text(
@@ -65,43 +205,87 @@ pub(crate) mod profile {
 
     fn value_text(
         labeled_profile: Option<LabeledProfile>,
-        undefined_text: &str,
+        undefined_text: Localized,
     ) -> ANSIString<'static> {
-        let profile_label = labeled_profile.map(|lp| (lp.profile_id(), lp.to_string()));
-        super::two_values_text(profile_label, undefined_text)
+        let profile_label = labeled_profile.map(|lp| (lp.index(), lp.to_string()));
+        super::indexed_value_text(profile_label, undefined_text)
     }
 }
 
-pub mod reboot_action {
-    pub(crate) const ACTION_DESCRIPTION: &str = "ação";
-    pub(crate) const UNDEFINED: &str = "indefinida";
-    pub const FAILED: &str = "A ação de reinicialização falhou";
-}
+pub(crate) mod reboot_action {
+    use chrono::{DateTime, Local, TimeDelta};
+
+    use super::Localized;
+    use crate::host_os::PendingAction;
+    use crate::options_types::RebootAction;
+
+    pub(crate) const ACTION_DESCRIPTION: Localized = Localized::new("ação", "action");
+    pub(crate) const UNDEFINED: Localized = Localized::new("indefinida", "undefined");
+    pub(crate) const SCHEDULED_DESCRIPTION: Localized =
+        Localized::new("ação agendada", "scheduled action");
+    pub(crate) const IMMEDIATELY: Localized = Localized::new("imediatamente", "immediately");
+    pub(crate) const FAILED: Localized = Localized::new(
+        "A ação de reinicialização falhou",
+        "The reboot action failed",
+    );
+    pub(crate) const CANCEL_FAILED: Localized = Localized::new(
+        "O cancelamento da ação agendada falhou",
+        "Canceling the scheduled action failed",
+    );
+    pub(crate) const NONE_SCHEDULED: Localized =
+        Localized::new("Nenhuma ação agendada", "No scheduled action");
+    pub(crate) const CANCELED: Localized =
+        Localized::new("Ação agendada cancelada", "Scheduled action canceled");
+
+    pub(crate) fn cannot_be_scheduled(action: RebootAction) -> String {
+        localized!(
+            pt_br: "A ação '{action}' não pode ser agendada",
+            en: "The action '{action}' can't be scheduled",
+        )
+    }
 
-fn two_values_option_value_text<T: Values + PartialEq + ToString>(
-    current_value: Option<T>,
-    undefined_text: &str,
-) -> ANSIString<'static> {
-    let current_value = current_value.map(|value| (value, value.to_string()));
-    two_values_text(current_value, undefined_text)
+    #[cfg(windows)]
+    pub(crate) fn unsupported(action: RebootAction) -> String {
+        localized!(
+            pt_br: "A ação '{action}' não é suportada neste sistema operacional",
+            en: "The action '{action}' isn't supported on this operating system",
+        )
+    }
+
+    /// Like "reiniciar às 23:30 (em 1h 05min)".
+    pub(crate) fn pending_text(pending_action: &PendingAction, now: DateTime<Local>) -> String {
+        let action = pending_action.action;
+        let time = pending_action.at.format("%H:%M");
+        let remaining = remaining_text(pending_action.at - now);
+        localized!(
+            pt_br: "{action} às {time} (em {remaining})",
+            en: "{action} at {time} (in {remaining})",
+        )
+    }
+
+    /// The remaining time of a countdown, like "1h 05min", "5min 03s" or "42s".
+    pub(crate) fn remaining_text(remaining: TimeDelta) -> String {
+        let seconds = remaining.num_seconds().max(0);
+        let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+        if hours > 0 {
+            format!("{hours}h {minutes:02}min")
+        } else if minutes > 0 {
+            format!("{minutes}min {seconds:02}s")
+        } else {
+            format!("{seconds}s")
+        }
+    }
 }
 
-fn two_values_text<T: Values + PartialEq>(
-    current_value: Option<(T, String)>,
-    undefined_text: &str,
+/// Paints the text of a value with a color chosen by its position among the possible values.
+fn indexed_value_text(
+    current_value: Option<(usize, String)>,
+    undefined_text: Localized,
 ) -> ANSIString<'static> {
+    const COLORS: [Color; 5] = [Blue, Green, Purple, Cyan, Yellow];
+
     let (color, text) = match current_value {
-        Some((current_value, text)) => {
-            let [value1, value2] = T::values();
-            let color = if current_value == value1 {
-                Blue
-            } else if current_value == value2 {
-                Green
-            } else {
-                unimplemented!()
-            };
-            (color, text)
-        }
+        Some((index, text)) => (COLORS[index % COLORS.len()], text),
         None => (Red, undefined_text.to_string()),
     };
     color.bold().paint(text)
@@ -159,6 +343,60 @@ impl<T: Display> Display for Capitalized<T> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn remaining_text() {
+        use chrono::TimeDelta;
+
+        let cases = [
+            (TimeDelta::seconds(3_900), "1h 05min"),
+            (TimeDelta::seconds(303), "5min 03s"),
+            (TimeDelta::seconds(42), "42s"),
+            (TimeDelta::seconds(-5), "0s"),
+        ];
+
+        for (remaining, expected) in cases {
+            assert_eq!(reboot_action::remaining_text(remaining), expected);
+        }
+    }
+
+    #[test]
+    fn locale_from_posix() {
+        assert_eq!(Locale::from_posix("pt_BR.UTF-8"), Some(Locale::PtBr));
+        assert_eq!(Locale::from_posix("pt"), Some(Locale::PtBr));
+        assert_eq!(Locale::from_posix("en_US.UTF-8"), Some(Locale::En));
+        assert_eq!(Locale::from_posix("en"), Some(Locale::En));
+        assert_eq!(Locale::from_posix("C"), None);
+        assert_eq!(Locale::from_posix(""), None);
+    }
+
+    #[test]
+    fn localized_follows_locale() {
+        let value = 42;
+
+        assert_eq!(reboot_action::UNDEFINED.to_string(), "indefinida");
+        assert_eq!(
+            localized!(pt_br: "valor {value}", en: "value {value}"),
+            "valor 42"
+        );
+
+        set_locale(Locale::En);
+        assert_eq!(reboot_action::UNDEFINED.to_string(), "undefined");
+        assert_eq!(
+            localized!(pt_br: "valor {value}", en: "value {value}"),
+            "value 42"
+        );
+    }
+
+    #[test]
+    fn capitalized_localized() {
+        set_locale(Locale::En);
+
+        assert_eq!(
+            Capitalized(operating_system::UNDEFINED).to_string(),
+            "Undefined"
+        );
+    }
+
     #[test]
     fn capitalized_write_str_empty_write_str() {
         let display = std::fmt::from_fn(|f| {
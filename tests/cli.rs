@@ -0,0 +1,264 @@
+//! Runs the executable end to end on a temporary state directory.
+#![cfg(not(windows))]
+
+mod harness;
+
+use harness::{CONFIGS_FILENAME, Harness, OPTIONS_FILENAME, fixture_path};
+
+const WINDOWS_GRUB_ENTRY: &str = "osprober-efi-1A2B-3C4D";
+const LINUX_GRUB_ENTRY: &str = "gnulinux-simple-9a8b7c6d";
+
+const CONFIGS: &str = r#"
+version = 2
+
+[operating_system.windows]
+grub_entry = "osprober-efi-1A2B-3C4D"
+
+[operating_system.linux]
+grub_entry = "gnulinux-simple-9a8b7c6d"
+
+[[operating_system.linux.scripts]]
+label_template = "{reboot_action} into {next_boot_operating_system}"
+next_boot_operating_system = "windows"
+next_windows_boot_profile = "tv"
+reboot_action = "reboot"
+
+[profile.tv]
+label = "TV"
+display_configs = "tv-display-configs"
+
+[profile.monitor]
+label = "Monitor"
+display_configs = "monitor-display-configs"
+"#;
+
+fn entry(key: &str, value: &str) -> (String, String) {
+    (key.to_string(), value.to_string())
+}
+
+#[test]
+fn show() {
+    let harness = Harness::with_configs(CONFIGS, &[("saved_entry", LINUX_GRUB_ENTRY)]);
+    harness.write_state_file(
+        OPTIONS_FILENAME,
+        "[operating_system.windows]\nprofile = \"monitor\"\n",
+    );
+
+    let output = harness.run(&["show", "--format", "json"]);
+
+    assert!(output.success, "{output:?}");
+    let state: serde_json::Value = serde_json::from_str(&output.stdout).unwrap();
+    assert_eq!(state["next_boot_operating_system"]["id"], "linux");
+    assert_eq!(state["saved_entry"], LINUX_GRUB_ENTRY);
+    assert_eq!(state["next_windows_boot_profile"]["id"], "monitor");
+    assert_eq!(
+        state["state_dir"]["path"],
+        harness.state_dir().display().to_string()
+    );
+    assert_eq!(state["state_dir"]["source"], "environment_variable");
+}
+
+#[test]
+fn predefined_script() {
+    let harness = Harness::with_configs(CONFIGS, &[("saved_entry", LINUX_GRUB_ENTRY)]);
+
+    let output = harness.run(&["script", "1"]);
+
+    assert!(output.success, "{output:?}");
+    assert_eq!(
+        harness.grubenv_entries(),
+        [entry("saved_entry", WINDOWS_GRUB_ENTRY)]
+    );
+    assert_eq!(
+        harness.read_state_file(OPTIONS_FILENAME),
+        "[operating_system.windows]\nprofile = \"tv\"\n"
+    );
+    assert_eq!(harness.stubbed_calls(), ["systemctl reboot"]);
+    assert_eq!(harness.backups_count(), 1);
+}
+
+#[test]
+fn script_edit_of_a_configured_os() {
+    let configs = format!("{CONFIGS}\n[operating_system.fedora]\ngrub_entry = \"fedora\"\n");
+    let harness = Harness::with_configs(&configs, &[]);
+
+    let output = harness.run(&["script", "add", "--fedora", "--label", "Off", "shutdown"]);
+
+    assert!(output.success, "{output:?}");
+    assert!(
+        harness
+            .read_state_file(CONFIGS_FILENAME)
+            .ends_with("[[operating_system.fedora.scripts]]\nreboot_action = \"shutdown\"\nlabel_template = \"Off\"\n"),
+    );
+
+    let output = harness.run(&["script", "remove", "--solaris", "1"]);
+
+    assert!(!output.success, "{output:?}");
+    assert!(output.stderr.contains("--solaris"), "{output:?}");
+}
+
+#[test]
+fn ad_hoc_script() {
+    let harness = Harness::with_configs(CONFIGS, &[("saved_entry", WINDOWS_GRUB_ENTRY)]);
+
+    let output = harness.run(&["os:linux:once", "shutdown"]);
+
+    assert!(output.success, "{output:?}");
+    assert_eq!(
+        harness.grubenv_entries(),
+        [
+            entry("saved_entry", WINDOWS_GRUB_ENTRY),
+            entry("next_entry", LINUX_GRUB_ENTRY),
+        ]
+    );
+    assert_eq!(harness.stubbed_calls(), ["systemctl poweroff"]);
+}
+
+#[test]
+fn ad_hoc_script_confirmed() {
+    let harness = Harness::with_configs(CONFIGS, &[("saved_entry", LINUX_GRUB_ENTRY)]);
+
+    let output = harness.run_with_stdin(&["os:windows", "reboot", "--confirm"], "y\n");
+
+    assert!(output.success, "{output:?}");
+    assert_eq!(
+        harness.grubenv_entries(),
+        [entry("saved_entry", WINDOWS_GRUB_ENTRY)]
+    );
+    assert_eq!(harness.stubbed_calls(), ["systemctl reboot"]);
+}
+
+#[test]
+fn ad_hoc_script_canceled() {
+    let harness = Harness::with_configs(CONFIGS, &[("saved_entry", WINDOWS_GRUB_ENTRY)]);
+    let grubenv = harness.read_state_file(harness::GRUBENV_FILENAME);
+
+    let output = harness.run_with_stdin(&["os:linux:once", "reboot@+10m", "--confirm"], "n\n");
+
+    assert!(output.success, "{output:?}");
+    assert!(output.stdout.contains("Canceled."), "{output:?}");
+    assert_eq!(harness.read_state_file(harness::GRUBENV_FILENAME), grubenv);
+    assert!(harness.stubbed_calls().is_empty());
+    assert_eq!(harness.backups_count(), 0);
+}
+
+#[test]
+fn dry_run_changes_nothing() {
+    let harness = Harness::with_configs(CONFIGS, &[("saved_entry", LINUX_GRUB_ENTRY)]);
+    let grubenv = harness.read_state_file(harness::GRUBENV_FILENAME);
+
+    let output = harness.run(&["--dry-run", "os:windows", "profile:tv", "reboot"]);
+
+    assert!(output.success, "{output:?}");
+    assert_eq!(harness.read_state_file(harness::GRUBENV_FILENAME), grubenv);
+    assert!(!harness.state_path(OPTIONS_FILENAME).exists());
+    assert!(harness.stubbed_calls().is_empty());
+    assert_eq!(harness.backups_count(), 0);
+}
+
+#[test]
+fn dry_run_with_schedule() {
+    let harness = Harness::with_configs(CONFIGS, &[("saved_entry", LINUX_GRUB_ENTRY)]);
+
+    let output = harness.run(&["--dry-run", "os:windows", "reboot@+10m"]);
+
+    assert!(output.success, "{output:?}");
+    assert!(!output.stdout.contains("Scheduled"), "{output:?}");
+    assert!(harness.stubbed_calls().is_empty());
+}
+
+#[test]
+fn configure_with_scripted_input() {
+    let harness = Harness::with_configs(CONFIGS, &[("saved_entry", LINUX_GRUB_ENTRY)]);
+    let grub_cfg = fixture_path("grub/multi-distro.cfg");
+
+    // ENTER to redo the configuration, and then the second entry is chosen for Linux, which
+    // matches more than one.
+    let output = harness.run_with_stdin(
+        &["configure", "--grub-cfg", grub_cfg.to_str().unwrap()],
+        "\n2\n",
+    );
+
+    assert!(output.success, "{output:?}");
+    let configs: toml::Table = toml::from_str(&harness.read_state_file(CONFIGS_FILENAME)).unwrap();
+    let grub_entry = |os: &str| {
+        configs["operating_system"][os]["grub_entry"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    };
+    assert_eq!(grub_entry("windows"), "osprober-efi-4444");
+    assert_eq!(grub_entry("linux"), "osprober-gnulinux-simple-2222");
+    // The rest of the configs is kept.
+    assert_eq!(configs["profile"]["tv"]["label"].as_str(), Some("TV"));
+    assert_eq!(harness.backups_count(), 1);
+    assert!(harness.stubbed_calls().is_empty());
+}
+
+#[test]
+fn configure_fails_without_input() {
+    let harness = Harness::with_configs(CONFIGS, &[("saved_entry", LINUX_GRUB_ENTRY)]);
+    let grub_cfg = fixture_path("grub/multi-distro.cfg");
+
+    let output = harness.run(&[
+        "configure",
+        "--yes",
+        "--grub-cfg",
+        grub_cfg.to_str().unwrap(),
+    ]);
+
+    assert!(!output.success);
+    assert!(
+        output
+            .stderr
+            .contains("More than one entry found for Linux"),
+        "{output:?}"
+    );
+    assert!(
+        harness
+            .read_state_file(CONFIGS_FILENAME)
+            .contains(LINUX_GRUB_ENTRY)
+    );
+}
+
+#[test]
+fn invalid_user_config() {
+    let harness = Harness::with_configs(CONFIGS, &[("saved_entry", LINUX_GRUB_ENTRY)]);
+    harness.write_user_config("state_dir = 1\n");
+
+    let output = harness.run_without_state_dir_env(&["show"]);
+
+    assert!(!output.success);
+    assert!(
+        output.stderr.contains("Invalid user config file"),
+        "{output:?}"
+    );
+}
+
+#[test]
+fn validate_with_unreadable_grub_cfg() {
+    let configs = CONFIGS.replace("{next_boot_operating_system}", "{os}");
+    let harness = Harness::with_configs(&configs, &[]);
+    let grub_cfg = harness.state_path("grub.cfg");
+
+    let output = harness.run(&[
+        "config",
+        "validate",
+        "--grub-cfg",
+        grub_cfg.to_str().unwrap(),
+    ]);
+
+    assert!(!output.success, "{output:?}");
+    let diagnostics: Vec<_> = output.stdout.lines().collect();
+    assert_eq!(diagnostics.len(), 2, "{output:?}");
+    assert!(
+        diagnostics[0].starts_with(&format!("{}: ", grub_cfg.display())),
+        "{output:?}"
+    );
+    assert!(
+        diagnostics[1].starts_with(&format!(
+            "{CONFIGS_FILENAME}:11: operating_system.linux.scripts[0].label_template: "
+        )),
+        "{output:?}"
+    );
+}
@@ -0,0 +1,215 @@
+//! Runs the `my-reboot` executable on a temporary state directory, with the commands of the power
+//! actions replaced by stubs that only record how they were called.
+
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::Duration;
+use std::{fs, thread};
+
+use tempfile::TempDir;
+
+pub const GRUBENV_FILENAME: &str = "grubenv";
+pub const CONFIGS_FILENAME: &str = "my-reboot-configs.toml";
+pub const OPTIONS_FILENAME: &str = "my-reboot-options.toml";
+pub const BACKUPS_DIRNAME: &str = "my-reboot-backups";
+
+const GRUBENV_HEADER_LINE: &str = "# GRUB Environment Block\n";
+const GRUBENV_CONTENT_LENGTH: usize = 1024;
+/// The commands run by the power actions on Linux.
+const STUBBED_COMMANDS: [&str; 2] = ["systemctl", "shutdown"];
+const STUB_LOG_FILENAME: &str = "stub.log";
+const USER_CONFIG_PATH: &str = "my-reboot/config.toml";
+/// After which the executable is considered hung.
+const RUN_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct Harness {
+    dir: TempDir,
+}
+
+/// What the executable printed, and whether it succeeded.
+#[derive(Debug)]
+pub struct Output {
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl Harness {
+    pub fn new() -> Harness {
+        let harness = Harness {
+            dir: tempfile::tempdir().unwrap(),
+        };
+        for dir in [harness.state_dir(), harness.bin_dir(), harness.config_dir()] {
+            fs::create_dir(dir).unwrap();
+        }
+
+        let stub_log = harness.dir.path().join(STUB_LOG_FILENAME);
+        for command in STUBBED_COMMANDS {
+            let path = harness.bin_dir().join(command);
+            let script = format!(
+                "#!/bin/sh\necho \"{command} $*\" >> '{}'\n",
+                stub_log.display()
+            );
+            fs::write(&path, script).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        harness
+    }
+
+    /// Also writes a `grubenv` with the given entries, like after GRUB's `save_env`.
+    pub fn with_configs(configs: &str, grubenv_entries: &[(&str, &str)]) -> Harness {
+        let harness = Harness::new();
+        harness.write_state_file(CONFIGS_FILENAME, configs);
+        harness.write_grubenv(grubenv_entries);
+        harness
+    }
+
+    pub fn state_dir(&self) -> PathBuf {
+        self.dir.path().join("state")
+    }
+
+    fn bin_dir(&self) -> PathBuf {
+        self.dir.path().join("bin")
+    }
+
+    /// The per-user config directory, so that the one of the user running the tests isn't used.
+    fn config_dir(&self) -> PathBuf {
+        self.dir.path().join("config")
+    }
+
+    pub fn state_path(&self, filename: &str) -> PathBuf {
+        self.state_dir().join(filename)
+    }
+
+    pub fn write_state_file(&self, filename: &str, content: &str) {
+        fs::write(self.state_path(filename), content).unwrap();
+    }
+
+    pub fn read_state_file(&self, filename: &str) -> String {
+        fs::read_to_string(self.state_path(filename)).unwrap()
+    }
+
+    pub fn write_grubenv(&self, entries: &[(&str, &str)]) {
+        let mut content = GRUBENV_HEADER_LINE.to_string();
+        for (key, value) in entries {
+            content.push_str(key);
+            content.push('=');
+            content.push_str(value);
+            content.push('\n');
+        }
+        content.push_str(&"#".repeat(GRUBENV_CONTENT_LENGTH - content.len()));
+        self.write_state_file(GRUBENV_FILENAME, &content);
+    }
+
+    /// The entries of the `grubenv`, which must still have its fixed size.
+    pub fn grubenv_entries(&self) -> Vec<(String, String)> {
+        let content = self.read_state_file(GRUBENV_FILENAME);
+        assert_eq!(content.len(), GRUBENV_CONTENT_LENGTH);
+        assert!(content.starts_with(GRUBENV_HEADER_LINE));
+        content
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| {
+                let (key, value) = line.split_once('=').unwrap();
+                (key.to_string(), value.to_string())
+            })
+            .collect()
+    }
+
+    /// The number of backups made of the state files.
+    pub fn backups_count(&self) -> usize {
+        match fs::read_dir(self.state_path(BACKUPS_DIRNAME)) {
+            Ok(entries) => entries.count(),
+            Err(_) => 0,
+        }
+    }
+
+    /// The command lines the stubs were called with, in order.
+    pub fn stubbed_calls(&self) -> Vec<String> {
+        match fs::read_to_string(self.dir.path().join(STUB_LOG_FILENAME)) {
+            Ok(log) => log.lines().map(str::to_string).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Writes the per-user config file.
+    pub fn write_user_config(&self, content: &str) {
+        let path = self.config_dir().join(USER_CONFIG_PATH);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    pub fn run(&self, args: &[&str]) -> Output {
+        self.run_with_stdin(args, "")
+    }
+
+    /// Runs the executable with the lines to be typed as its input.
+    pub fn run_with_stdin(&self, args: &[&str], stdin: &str) -> Output {
+        let mut command = self.command(args);
+        command.env("MY_REBOOT_STATE_DIR", self.state_dir());
+        output(command, stdin)
+    }
+
+    /// Runs the executable without the state directory in the environment, so that it's resolved
+    /// from the per-user config file.
+    pub fn run_without_state_dir_env(&self, args: &[&str]) -> Output {
+        let mut command = self.command(args);
+        command.env_remove("MY_REBOOT_STATE_DIR");
+        output(command, "")
+    }
+
+    fn command(&self, args: &[&str]) -> Command {
+        let path = std::env::var_os("PATH").unwrap_or_default();
+        let mut paths = vec![self.bin_dir()];
+        paths.extend(std::env::split_paths(&path));
+
+        let mut command = Command::new(env!("CARGO_BIN_EXE_my-reboot"));
+        command
+            .args(args)
+            .env("PATH", std::env::join_paths(paths).unwrap())
+            .env("XDG_CONFIG_HOME", self.config_dir())
+            .env("LC_ALL", "en_US.UTF-8")
+            .env("RUST_BACKTRACE", "0")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        command
+    }
+}
+
+/// Fails if the executable doesn't finish in time, instead of hanging the tests.
+fn output(mut command: Command, stdin: &str) -> Output {
+    let mut child = command.spawn().unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+
+    let pid = child.id();
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || sender.send(child.wait_with_output()));
+    let Ok(output) = receiver.recv_timeout(RUN_TIMEOUT) else {
+        let _ = Command::new("kill").arg("-9").arg(pid.to_string()).status();
+        panic!("The executable didn't finish in {RUN_TIMEOUT:?}");
+    };
+    let output = output.unwrap();
+
+    Output {
+        success: output.status.success(),
+        stdout: String::from_utf8(output.stdout).unwrap(),
+        stderr: String::from_utf8(output.stderr).unwrap(),
+    }
+}
+
+/// A file of `tests/fixtures`.
+pub fn fixture_path(path: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(path)
+}